@@ -30,7 +30,7 @@ mod tests {
         )); //  create testfile and 0 padding for 400 bytes
         let mut bm = BufferMgr::new(fm.clone(), log_mgr.clone(), 3); // 3 buffers
 
-        let buffer1 = bm.pin(BlockId::new("testfile".to_string(), 1)); // pin block 1
+        let buffer1 = bm.pin(BlockId::new("testfile".to_string(), 1)).unwrap(); // pin block 1
         {
             let mut buffer1_ = buffer1.lock().unwrap();
             let p = buffer1_.contents(); // get page asoociated with buffer
@@ -39,12 +39,12 @@ mod tests {
             buffer1_.set_modified(1, 0); // mark buffer as modified
         }
         bm.unpin(buffer1); // unpin buffer
-        let buffer2 = bm.pin(BlockId::new("testfile".to_string(), 2)); // flush buffer1 to disk
-        let _ = bm.pin(BlockId::new("testfile".to_string(), 3));
-        let _ = bm.pin(BlockId::new("testfile".to_string(), 4));
+        let buffer2 = bm.pin(BlockId::new("testfile".to_string(), 2)).unwrap(); // flush buffer1 to disk
+        let _ = bm.pin(BlockId::new("testfile".to_string(), 3)).unwrap();
+        let _ = bm.pin(BlockId::new("testfile".to_string(), 4)).unwrap();
 
         bm.unpin(buffer2); // None get dirty buffer2, so it does not need to be flushed
-        let buffer2 = bm.pin(BlockId::new("testfile".to_string(), 1));
+        let buffer2 = bm.pin(BlockId::new("testfile".to_string(), 1)).unwrap();
         {
             let mut buffer2_ = buffer2.lock().unwrap();
             let p2 = buffer2_.contents();
@@ -81,18 +81,18 @@ mod tests {
         let mut bm = BufferMgr::new(fm.clone(), log_mgr.clone(), 6); // 3 buffers
         assert_eq!(bm.available(), 6);
         let mut buff = vec![None; 6];
-        buff[0] = Some(bm.pin(BlockId::new("testfile".to_string(), 0)));
-        buff[1] = Some(bm.pin(BlockId::new("testfile".to_string(), 1)));
-        buff[2] = Some(bm.pin(BlockId::new("testfile".to_string(), 2)));
+        buff[0] = Some(bm.pin(BlockId::new("testfile".to_string(), 0)).unwrap());
+        buff[1] = Some(bm.pin(BlockId::new("testfile".to_string(), 1)).unwrap());
+        buff[2] = Some(bm.pin(BlockId::new("testfile".to_string(), 2)).unwrap());
         bm.unpin(buff[1].clone().unwrap());
         assert_eq!(bm.available(), 4);
         buff[1] = None;
-        buff[3] = Some(bm.pin(BlockId::new("testfile".to_string(), 0)));
-        buff[4] = Some(bm.pin(BlockId::new("testfile".to_string(), 1)));
+        buff[3] = Some(bm.pin(BlockId::new("testfile".to_string(), 0)).unwrap());
+        buff[4] = Some(bm.pin(BlockId::new("testfile".to_string(), 1)).unwrap());
         assert_eq!(bm.available(), 3);
         bm.unpin(buff[2].clone().unwrap());
         buff[2] = None;
-        buff[5] = Some(bm.pin(BlockId::new("testfile".to_string(), 3)));
+        buff[5] = Some(bm.pin(BlockId::new("testfile".to_string(), 3)).unwrap());
 
         assert_eq!(
             buff[0].clone().unwrap().lock().unwrap().block().unwrap(),
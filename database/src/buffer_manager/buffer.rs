@@ -3,6 +3,7 @@ use std::sync::{Arc, Mutex};
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::file_mgr::FileMgr;
 use crate::file_manager::page::Page;
+use crate::file_manager::FileManagerError;
 use crate::log_manager::log_mgr::LogMgr;
 
 #[derive(Clone, Debug)]
@@ -55,13 +56,12 @@ impl Buffer {
         self.txnum
     }
 
-    pub fn assign_to_block(&mut self, b: BlockId) {
+    pub fn assign_to_block(&mut self, b: BlockId) -> Result<(), FileManagerError> {
         self.flush();
-        self.fm
-            .read(&b, &mut self.contents)
-            .expect("assign_to_block: read error");
+        self.fm.read(&b, &mut self.contents)?;
         self.blk = Some(b);
         self.pins = 0;
+        Ok(())
     }
 
     pub fn flush(&mut self) {
@@ -1,6 +1,8 @@
 use crate::buffer_manager::buffer::Buffer;
+use crate::error::{DbError, DbResult};
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::file_mgr::FileMgr;
+use crate::file_manager::FileManagerError;
 use crate::log_manager::log_mgr::LogMgr;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -47,6 +49,13 @@ impl BufferMgr {
         }
     }
 
+    /// flush the modified pages of every transaction
+    pub fn flush_all_txs(&self) {
+        for buffer in self.buffer_pool.iter() {
+            buffer.lock().unwrap().flush();
+        }
+    }
+
     /// unpin the page
     // to do: convert synchronize method
     pub fn unpin(&mut self, buff: Arc<Mutex<Buffer>>) {
@@ -59,22 +68,23 @@ impl BufferMgr {
     }
 
     /// returns a buffer object pinned to a page containing the blk
+    /// waiting too long for an unpinned buffer is a buffer abort
     /// @param blk block id
     /// @return buffer object
     // to do: convert synchronize method
-    pub fn pin(&mut self, blk: BlockId) -> Arc<Mutex<Buffer>> {
+    pub fn pin(&mut self, blk: BlockId) -> DbResult<Arc<Mutex<Buffer>>> {
         let timestamp = time::SystemTime::now()
             .duration_since(time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as i64;
-        let mut buff = self.try_to_pin(blk.clone());
+        let mut buff = self.try_to_pin(blk.clone())?;
         while buff.is_none() && !self.wait_too_long(timestamp) {
             thread::sleep(time::Duration::from_millis(10));
-            buff = self.try_to_pin(blk.clone());
+            buff = self.try_to_pin(blk.clone())?;
         }
         match buff {
-            Some(b) => b,
-            None => panic!("buffer pool has no available buffers"),
+            Some(b) => Ok(b),
+            None => Err(DbError::BufferAbort(blk)),
         }
     }
 
@@ -86,7 +96,7 @@ impl BufferMgr {
         now - starttime > constants::MAX_TIME
     }
 
-    fn try_to_pin(&mut self, blk: BlockId) -> Option<Arc<Mutex<Buffer>>> {
+    fn try_to_pin(&mut self, blk: BlockId) -> Result<Option<Arc<Mutex<Buffer>>>, FileManagerError> {
         let buff = match self.find_existing_buffer(blk.clone()) {
             Some(b) => Some(b), // if buffer exists, return it
             None => {
@@ -96,7 +106,7 @@ impl BufferMgr {
                     Some(b) => {
                         {
                             let mut b_ = b.lock().unwrap();
-                            b_.assign_to_block(blk);
+                            b_.assign_to_block(blk)?;
                         }
                         Some(b)
                     }
@@ -113,9 +123,9 @@ impl BufferMgr {
                     }
                     b_.pin();
                 }
-                Some(b)
+                Ok(Some(b))
             }
-            None => None,
+            None => Ok(None),
         }
     }
 
@@ -8,6 +8,8 @@ pub mod integer {
 pub enum Constant {
     Int(i32),
     String(String),
+    /// the SQL null value; it can appear in expressions but cannot be stored
+    Null,
 }
 
 impl Display for Constant {
@@ -15,6 +17,7 @@ impl Display for Constant {
         match self {
             Constant::Int(i) => write!(f, "{}", i),
             Constant::String(s) => write!(f, "{}", s),
+            Constant::Null => write!(f, "null"),
         }
     }
 }
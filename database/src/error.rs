@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use crate::file_manager::block_id::BlockId;
+use crate::file_manager::FileManagerError;
+use crate::parser::parse_error::ParseError;
+use crate::transaction_manager::concurrency_manager::LockAbortError;
+
+/// the errors a statement can fail with
+/// an abort error leaves the transaction unable to continue, so it has to be rolled back;
+/// after any other error the transaction can go on
+#[derive(Debug)]
+pub enum DbError {
+    /// the statement is not valid SQL
+    /// boxed, since it is far larger than the other errors
+    Parse(Box<ParseError>),
+    /// a table, field, view or index is missing, or cannot be defined
+    Catalog(String),
+    /// a value does not fit the type of its field or operator
+    Type(String),
+    /// the statement does not fit the state of the transaction, e.g. an unknown savepoint
+    Transaction(String),
+    /// the transaction could not get a lock (abort error)
+    LockAbort(LockAbortError),
+    /// no buffer became available for the block in time (abort error)
+    BufferAbort(BlockId),
+    /// reading or writing a file failed
+    Io(FileManagerError),
+    /// the data on disk is not what the database wrote
+    Corruption(String),
+}
+
+impl DbError {
+    /// whether the transaction has to be rolled back
+    pub fn is_abort(&self) -> bool {
+        matches!(self, DbError::LockAbort(_) | DbError::BufferAbort(_))
+    }
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::Parse(e) => write!(f, "syntax error at {}", e),
+            DbError::Catalog(msg) => write!(f, "{}", msg),
+            DbError::Type(msg) => write!(f, "type error: {}", msg),
+            DbError::Transaction(msg) => write!(f, "{}", msg),
+            DbError::LockAbort(e) => write!(f, "transaction aborted: {}", e),
+            DbError::BufferAbort(blk) => write!(
+                f,
+                "transaction aborted: no buffer available for block {}",
+                blk
+            ),
+            DbError::Io(e) => write!(f, "i/o error: {:?}", e),
+            DbError::Corruption(msg) => write!(f, "corrupted data: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<LockAbortError> for DbError {
+    fn from(e: LockAbortError) -> Self {
+        DbError::LockAbort(e)
+    }
+}
+
+impl From<FileManagerError> for DbError {
+    fn from(e: FileManagerError) -> Self {
+        DbError::Io(e)
+    }
+}
+
+/// a value that cannot be read from a page means the page does not hold what was written to it
+impl From<std::io::Error> for DbError {
+    fn from(e: std::io::Error) -> Self {
+        DbError::Corruption(e.to_string())
+    }
+}
+
+pub type DbResult<T> = Result<T, DbError>;
@@ -10,6 +10,8 @@ pub enum FileManagerError {
     ReadBlockError(block_id::BlockId),
     WriteBlockError(block_id::BlockId),
     AppendBlockError(block_id::BlockId),
+    RemoveFileError(String),
+    MoveFileError(String),
 }
 
 #[cfg(test)]
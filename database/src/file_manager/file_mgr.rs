@@ -212,6 +212,45 @@ impl FileMgr {
         }
     }
 
+    /// Returns the names of the files in the database directory.
+    pub fn file_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(self.db_directory.clone()).unwrap() {
+            let file_path = entry.unwrap().path();
+            if file_path.is_file() {
+                names.push(file_path.file_name().unwrap().to_string_lossy().to_string());
+            }
+        }
+        names
+    }
+
+    /// Deletes the specified file.
+    /// @param filename the name of the file
+    pub fn remove(&mut self, filename: String) -> Result<(), FileManagerError> {
+        self.open_files.remove(&filename);
+        match fs::remove_file(self.get_path(filename.clone())) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(FileManagerError::RemoveFileError(filename)),
+        }
+    }
+
+    /// Moves the specified file into a subdirectory of the database directory,
+    /// creating the subdirectory if it does not exist.
+    /// @param filename the name of the file
+    /// @param dirname the name of the subdirectory
+    pub fn move_to(&mut self, filename: String, dirname: String) -> Result<(), FileManagerError> {
+        self.open_files.remove(&filename);
+        let dir = self.get_path(dirname);
+        let mut dest = PathBuf::from(dir.clone());
+        dest.push(filename.clone());
+        match fs::create_dir_all(dir)
+            .and_then(|_| fs::rename(self.get_path(filename.clone()), dest))
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(FileManagerError::MoveFileError(filename)),
+        }
+    }
+
     pub fn is_new(&self) -> bool {
         self.is_new
     }
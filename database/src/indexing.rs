@@ -1,2 +1,7 @@
+pub mod btree_dir;
+pub mod btree_index;
+pub mod btree_leaf;
+pub mod btree_page;
+pub mod dir_entry;
 pub mod hash_index;
 pub mod index;
@@ -0,0 +1,101 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::file_manager::block_id::BlockId;
+use crate::indexing::btree_page::BTPage;
+use crate::indexing::dir_entry::DirEntry;
+use crate::record_management::layout::Layout;
+use crate::transaction_manager::transaction::Transaction;
+
+/// a directory block of a B-tree
+/// directory record | block | dataval |
+pub struct BTreeDir {
+    layout: Layout,
+    contents: BTPage,
+    filename: String,
+}
+
+impl BTreeDir {
+    pub fn new(tx: &mut Transaction, blk: BlockId, layout: Layout) -> DbResult<Self> {
+        Ok(Self {
+            contents: BTPage::new(tx, blk.clone(), layout.clone())?,
+            layout,
+            filename: blk.filename(),
+        })
+    }
+
+    pub fn close(&mut self, tx: &mut Transaction) {
+        self.contents.close(tx);
+    }
+
+    /// walk down the directory to the leaf that may hold the search key
+    /// @return: the block number of the leaf
+    pub fn search(&mut self, tx: &mut Transaction, search_key: &Constant) -> DbResult<i32> {
+        let mut childblk = self.find_child_block(tx, search_key)?;
+        while self.contents.get_flag(tx)? > 0 {
+            self.contents.close(tx);
+            self.contents = BTPage::new(tx, childblk, self.layout.clone())?;
+            childblk = self.find_child_block(tx, search_key)?;
+        }
+        return Ok(childblk.number());
+    }
+
+    /// move the entries of the root to a new block and
+    /// make the root point to that block and to the new entry
+    /// the root always stays at block 0
+    pub fn make_new_root(&mut self, tx: &mut Transaction, e: DirEntry) -> DbResult<()> {
+        let firstval = self.contents.get_data_val(tx, 0)?;
+        let level = self.contents.get_flag(tx)?;
+        let newblk = self.contents.split(tx, 0, level)?; // transfer all the records
+        let oldroot = DirEntry::new(firstval, newblk.number());
+        self.insert_entry(tx, oldroot)?;
+        self.insert_entry(tx, e)?;
+        self.contents.set_flag(tx, level + 1)
+    }
+
+    /// insert the entry of a new leaf below this block
+    /// if this block splits, return the entry of the new directory block
+    pub fn insert(&mut self, tx: &mut Transaction, e: DirEntry) -> DbResult<Option<DirEntry>> {
+        if self.contents.get_flag(tx)? == 0 {
+            return self.insert_entry(tx, e);
+        }
+        let childblk = self.find_child_block(tx, &e.data_val())?;
+        let mut child = BTreeDir::new(tx, childblk, self.layout.clone())?;
+        let myentry = child.insert(tx, e);
+        child.close(tx);
+        match myentry? {
+            Some(myentry) => self.insert_entry(tx, myentry),
+            None => Ok(None),
+        }
+    }
+
+    fn insert_entry(&mut self, tx: &mut Transaction, e: DirEntry) -> DbResult<Option<DirEntry>> {
+        let newslot = 1 + self.contents.find_slot_before(tx, &e.data_val())?;
+        self.contents
+            .insert_dir(tx, newslot, e.data_val(), e.block_number())?;
+        if !self.contents.is_full(tx)? {
+            return Ok(None);
+        }
+        // the page is full, so split it
+        let level = self.contents.get_flag(tx)?;
+        let splitpos = self.contents.get_num_recs(tx)? / 2;
+        let splitval = self.contents.get_data_val(tx, splitpos)?;
+        let newblk = self.contents.split(tx, splitpos, level)?;
+        return Ok(Some(DirEntry::new(splitval, newblk.number())));
+    }
+
+    /// the child is the last one whose dataval is not greater than the search key
+    fn find_child_block(
+        &mut self,
+        tx: &mut Transaction,
+        search_key: &Constant,
+    ) -> DbResult<BlockId> {
+        let mut slot = self.contents.find_slot_before(tx, search_key)?;
+        if slot + 1 < self.contents.get_num_recs(tx)?
+            && self.contents.get_data_val(tx, slot + 1)? == *search_key
+        {
+            slot += 1;
+        }
+        let blknum = self.contents.get_child_num(tx, slot)?;
+        return Ok(BlockId::new(self.filename.clone(), blknum));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::file_manager::block_id::BlockId;
+use crate::indexing::btree_dir::BTreeDir;
+use crate::indexing::btree_leaf::BTreeLeaf;
+use crate::indexing::btree_page::BTPage;
+use crate::indexing::index::Index;
+use crate::record_management::layout::Layout;
+use crate::record_management::rid::RID;
+use crate::record_management::schema::{Schema, Type};
+use crate::transaction_manager::transaction::Transaction;
+
+/// B+-tree index
+/// the leaves are stored in the table idxname + "leaf" and the directory in idxname + "dir"
+/// leaf record | block | id | dataval |
+/// directory record | block | dataval |
+pub struct BTreeIndex {
+    dir_layout: Layout,
+    leaf_layout: Layout,
+    leaftbl: String,
+    leaf: Option<BTreeLeaf>,
+    rootblk: BlockId,
+}
+
+impl BTreeIndex {
+    /// open the index, creating the first leaf and the root if the index is new
+    pub fn new(tx: &mut Transaction, idxname: String, leaf_layout: Layout) -> DbResult<Self> {
+        // deal with the leaves
+        let leaftbl = idxname.clone() + "leaf";
+        if tx.size(leaftbl.clone())? == 0 {
+            let blk = tx.append(leaftbl.clone())?;
+            let mut node = BTPage::new(tx, blk.clone(), leaf_layout.clone())?;
+            node.format(tx, &blk, -1)?;
+            node.close(tx);
+        }
+
+        // deal with the directory
+        let leafsch = leaf_layout.schema();
+        let mut dirsch = Schema::new();
+        dirsch.add("block".to_string(), &leafsch)?;
+        dirsch.add("dataval".to_string(), &leafsch)?;
+        let dirtbl = idxname + "dir";
+        let dir_layout = Layout::new_from_schema(dirsch);
+        let rootblk = BlockId::new(dirtbl.clone(), 0);
+        if tx.size(dirtbl.clone())? == 0 {
+            // create new root block
+            tx.append(dirtbl)?;
+            let mut node = BTPage::new(tx, rootblk.clone(), dir_layout.clone())?;
+            node.format(tx, &rootblk, 0)?;
+            // insert initial directory entry
+            let minval = match leafsch.get_type_(&"dataval".to_string())? {
+                Type::INTEGER => Constant::Int(i32::MIN),
+                Type::VARCHAR => Constant::String("".to_string()),
+            };
+            node.insert_dir(tx, 0, minval, 0)?;
+            node.close(tx);
+        }
+
+        Ok(Self {
+            dir_layout,
+            leaf_layout,
+            leaftbl,
+            leaf: None,
+            rootblk,
+        })
+    }
+
+    /// the dataval of the current index record
+    pub fn get_data_val(&mut self, tx: &mut Transaction) -> DbResult<Constant> {
+        self.leaf
+            .as_mut()
+            .expect("BTreeIndex.get_data_val() called before before_first()")
+            .get_data_val(tx)
+    }
+
+    /// the number of block accesses to find the leaf holding a search key
+    pub fn search_cost(num_blocks: i32, rpb: i32) -> i32 {
+        if num_blocks <= 1 || rpb <= 1 {
+            return 1;
+        }
+        return 1 + ((num_blocks as f64).ln() / (rpb as f64).ln()) as i32;
+    }
+
+    fn search_leaf(&mut self, search_key: &Constant, tx: &mut Transaction) -> DbResult<i32> {
+        let mut root = BTreeDir::new(tx, self.rootblk.clone(), self.dir_layout.clone())?;
+        let blknum = root.search(tx, search_key);
+        root.close(tx);
+        return blknum;
+    }
+}
+
+impl Index for BTreeIndex {
+    /// walk down the directory to the leaf that may hold the search key
+    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction) -> DbResult<()> {
+        self.close(tx);
+        let blknum = self.search_leaf(&search_key, tx)?;
+        let leafblk = BlockId::new(self.leaftbl.clone(), blknum);
+        self.leaf = Some(BTreeLeaf::new(
+            tx,
+            leafblk,
+            self.leaf_layout.clone(),
+            search_key,
+        )?);
+        Ok(())
+    }
+
+    /// walk down to the leaf that may hold lo and follow the leaf chain up to hi
+    /// the records are not returned in key order
+    fn before_range(
+        &mut self,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> DbResult<()> {
+        self.close(tx);
+        let blknum = match lo.as_ref() {
+            Some(lo) => self.search_leaf(lo, tx)?,
+            None => 0, // the leftmost leaf never moves
+        };
+        let leafblk = BlockId::new(self.leaftbl.clone(), blknum);
+        self.leaf = Some(BTreeLeaf::new_range(
+            tx,
+            leafblk,
+            self.leaf_layout.clone(),
+            lo,
+            hi,
+        )?);
+        Ok(())
+    }
+
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        self.leaf
+            .as_mut()
+            .expect("BTreeIndex.next() called before before_first()")
+            .next(tx)
+    }
+
+    fn get_data_rid(&mut self, tx: &mut Transaction) -> DbResult<RID> {
+        self.leaf
+            .as_mut()
+            .expect("BTreeIndex.get_data_rid() called before before_first()")
+            .get_data_rid(tx)
+    }
+
+    /// insert into the leaf, and if the leaf splits, insert its new entry into the directory
+    /// if the root splits, the tree grows one level
+    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        self.before_first(val, tx)?;
+        let e = match self.leaf.take() {
+            Some(mut leaf) => {
+                let e = leaf.insert(tx, data_rid);
+                leaf.close(tx);
+                e
+            }
+            None => panic!("BTreeIndex.insert() called before before_first()"),
+        };
+        if let Some(e) = e? {
+            let mut root = BTreeDir::new(tx, self.rootblk.clone(), self.dir_layout.clone())?;
+            let result = match root.insert(tx, e) {
+                Ok(Some(e2)) => root.make_new_root(tx, e2),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            root.close(tx);
+            return result;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        self.before_first(val, tx)?;
+        if let Some(mut leaf) = self.leaf.take() {
+            let result = leaf.delete(tx, data_rid);
+            leaf.close(tx);
+            return result;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, tx: &mut Transaction) {
+        if let Some(mut leaf) = self.leaf.take() {
+            leaf.close(tx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn leaf_layout() -> Layout {
+        let mut sch = Schema::new();
+        sch.add_int_field("block".to_string());
+        sch.add_int_field("id".to_string());
+        sch.add_int_field("dataval".to_string());
+        Layout::new_from_schema(sch)
+    }
+
+    fn lookup(idx: &mut BTreeIndex, val: i32, tx: &mut Transaction) -> Vec<RID> {
+        let mut rids = Vec::new();
+        idx.before_first(Constant::Int(val), tx).unwrap();
+        while idx.next(tx).unwrap() {
+            rids.push(idx.get_data_rid(tx).unwrap());
+        }
+        rids
+    }
+
+    fn range(
+        idx: &mut BTreeIndex,
+        lo: Option<i32>,
+        hi: Option<i32>,
+        tx: &mut Transaction,
+    ) -> Vec<i32> {
+        let mut vals = Vec::new();
+        idx.before_range(lo.map(Constant::Int), hi.map(Constant::Int), tx)
+            .unwrap();
+        while idx.next(tx).unwrap() {
+            match idx.get_data_val(tx).unwrap() {
+                Constant::Int(v) => vals.push(v),
+                _ => panic!("expected int"),
+            }
+        }
+        vals.sort();
+        vals
+    }
+
+    #[test]
+    fn test_btree_index() -> Result<()> {
+        setup();
+        let db = SimpleDB::new("./db/logtest", "btreeindextest", 400, 8)?;
+        let mut tx = db.new_tx();
+        let mut idx = BTreeIndex::new(&mut tx, "testidx".to_string(), leaf_layout())?;
+
+        // enough records to split leaves and the root
+        // (400 - 12) / 16 = 24 records per page
+        let n = 1000;
+        for i in 0..n {
+            let val = (i * 7) % n; // not in key order
+            idx.insert(Constant::Int(val), RID::new(val, 0), &mut tx)?;
+        }
+        assert!(tx.size("testidxdir".to_string())? > 1);
+        assert_eq!(tx.available_buffs(), 8);
+
+        for val in [0, 1, 499, 500, 999] {
+            assert_eq!(lookup(&mut idx, val, &mut tx), vec![RID::new(val, 0)]);
+        }
+        assert!(lookup(&mut idx, n, &mut tx).is_empty());
+        assert!(lookup(&mut idx, -1, &mut tx).is_empty());
+
+        assert_eq!(
+            range(&mut idx, Some(100), Some(110), &mut tx),
+            (100..=110).collect::<Vec<i32>>()
+        );
+        assert_eq!(range(&mut idx, None, Some(4), &mut tx), vec![0, 1, 2, 3, 4]);
+        assert_eq!(
+            range(&mut idx, Some(995), None, &mut tx),
+            vec![995, 996, 997, 998, 999]
+        );
+        assert_eq!(range(&mut idx, None, None, &mut tx).len(), n as usize);
+        assert!(range(&mut idx, Some(20), Some(10), &mut tx).is_empty());
+
+        for i in (0..n).step_by(2) {
+            idx.delete(Constant::Int(i), RID::new(i, 0), &mut tx)?;
+        }
+        assert!(lookup(&mut idx, 500, &mut tx).is_empty());
+        assert_eq!(lookup(&mut idx, 501, &mut tx), vec![RID::new(501, 0)]);
+        assert_eq!(
+            range(&mut idx, Some(100), Some(110), &mut tx),
+            vec![101, 103, 105, 107, 109]
+        );
+        idx.close(&mut tx);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_btree_index_duplicates() -> Result<()> {
+        setup();
+        let db = SimpleDB::new("./db/logtest", "btreeduptest", 400, 8)?;
+        let mut tx = db.new_tx();
+        let mut idx = BTreeIndex::new(&mut tx, "dupidx".to_string(), leaf_layout())?;
+
+        // 100 records with the same key need overflow blocks
+        for i in 0..100 {
+            idx.insert(Constant::Int(5), RID::new(i, 1), &mut tx)?;
+        }
+        // keys around the overflow chain, including a smaller one
+        for i in 0..50 {
+            idx.insert(Constant::Int(10), RID::new(i, 2), &mut tx)?;
+            idx.insert(Constant::Int(1), RID::new(i, 3), &mut tx)?;
+        }
+
+        assert_eq!(lookup(&mut idx, 5, &mut tx).len(), 100);
+        assert_eq!(lookup(&mut idx, 10, &mut tx).len(), 50);
+        assert_eq!(lookup(&mut idx, 1, &mut tx).len(), 50);
+        assert_eq!(range(&mut idx, Some(2), Some(9), &mut tx), vec![5; 100]);
+        assert_eq!(range(&mut idx, Some(5), None, &mut tx).len(), 150);
+        assert_eq!(range(&mut idx, None, Some(4), &mut tx), vec![1; 50]);
+
+        // delete every record of key 5, starting with the one representing the chain
+        for i in 0..100 {
+            idx.delete(Constant::Int(5), RID::new(i, 1), &mut tx)?;
+            assert_eq!(lookup(&mut idx, 5, &mut tx).len(), 99 - i as usize);
+        }
+        assert_eq!(range(&mut idx, None, None, &mut tx).len(), 100);
+        idx.close(&mut tx);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::file_manager::block_id::BlockId;
+use crate::indexing::btree_page::BTPage;
+use crate::indexing::dir_entry::DirEntry;
+use crate::record_management::layout::Layout;
+use crate::record_management::rid::RID;
+use crate::transaction_manager::transaction::Transaction;
+
+/// what the leaf scan is looking for
+enum LeafSearch {
+    /// records whose dataval equals the key
+    Equal(Constant),
+    /// records whose dataval is between the bounds (inclusive), None means unbounded
+    Range(Option<Constant>, Option<Constant>),
+}
+
+/// a scan over the leaf blocks of a B-tree
+/// a leaf reachable from the directory may have a chain of overflow blocks,
+/// which hold further records having the same dataval as its first record
+pub struct BTreeLeaf {
+    layout: Layout,
+    search: LeafSearch,
+    contents: BTPage,
+    current_slot: i32,
+    filename: String,
+    in_overflow: bool,
+    sibling: i32,
+    chain_key: Option<Constant>,
+    past_hi: bool,
+}
+
+impl BTreeLeaf {
+    /// open the leaf block and position before the first record having the search key
+    pub fn new(
+        tx: &mut Transaction,
+        blk: BlockId,
+        layout: Layout,
+        search_key: Constant,
+    ) -> DbResult<Self> {
+        let mut contents = BTPage::new(tx, blk.clone(), layout.clone())?;
+        let current_slot = contents.find_slot_before(tx, &search_key)?;
+        let mut leaf = Self {
+            layout,
+            search: LeafSearch::Equal(search_key),
+            contents,
+            current_slot,
+            filename: blk.filename(),
+            in_overflow: false,
+            sibling: -1,
+            chain_key: None,
+            past_hi: false,
+        };
+        leaf.enter_primary(tx)?;
+        Ok(leaf)
+    }
+
+    /// open the leaf block and position before the first record not smaller than lo
+    /// the scan continues into the following leaves until a record is greater than hi
+    pub fn new_range(
+        tx: &mut Transaction,
+        blk: BlockId,
+        layout: Layout,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+    ) -> DbResult<Self> {
+        let mut contents = BTPage::new(tx, blk.clone(), layout.clone())?;
+        let current_slot = match lo.as_ref() {
+            Some(lo) => contents.find_slot_before(tx, lo)?,
+            None => -1,
+        };
+        let mut leaf = Self {
+            layout,
+            search: LeafSearch::Range(lo, hi),
+            contents,
+            current_slot,
+            filename: blk.filename(),
+            in_overflow: false,
+            sibling: -1,
+            chain_key: None,
+            past_hi: false,
+        };
+        leaf.enter_primary(tx)?;
+        Ok(leaf)
+    }
+
+    pub fn close(&mut self, tx: &mut Transaction) {
+        self.contents.close(tx);
+    }
+
+    /// move to the next leaf record matching the search
+    pub fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        match &self.search {
+            LeafSearch::Equal(_) => self.next_equal(tx),
+            LeafSearch::Range(_, _) => self.next_in_range(tx),
+        }
+    }
+
+    pub fn get_data_rid(&mut self, tx: &mut Transaction) -> DbResult<RID> {
+        self.contents.get_data_rid(tx, self.current_slot)
+    }
+
+    pub fn get_data_val(&mut self, tx: &mut Transaction) -> DbResult<Constant> {
+        self.contents.get_data_val(tx, self.current_slot)
+    }
+
+    /// delete the leaf record having the search key and the rid
+    pub fn delete(&mut self, tx: &mut Transaction, data_rid: RID) -> DbResult<()> {
+        while self.next(tx)? {
+            if self.get_data_rid(tx)? == data_rid {
+                if !self.in_overflow && self.current_slot == 0 && self.contents.get_flag(tx)? >= 0 {
+                    // the first record of the leaf stands for its overflow chain
+                    self.replace_first_from_overflow(tx)?;
+                } else {
+                    self.contents.delete(tx, self.current_slot)?;
+                }
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// insert a leaf record having the search key and the rid
+    /// if the leaf splits, return the directory entry of the new leaf
+    pub fn insert(&mut self, tx: &mut Transaction, data_rid: RID) -> DbResult<Option<DirEntry>> {
+        let search_key = match &self.search {
+            LeafSearch::Equal(key) => key.clone(),
+            LeafSearch::Range(_, _) => panic!("BTreeLeaf.insert() called on a range scan"),
+        };
+
+        let flag = self.contents.get_flag(tx)?;
+        if flag >= 0 && self.contents.get_data_val(tx, 0)? > search_key {
+            // the new record is smaller than the overflow chain,
+            // so move the whole leaf with its chain to a new block
+            let firstval = self.contents.get_data_val(tx, 0)?;
+            let newblk = self.contents.split(tx, 0, flag)?;
+            self.link_sibling(tx, &newblk)?;
+            self.current_slot = 0;
+            self.contents.set_flag(tx, -1)?;
+            self.contents
+                .insert_leaf(tx, self.current_slot, search_key, data_rid)?;
+            return Ok(Some(DirEntry::new(firstval, newblk.number())));
+        }
+
+        self.current_slot += 1;
+        self.contents
+            .insert_leaf(tx, self.current_slot, search_key, data_rid)?;
+        if !self.contents.is_full(tx)? {
+            return Ok(None);
+        }
+
+        // the page is full, so split it
+        let firstkey = self.contents.get_data_val(tx, 0)?;
+        let num_recs = self.contents.get_num_recs(tx)?;
+        let lastkey = self.contents.get_data_val(tx, num_recs - 1)?;
+        if lastkey == firstkey {
+            // create an overflow block to hold all but the first record
+            let newblk = self.contents.split(tx, 1, flag)?;
+            self.contents.set_flag(tx, newblk.number())?;
+            return Ok(None);
+        }
+
+        // records having the same key must stay in the same block
+        let mut splitpos = num_recs / 2;
+        let mut splitkey = self.contents.get_data_val(tx, splitpos)?;
+        if splitkey == firstkey {
+            // move right, looking for the next key
+            while self.contents.get_data_val(tx, splitpos)? == splitkey {
+                splitpos += 1;
+            }
+            splitkey = self.contents.get_data_val(tx, splitpos)?;
+        } else {
+            // move left, looking for the first record having that key
+            while self.contents.get_data_val(tx, splitpos - 1)? == splitkey {
+                splitpos -= 1;
+            }
+        }
+        let newblk = self.contents.split(tx, splitpos, -1)?;
+        self.link_sibling(tx, &newblk)?;
+        return Ok(Some(DirEntry::new(splitkey, newblk.number())));
+    }
+
+    fn next_equal(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let search_key = match &self.search {
+            LeafSearch::Equal(key) => key.clone(),
+            LeafSearch::Range(_, _) => unreachable!(),
+        };
+        loop {
+            self.current_slot += 1;
+            if self.current_slot < self.contents.get_num_recs(tx)?
+                && self.get_data_val(tx)? == search_key
+            {
+                return Ok(true);
+            }
+            // the remaining records can only be in the overflow chain
+            if self.chain_key.as_ref() != Some(&search_key) || !self.try_overflow(tx)? {
+                return Ok(false);
+            }
+        }
+    }
+
+    fn next_in_range(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let (lo, hi) = match &self.search {
+            LeafSearch::Range(lo, hi) => (lo.clone(), hi.clone()),
+            LeafSearch::Equal(_) => unreachable!(),
+        };
+        loop {
+            self.current_slot += 1;
+            if self.current_slot < self.contents.get_num_recs(tx)? {
+                let val = self.get_data_val(tx)?;
+                if lo.as_ref().is_some_and(|lo| val < *lo) {
+                    continue;
+                }
+                if hi.as_ref().is_none_or(|hi| val <= *hi) {
+                    return Ok(true);
+                }
+                // the rest of this block is out of range, and so are the following leaves,
+                // but the overflow chain holds the first key of the leaf
+                self.past_hi = true;
+            }
+            if self.try_overflow(tx)? {
+                continue;
+            }
+            if self.past_hi || self.sibling < 0 {
+                return Ok(false);
+            }
+            let blk = BlockId::new(self.filename.clone(), self.sibling);
+            self.move_to(tx, blk)?;
+            self.in_overflow = false;
+            self.enter_primary(tx)?;
+        }
+    }
+
+    /// move to the next block of the overflow chain, if any
+    fn try_overflow(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let flag = self.contents.get_flag(tx)?;
+        if flag < 0 {
+            return Ok(false);
+        }
+        let blk = BlockId::new(self.filename.clone(), flag);
+        self.move_to(tx, blk)?;
+        self.in_overflow = true;
+        return Ok(true);
+    }
+
+    fn move_to(&mut self, tx: &mut Transaction, blk: BlockId) -> DbResult<()> {
+        self.contents.close(tx);
+        self.contents = BTPage::new(tx, blk, self.layout.clone())?;
+        self.current_slot = -1;
+        Ok(())
+    }
+
+    /// remember the sibling and the overflow key of the leaf just opened
+    fn enter_primary(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.sibling = self.contents.get_sibling(tx)?;
+        self.chain_key = if self.contents.get_flag(tx)? >= 0 {
+            Some(self.contents.get_data_val(tx, 0)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    /// link the new leaf after this one in key order
+    fn link_sibling(&mut self, tx: &mut Transaction, newblk: &BlockId) -> DbResult<()> {
+        let sibling = self.contents.get_sibling(tx)?;
+        let mut newpage = BTPage::new(tx, newblk.clone(), self.layout.clone())?;
+        newpage.set_sibling(tx, sibling)?;
+        newpage.close(tx);
+        self.contents.set_sibling(tx, newblk.number())
+    }
+
+    /// replace the first record of the leaf by one taken from its overflow chain
+    /// so that the first record keeps representing the chain
+    fn replace_first_from_overflow(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        let firstval = self.contents.get_data_val(tx, 0)?;
+        self.contents.delete(tx, 0)?;
+        if self.contents.get_num_recs(tx)? > 0 && self.contents.get_data_val(tx, 0)? == firstval {
+            return Ok(());
+        }
+        loop {
+            let flag = self.contents.get_flag(tx)?;
+            if flag < 0 {
+                // the chain was empty
+                return Ok(());
+            }
+            let blk = BlockId::new(self.filename.clone(), flag);
+            let mut overflow = BTPage::new(tx, blk, self.layout.clone())?;
+            let num_recs = overflow.get_num_recs(tx)?;
+            if num_recs == 0 {
+                // unlink the empty overflow block
+                let next = overflow.get_flag(tx)?;
+                overflow.close(tx);
+                self.contents.set_flag(tx, next)?;
+                continue;
+            }
+            let rid = overflow.get_data_rid(tx, num_recs - 1)?;
+            overflow.delete(tx, num_recs - 1)?;
+            if num_recs == 1 {
+                let next = overflow.get_flag(tx)?;
+                self.contents.set_flag(tx, next)?;
+            }
+            overflow.close(tx);
+            self.contents.insert_leaf(tx, 0, firstval, rid)?;
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::common::{integer, Constant};
+use crate::error::{DbError, DbResult};
+use crate::file_manager::block_id::BlockId;
+use crate::record_management::layout::Layout;
+use crate::record_management::rid::RID;
+use crate::record_management::schema::Type;
+use crate::transaction_manager::transaction::Transaction;
+
+const FLAG_OFFSET: i32 = 0;
+const NUM_RECS_OFFSET: i32 = integer::BYTES;
+const SIBLING_OFFSET: i32 = 2 * integer::BYTES;
+const HEADER_SIZE: i32 = 3 * integer::BYTES;
+
+/// a page of a B-tree block, used by both directory and leaf blocks
+/// records are kept sorted by dataval
+/// | flag | number of records | sibling | record 0 | record 1 | ...
+/// directory block: flag is the level of the block (0 means the children are leaves)
+/// leaf block: flag is the overflow block number (-1 if there is none)
+/// sibling is the next leaf block in key order (-1 if there is none), unused by directory blocks
+pub struct BTPage {
+    currentblk: Option<BlockId>,
+    layout: Layout,
+}
+
+impl BTPage {
+    pub fn new(tx: &mut Transaction, currentblk: BlockId, layout: Layout) -> DbResult<Self> {
+        tx.pin(currentblk.clone())?;
+        Ok(Self {
+            currentblk: Some(currentblk),
+            layout,
+        })
+    }
+
+    /// return the position of the last record whose dataval is smaller than the search key
+    /// if there is no such record, return -1
+    pub fn find_slot_before(
+        &mut self,
+        tx: &mut Transaction,
+        search_key: &Constant,
+    ) -> DbResult<i32> {
+        let mut slot = 0;
+        while slot < self.get_num_recs(tx)? && self.get_data_val(tx, slot)? < *search_key {
+            slot += 1;
+        }
+        return Ok(slot - 1);
+    }
+
+    pub fn close(&mut self, tx: &mut Transaction) {
+        if let Some(blk) = self.currentblk.take() {
+            tx.unpin(blk);
+        }
+    }
+
+    /// whether there is no room for one more record
+    pub fn is_full(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let num_recs = self.get_num_recs(tx)?;
+        return Ok(self.slot_pos(num_recs + 1) >= tx.block_size());
+    }
+
+    /// move the records from splitpos onward to a new block
+    /// @param flag: the flag of the new block
+    /// @return: the new block
+    pub fn split(&mut self, tx: &mut Transaction, splitpos: i32, flag: i32) -> DbResult<BlockId> {
+        let newblk = self.append_new(tx, flag)?;
+        let mut newpage = BTPage::new(tx, newblk.clone(), self.layout.clone())?;
+        self.transfer_recs(tx, splitpos, &mut newpage)?;
+        newpage.set_flag(tx, flag)?;
+        newpage.close(tx);
+        return Ok(newblk);
+    }
+
+    pub fn get_data_val(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<Constant> {
+        return self.get_val(tx, slot, &"dataval".to_string());
+    }
+
+    pub fn get_flag(&mut self, tx: &mut Transaction) -> DbResult<i32> {
+        return tx.get_int(self.block(), FLAG_OFFSET);
+    }
+
+    pub fn set_flag(&mut self, tx: &mut Transaction, val: i32) -> DbResult<()> {
+        return tx.set_int(self.block(), FLAG_OFFSET, val, true);
+    }
+
+    pub fn get_sibling(&mut self, tx: &mut Transaction) -> DbResult<i32> {
+        return tx.get_int(self.block(), SIBLING_OFFSET);
+    }
+
+    pub fn set_sibling(&mut self, tx: &mut Transaction, blknum: i32) -> DbResult<()> {
+        return tx.set_int(self.block(), SIBLING_OFFSET, blknum, true);
+    }
+
+    /// append an empty block to the file of this page
+    pub fn append_new(&mut self, tx: &mut Transaction, flag: i32) -> DbResult<BlockId> {
+        let blk = tx.append(self.block().filename())?;
+        tx.pin(blk.clone())?;
+        self.format(tx, &blk, flag)?;
+        tx.unpin(blk.clone());
+        return Ok(blk);
+    }
+
+    /// initialize the header and every record slot of the block
+    /// the block must be pinned
+    /// the header is logged so that recovery can redo it, since a zeroed header is not an empty page
+    /// the record slots need no logging, an appended block is zeroed already
+    pub fn format(&mut self, tx: &mut Transaction, blk: &BlockId, flag: i32) -> DbResult<()> {
+        tx.set_int(blk.clone(), FLAG_OFFSET, flag, true)?;
+        tx.set_int(blk.clone(), NUM_RECS_OFFSET, 0, true)?;
+        tx.set_int(blk.clone(), SIBLING_OFFSET, -1, true)?;
+        let recsize = self.layout.slot_size();
+        let mut pos = HEADER_SIZE;
+        while pos + recsize <= tx.block_size() {
+            self.make_default_record(tx, blk, pos)?;
+            pos += recsize;
+        }
+        Ok(())
+    }
+
+    // methods called only by BTreeDir
+
+    pub fn get_child_num(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
+        return self.get_int(tx, slot, &"block".to_string());
+    }
+
+    pub fn insert_dir(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        val: Constant,
+        blknum: i32,
+    ) -> DbResult<()> {
+        self.insert(tx, slot)?;
+        self.set_val(tx, slot, &"dataval".to_string(), val)?;
+        self.set_int(tx, slot, &"block".to_string(), blknum)
+    }
+
+    // methods called only by BTreeLeaf
+
+    pub fn get_data_rid(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<RID> {
+        let blknum = self.get_int(tx, slot, &"block".to_string())?;
+        let id = self.get_int(tx, slot, &"id".to_string())?;
+        return Ok(RID::new(blknum, id));
+    }
+
+    pub fn insert_leaf(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        val: Constant,
+        rid: RID,
+    ) -> DbResult<()> {
+        self.insert(tx, slot)?;
+        self.set_val(tx, slot, &"dataval".to_string(), val)?;
+        self.set_int(tx, slot, &"block".to_string(), rid.block_number())?;
+        self.set_int(tx, slot, &"id".to_string(), rid.slot_number())
+    }
+
+    pub fn delete(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<()> {
+        for i in slot + 1..self.get_num_recs(tx)? {
+            self.copy_record(tx, i, i - 1)?;
+        }
+        let num_recs = self.get_num_recs(tx)?;
+        self.set_num_recs(tx, num_recs - 1)
+    }
+
+    pub fn get_num_recs(&mut self, tx: &mut Transaction) -> DbResult<i32> {
+        return tx.get_int(self.block(), NUM_RECS_OFFSET);
+    }
+
+    pub fn block(&self) -> BlockId {
+        self.currentblk.clone().expect("BTPage used after close()")
+    }
+
+    fn make_default_record(
+        &mut self,
+        tx: &mut Transaction,
+        blk: &BlockId,
+        pos: i32,
+    ) -> DbResult<()> {
+        let sch = self.layout.schema();
+        for fldname in sch.get_fields().iter() {
+            let offset = self.layout.offset(fldname);
+            match sch.get_type_(fldname)? {
+                Type::INTEGER => tx.set_int(blk.clone(), pos + offset, 0, false)?,
+                Type::VARCHAR => tx.set_string(blk.clone(), pos + offset, "".to_string(), false)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn get_int(&mut self, tx: &mut Transaction, slot: i32, fldname: &String) -> DbResult<i32> {
+        let pos = self.fld_pos(slot, fldname);
+        return tx.get_int(self.block(), pos);
+    }
+
+    fn get_string(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        fldname: &String,
+    ) -> DbResult<String> {
+        let pos = self.fld_pos(slot, fldname);
+        return tx.get_string(self.block(), pos);
+    }
+
+    fn get_val(&mut self, tx: &mut Transaction, slot: i32, fldname: &String) -> DbResult<Constant> {
+        match self.layout.schema().get_type_(fldname)? {
+            Type::INTEGER => Ok(Constant::Int(self.get_int(tx, slot, fldname)?)),
+            Type::VARCHAR => Ok(Constant::String(self.get_string(tx, slot, fldname)?)),
+        }
+    }
+
+    fn set_int(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        fldname: &String,
+        val: i32,
+    ) -> DbResult<()> {
+        let pos = self.fld_pos(slot, fldname);
+        return tx.set_int(self.block(), pos, val, true);
+    }
+
+    fn set_string(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        fldname: &String,
+        val: String,
+    ) -> DbResult<()> {
+        let pos = self.fld_pos(slot, fldname);
+        return tx.set_string(self.block(), pos, val, true);
+    }
+
+    fn set_val(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        fldname: &String,
+        val: Constant,
+    ) -> DbResult<()> {
+        match val {
+            Constant::Int(i) => self.set_int(tx, slot, fldname, i),
+            Constant::String(s) => self.set_string(tx, slot, fldname, s),
+            Constant::Null => Err(DbError::Type(
+                "null cannot be stored in an index".to_string(),
+            )),
+        }
+    }
+
+    fn set_num_recs(&mut self, tx: &mut Transaction, n: i32) -> DbResult<()> {
+        return tx.set_int(self.block(), NUM_RECS_OFFSET, n, true);
+    }
+
+    /// shift the records from slot onward one position to the right
+    fn insert(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<()> {
+        let num_recs = self.get_num_recs(tx)?;
+        for i in (slot + 1..=num_recs).rev() {
+            self.copy_record(tx, i - 1, i)?;
+        }
+        self.set_num_recs(tx, num_recs + 1)
+    }
+
+    fn copy_record(&mut self, tx: &mut Transaction, from: i32, to: i32) -> DbResult<()> {
+        let sch = self.layout.schema();
+        for fldname in sch.get_fields().iter() {
+            let val = self.get_val(tx, from, fldname)?;
+            self.set_val(tx, to, fldname, val)?;
+        }
+        Ok(())
+    }
+
+    fn transfer_recs(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        dest: &mut BTPage,
+    ) -> DbResult<()> {
+        let mut destslot = 0;
+        while slot < self.get_num_recs(tx)? {
+            dest.insert(tx, destslot)?;
+            let sch = self.layout.schema();
+            for fldname in sch.get_fields().iter() {
+                let val = self.get_val(tx, slot, fldname)?;
+                dest.set_val(tx, destslot, fldname, val)?;
+            }
+            self.delete(tx, slot)?;
+            destslot += 1;
+        }
+        Ok(())
+    }
+
+    fn fld_pos(&self, slot: i32, fldname: &String) -> i32 {
+        return self.slot_pos(slot) + self.layout.offset(fldname);
+    }
+
+    fn slot_pos(&self, slot: i32) -> i32 {
+        return HEADER_SIZE + slot * self.layout.slot_size();
+    }
+}
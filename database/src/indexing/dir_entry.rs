@@ -0,0 +1,23 @@
+use crate::common::Constant;
+
+/// a directory entry of a B-tree
+/// dataval is the smallest value stored in the child block blknum
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirEntry {
+    dataval: Constant,
+    blknum: i32,
+}
+
+impl DirEntry {
+    pub fn new(dataval: Constant, blknum: i32) -> Self {
+        Self { dataval, blknum }
+    }
+
+    pub fn data_val(&self) -> Constant {
+        self.dataval.clone()
+    }
+
+    pub fn block_number(&self) -> i32 {
+        self.blknum
+    }
+}
@@ -1,9 +1,8 @@
-#[allow(unused_imports)]
 use std::collections::hash_map::DefaultHasher;
-#[allow(unused_imports)]
 use std::hash::{Hash, Hasher};
 
 use crate::common::Constant;
+use crate::error::{DbError, DbResult};
 use crate::indexing::index::Index;
 use crate::record_management::rid::RID;
 use crate::record_management::{layout::Layout, table_scan::TableScan};
@@ -11,7 +10,9 @@ use crate::transaction_manager::transaction::Transaction;
 
 const NUM_BUCKETS: i32 = 100;
 
-#[allow(dead_code)]
+/// static hash index
+/// each bucket is stored in its own table named idxname + bucket number
+/// index record | block | id | dataval |
 pub struct HashIndex {
     idxname: String,
     layout: Layout,
@@ -32,87 +33,200 @@ impl HashIndex {
     pub fn search_cost(num_blocks: i32, _rpb: i32) -> i32 {
         return num_blocks / NUM_BUCKETS;
     }
+
+    /// reclaim the index record versions no transaction can see any more
+    /// @return: the number of reclaimed versions
+    pub fn vacuum(idxname: String, layout: Layout, tx: &mut Transaction) -> DbResult<i32> {
+        let mut count = 0;
+        for bucket in 0..NUM_BUCKETS {
+            let tblname = idxname.clone() + bucket.to_string().as_str();
+            if tx.size(tblname.clone() + ".tbl")? == 0 {
+                continue;
+            }
+            let mut ts = TableScan::new(tx, tblname, layout.clone())?;
+            let reclaimed = ts.vacuum(tx);
+            ts.close(tx);
+            count += reclaimed?;
+        }
+        return Ok(count);
+    }
 }
 
-#[allow(unused_variables)]
 impl Index for HashIndex {
-    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction) {
-        todo!("HashIndex.before_first()");
-        // self.close(tx);
-        // self.search_key = Some(search_key.clone());
-
-        // // convert hash value
-        // let mut hasher = DefaultHasher::new();
-        // search_key.hash(&mut hasher);
-        // let bucket = hasher.finish() % NUM_BUCKETS as u64;
-
-        // // open the appropriate bucket
-        // let tblname = self.idxname.clone() + bucket.to_string().as_str();
-        // self.ts = Some(TableScan::new(tx, tblname, self.layout.clone()));
+    /// open the bucket that the search key hashes to
+    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction) -> DbResult<()> {
+        self.close(tx);
+
+        // convert hash value
+        let mut hasher = DefaultHasher::new();
+        search_key.hash(&mut hasher);
+        let bucket = hasher.finish() % NUM_BUCKETS as u64;
+        self.search_key = Some(search_key);
+
+        // open the appropriate bucket
+        let tblname = self.idxname.clone() + bucket.to_string().as_str();
+        self.ts = Some(TableScan::new(tx, tblname, self.layout.clone())?);
+        Ok(())
+    }
+
+    /// the values of a range hash to different buckets, so only a single value can be searched
+    fn before_range(
+        &mut self,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> DbResult<()> {
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if lo == hi => self.before_first(lo, tx),
+            _ => Err(DbError::Catalog(format!(
+                "hash index {} cannot scan a range",
+                self.idxname
+            ))),
+        }
     }
 
-    fn next(&mut self, tx: &mut Transaction) -> bool {
-        todo!("HashIndex.next()");
-        // match &mut self.ts {
-        //     Some(ts) => {
-        //         while ts.next(tx) {
-        //             if ts.get_value(tx, &"dataval".to_string()) == self.search_key.clone().expect("HashIndex.next() called before before_first()") {
-        //                 return true;
-        //             }
-        //         }
-        //         return false;
-        //     }
-        //     None => {
-        //         panic!("HashIndex.next() called before before_first()");
-        //     }
-        // }
+    /// move to the next index record in the bucket having the search key
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let search_key = self
+            .search_key
+            .clone()
+            .expect("HashIndex.next() called before before_first()");
+        match self.ts.as_mut() {
+            Some(ts) => {
+                while ts.next(tx)? {
+                    if ts.get_value(tx, &"dataval".to_string())? == search_key {
+                        return Ok(true);
+                    }
+                }
+                return Ok(false);
+            }
+            None => {
+                panic!("HashIndex.next() called before before_first()");
+            }
+        }
     }
 
-    fn get_data_rid(&mut self, tx: &mut Transaction) -> RID {
-        todo!("HashIndex.get_data_rid()");
-        // match self.ts.as_mut() {
-        //     Some(ts) => {
-        //         let blknum = ts.get_int(tx, &"block".to_string());
-        //         let id = ts.get_int(tx, &"id".to_string());
-        //         return RID::new(blknum, id);
-        //     }
-        //     None => {
-        //         panic!("HashIndex.get_data_rid() called before before_first()");
-        //     }
-        // }
+    fn get_data_rid(&mut self, tx: &mut Transaction) -> DbResult<RID> {
+        match self.ts.as_mut() {
+            Some(ts) => {
+                let blknum = ts.get_int(tx, &"block".to_string())?;
+                let id = ts.get_int(tx, &"id".to_string())?;
+                return Ok(RID::new(blknum, id));
+            }
+            None => {
+                panic!("HashIndex.get_data_rid() called before before_first()");
+            }
+        }
     }
 
-    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) {
-        todo!("HashIndex.insert()");
-        // self.before_first(val.clone(), tx); // go to the bucket
-        // match self.ts.as_mut() {
-        //     Some(ts) => {
-        //         ts.insert(tx);
-        //         ts.set_int(tx, &"block".to_string(), data_rid.block_number());
-        //         ts.set_int(tx, &"id".to_string(), data_rid.slot_number());
-        //         ts.set_value(tx, &"dataval".to_string(), val.clone());
-        //     }
-        //     None => {
-        //         panic!("HashIndex.insert() called before before_first()");
-        //     }
-        // }
+    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        self.before_first(val.clone(), tx)?; // go to the bucket
+        match self.ts.as_mut() {
+            Some(ts) => {
+                ts.insert(tx)?;
+                ts.set_int(tx, &"block".to_string(), data_rid.block_number())?;
+                ts.set_int(tx, &"id".to_string(), data_rid.slot_number())?;
+                return ts.set_value(tx, &"dataval".to_string(), val);
+            }
+            None => {
+                panic!("HashIndex.insert() called before before_first()");
+            }
+        }
     }
 
-    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) {
-        todo!("HashIndex.delete()");
-        // self.before_first(val.clone(), tx);
-        // while self.next(tx) {
-        //     if self.get_data_rid(tx) == data_rid {
-        //         self.ts.as_mut().expect("HashIndex.delete() called before before_first()").delete(tx);
-        //         return;
-        //     }
-        // }
+    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        self.before_first(val, tx)?;
+        while self.next(tx)? {
+            if self.get_data_rid(tx)? == data_rid {
+                return self
+                    .ts
+                    .as_mut()
+                    .expect("HashIndex.delete() called before before_first()")
+                    .delete(tx);
+            }
+        }
+        Ok(())
     }
 
     fn close(&mut self, tx: &mut Transaction) {
-        todo!("HashIndex.close()");
-        // if let Some(ts) = &mut self.ts {
-        //     ts.close(tx);
-        // }
+        if let Some(mut ts) = self.ts.take() {
+            ts.close(tx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_management::schema::Schema;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_hash_index() -> Result<()> {
+        setup();
+        let db = SimpleDB::new("./db/logtest", "hashindextest", 400, 8)?;
+        let mut tx = db.new_tx();
+
+        let mut sch = Schema::new();
+        sch.add_int_field("block".to_string());
+        sch.add_int_field("id".to_string());
+        sch.add_int_field("dataval".to_string());
+        let layout = Layout::new_from_schema(sch);
+        let mut idx = HashIndex::new("testidx".to_string(), layout);
+
+        for i in 0..20 {
+            idx.insert(Constant::Int(i % 5), RID::new(i / 10, i % 10), &mut tx)?;
+        }
+
+        // every value 3 is found
+        let mut rids = Vec::new();
+        idx.before_first(Constant::Int(3), &mut tx)?;
+        while idx.next(&mut tx)? {
+            rids.push(idx.get_data_rid(&mut tx)?);
+        }
+        assert_eq!(
+            rids,
+            vec![
+                RID::new(0, 3),
+                RID::new(0, 8),
+                RID::new(1, 3),
+                RID::new(1, 8)
+            ]
+        );
+
+        // delete removes only the matching rid
+        idx.delete(Constant::Int(3), RID::new(0, 8), &mut tx)?;
+        let mut n = 0;
+        idx.before_first(Constant::Int(3), &mut tx)?;
+        while idx.next(&mut tx)? {
+            assert_ne!(idx.get_data_rid(&mut tx)?, RID::new(0, 8));
+            n += 1;
+        }
+        assert_eq!(n, 3);
+
+        // missing keys yield nothing
+        idx.before_first(Constant::Int(42), &mut tx)?;
+        assert!(!idx.next(&mut tx)?);
+        idx.close(&mut tx);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
     }
 }
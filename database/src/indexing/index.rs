@@ -1,11 +1,121 @@
+use std::fmt::Display;
+
 use crate::common::Constant;
+use crate::error::DbResult;
+use crate::indexing::btree_index::BTreeIndex;
+use crate::indexing::hash_index::HashIndex;
 use crate::{record_management::rid::RID, transaction_manager::transaction::Transaction};
 
 pub trait Index {
-    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction);
-    fn next(&mut self, tx: &mut Transaction) -> bool;
-    fn get_data_rid(&mut self, tx: &mut Transaction) -> RID;
-    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction);
-    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction);
+    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction) -> DbResult<()>;
+    /// position before the first index record whose dataval is between lo and hi (inclusive)
+    /// None means the range is unbounded on that side
+    /// only an index whose method supports ranges can scan one that is not a single value
+    fn before_range(
+        &mut self,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> DbResult<()>;
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool>;
+    fn get_data_rid(&mut self, tx: &mut Transaction) -> DbResult<RID>;
+    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()>;
+    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()>;
     fn close(&mut self, tx: &mut Transaction);
 }
+
+/// the way an index is organized, stored in idxcat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexMethod {
+    Hash,
+    BTree,
+}
+
+impl IndexMethod {
+    /// parse the name used in idxcat and in CREATE INDEX ... USING
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hash" => Some(IndexMethod::Hash),
+            "btree" => Some(IndexMethod::BTree),
+            _ => None,
+        }
+    }
+
+    /// whether the index can scan the records of a range of values
+    pub fn supports_range(&self) -> bool {
+        match self {
+            IndexMethod::Hash => false,
+            IndexMethod::BTree => true,
+        }
+    }
+}
+
+impl Display for IndexMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexMethod::Hash => write!(f, "hash"),
+            IndexMethod::BTree => write!(f, "btree"),
+        }
+    }
+}
+
+pub enum IndexType {
+    HashIndex(HashIndex),
+    BTreeIndex(BTreeIndex),
+}
+
+impl Index for IndexType {
+    fn before_first(&mut self, search_key: Constant, tx: &mut Transaction) -> DbResult<()> {
+        match self {
+            IndexType::HashIndex(idx) => idx.before_first(search_key, tx),
+            IndexType::BTreeIndex(idx) => idx.before_first(search_key, tx),
+        }
+    }
+
+    fn before_range(
+        &mut self,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> DbResult<()> {
+        match self {
+            IndexType::HashIndex(idx) => idx.before_range(lo, hi, tx),
+            IndexType::BTreeIndex(idx) => idx.before_range(lo, hi, tx),
+        }
+    }
+
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        match self {
+            IndexType::HashIndex(idx) => idx.next(tx),
+            IndexType::BTreeIndex(idx) => idx.next(tx),
+        }
+    }
+
+    fn get_data_rid(&mut self, tx: &mut Transaction) -> DbResult<RID> {
+        match self {
+            IndexType::HashIndex(idx) => idx.get_data_rid(tx),
+            IndexType::BTreeIndex(idx) => idx.get_data_rid(tx),
+        }
+    }
+
+    fn insert(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        match self {
+            IndexType::HashIndex(idx) => idx.insert(val, data_rid, tx),
+            IndexType::BTreeIndex(idx) => idx.insert(val, data_rid, tx),
+        }
+    }
+
+    fn delete(&mut self, val: Constant, data_rid: RID, tx: &mut Transaction) -> DbResult<()> {
+        match self {
+            IndexType::HashIndex(idx) => idx.delete(val, data_rid, tx),
+            IndexType::BTreeIndex(idx) => idx.delete(val, data_rid, tx),
+        }
+    }
+
+    fn close(&mut self, tx: &mut Transaction) {
+        match self {
+            IndexType::HashIndex(idx) => idx.close(tx),
+            IndexType::BTreeIndex(idx) => idx.close(tx),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::{Arc, Mutex};
 
 pub mod buffer_manager;
 pub mod common;
+pub mod error;
 pub mod file_manager;
 pub mod indexing;
 pub mod log_manager;
@@ -10,52 +11,228 @@ pub mod parser;
 pub mod planning;
 pub mod record_management;
 pub mod scans;
+pub mod session;
 pub mod transaction_manager;
 
 pub struct SimpleDB {
-    _log_file: String,
-    _block_size: i32,
-    _num_buffer: i32,
     file_mgr: file_manager::file_mgr::FileMgr,
     log_mgr: Arc<Mutex<log_manager::log_mgr::LogMgr>>,
     buffer_mgr: Arc<Mutex<buffer_manager::buffer_mgr::BufferMgr>>,
+    lock_table: Arc<transaction_manager::concurrency_manager::lock_table::LockTable>,
+    versions: Arc<transaction_manager::version_manager::VersionMgr>,
+    checkpointer: Option<transaction_manager::checkpointer::Checkpointer>,
+    /// the catalog shared by the planners of every session, none without a catalog
+    metadata_mgr: Option<Arc<Mutex<metadata_management::metadata_mgr::MetadataMgr>>>,
 }
 
-impl SimpleDB {
-    pub fn new(_log_file: String, _block_size: i32, _num_buffer: i32) -> Self {
-        let file_mgr =
-            file_manager::file_mgr::FileMgr::new("./db/logtest".to_string(), _block_size);
-        let log_mgr = Arc::new(Mutex::new(
-            log_manager::log_mgr::LogMgr::new(file_mgr.clone(), _log_file.clone()).unwrap(),
-        ));
+/// the settings of a database to open, e.g.
+/// SimpleDB::builder().dir("./db/shop").block_size(400).buffers(8).open()
+pub struct SimpleDBBuilder {
+    dir: String,
+    log_file: String,
+    block_size: i32,
+    num_buffer: i32,
+}
+
+impl SimpleDBBuilder {
+    /// the directory of the database files, created if missing
+    pub fn dir(mut self, dir: &str) -> Self {
+        self.dir = dir.to_string();
+        return self;
+    }
+
+    /// the name of the log file in the directory
+    pub fn log_file(mut self, log_file: &str) -> Self {
+        self.log_file = log_file.to_string();
+        return self;
+    }
+
+    pub fn block_size(mut self, block_size: i32) -> Self {
+        self.block_size = block_size;
+        return self;
+    }
+
+    /// the number of buffers of the buffer pool
+    pub fn buffers(mut self, num_buffer: i32) -> Self {
+        self.num_buffer = num_buffer;
+        return self;
+    }
+
+    /// open the database with a catalog
+    /// a new directory gets an empty catalog, an existing one is recovered first,
+    /// undoing the transactions that did not commit before the last shutdown
+    pub fn open(self) -> error::DbResult<SimpleDB> {
+        let mut db = self.start()?;
+        let is_new = db.file_mgr.is_new();
+        if !is_new {
+            // the numbers of this process must not collide with those already on disk
+            let last_txnum = transaction_manager::recovery_manager::RecoveryMgr::last_tx_number(
+                &mut db.log_mgr.lock().unwrap(),
+            )?;
+            transaction_manager::transaction::Transaction::start_tx_numbers_after(last_txnum);
+        }
+        let mut tx = db.new_tx();
+        let recovered = if is_new { Ok(()) } else { tx.recovery() };
+        let mdm = recovered
+            .and_then(|_| metadata_management::metadata_mgr::MetadataMgr::new(is_new, &mut tx));
+        let mdm = match mdm {
+            Ok(mdm) => mdm,
+            Err(e) => {
+                // the error is reported rather than a failure to roll back
+                let _ = tx.roll_back();
+                return Err(e);
+            }
+        };
+        tx.commit()?;
+        db.metadata_mgr = Some(Arc::new(Mutex::new(mdm)));
+        return Ok(db);
+    }
+
+    /// the managers only, without recovery or a catalog
+    fn start(self) -> error::DbResult<SimpleDB> {
+        let file_mgr = file_manager::file_mgr::FileMgr::new(self.dir, self.block_size);
+        let log_mgr = Arc::new(Mutex::new(log_manager::log_mgr::LogMgr::new(
+            file_mgr.clone(),
+            self.log_file.clone(),
+        )?));
         let buffer_mgr = Arc::new(Mutex::new(buffer_manager::buffer_mgr::BufferMgr::new(
             file_mgr.clone(),
             log_mgr.clone(),
-            _num_buffer,
+            self.num_buffer,
         )));
 
-        return Self {
-            _log_file,
-            _block_size,
-            _num_buffer,
+        let lock_table =
+            Arc::new(transaction_manager::concurrency_manager::lock_table::LockTable::new());
+        let versions = Arc::new(transaction_manager::version_manager::VersionMgr::new());
+
+        return Ok(SimpleDB {
             file_mgr,
             log_mgr,
             buffer_mgr,
+            lock_table,
+            versions,
+            checkpointer: None,
+            metadata_mgr: None,
+        });
+    }
+}
+
+impl SimpleDB {
+    /// the defaults are the directory ./db, 400 byte blocks and 8 buffers
+    pub fn builder() -> SimpleDBBuilder {
+        return SimpleDBBuilder {
+            dir: "./db".to_string(),
+            log_file: "simpledb.log".to_string(),
+            block_size: 400,
+            num_buffer: 8,
         };
     }
 
+    /// the database in dir without recovery or a catalog, for testing the layers below the planner
+    /// use the builder to open a database for running SQL
+    pub fn new(
+        dir: &str,
+        log_file: &str,
+        block_size: i32,
+        num_buffer: i32,
+    ) -> error::DbResult<Self> {
+        return Self::builder()
+            .dir(dir)
+            .log_file(log_file)
+            .block_size(block_size)
+            .buffers(num_buffer)
+            .start();
+    }
+
+    /// start a locking transaction
     pub fn new_tx(&self) -> transaction_manager::transaction::Transaction {
+        return self.new_tx_with_mode(transaction_manager::version_manager::TxMode::Locking);
+    }
+
+    /// start a transaction whose reads either take slocks or see a snapshot
+    pub fn new_tx_with_mode(
+        &self,
+        mode: transaction_manager::version_manager::TxMode,
+    ) -> transaction_manager::transaction::Transaction {
         return transaction_manager::transaction::Transaction::new(
             self.file_mgr.clone(),
             self.log_mgr.clone(),
             self.buffer_mgr.clone(),
+            self.lock_table.clone(),
+            self.versions.clone(),
+            mode,
         );
     }
 
-    pub fn new_metadata_mgr(
+    /// choose how the transactions of the database deal with deadlocks
+    /// @param max_time: how long a transaction waits for a lock before it is aborted
+    pub fn set_lock_policy(
+        &self,
+        policy: transaction_manager::concurrency_manager::lock_table::DeadlockPolicy,
+        max_time: std::time::Duration,
+    ) {
+        self.lock_table.set_policy(policy, max_time);
+    }
+
+    /// write a non-quiescent checkpoint now, while the transactions keep running
+    pub fn checkpoint(&self) -> error::DbResult<()> {
+        transaction_manager::recovery_manager::RecoveryMgr::checkpoint(
+            &self.log_mgr,
+            &self.buffer_mgr,
+            &self.versions,
+        )?;
+        Ok(())
+    }
+
+    /// checkpoint in the background every time the log grows by log_blocks blocks
+    /// @param period: how often the size of the log is checked
+    pub fn start_checkpointer(&mut self, log_blocks: i32, period: std::time::Duration) {
+        // the previous checkpointer, if any, is stopped when it is dropped
+        self.checkpointer = Some(transaction_manager::checkpointer::Checkpointer::start(
+            self.log_mgr.clone(),
+            self.buffer_mgr.clone(),
+            self.versions.clone(),
+            log_blocks,
+            period,
+        ));
+    }
+
+    /// choose what the checkpoints do with the log segments recovery no longer needs
+    pub fn set_log_retention(&self, retention: log_manager::log_mgr::LogRetention) {
+        self.log_mgr.lock().unwrap().set_retention(retention);
+    }
+
+    /// run a script, such as a schema migration, in a session of its own
+    /// the statements autocommit unless the script groups them with BEGIN and COMMIT,
+    /// and a transaction the script leaves open is rolled back
+    /// @return: the result of each statement, up to the first one that fails
+    pub fn execute_script(
         &self,
-        tx: &mut transaction_manager::transaction::Transaction,
-    ) -> metadata_management::metadata_mgr::MetadataMgr {
-        return metadata_management::metadata_mgr::MetadataMgr::new(true, tx);
+        script: &str,
+    ) -> error::DbResult<Vec<session::result_set::QueryResult>> {
+        let mut session = session::Session::new(self)?;
+        return session.execute_script(script.to_string());
+    }
+
+    /// the catalog of a database opened by the builder
+    pub fn metadata_mgr(
+        &self,
+    ) -> error::DbResult<Arc<Mutex<metadata_management::metadata_mgr::MetadataMgr>>> {
+        match &self.metadata_mgr {
+            Some(mdm) => return Ok(mdm.clone()),
+            None => {
+                return Err(error::DbError::Catalog(
+                    "the database was opened without a catalog".to_string(),
+                ))
+            }
+        }
+    }
+
+    pub fn new_planner(&self) -> error::DbResult<planning::planner::Planner> {
+        let mdm = self.metadata_mgr()?;
+        return Ok(planning::planner::Planner::new(
+            planning::query_planner::QueryPlanner::new(mdm.clone()),
+            planning::update_planner::UpdatePlanner::new(mdm),
+        ));
     }
 }
@@ -1,66 +1,97 @@
+use crate::error::DbResult;
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::file_mgr::FileMgr;
 use crate::file_manager::page::Page;
 use std::iter::Iterator;
 
+/// reads the log records from the newest to the oldest,
+/// moving from the first block of a segment to the last block of the previous one
+/// a block that cannot be read ends the iteration with an error
 pub struct LogIterator {
     fm: FileMgr,
     blk: BlockId,
     p: Page,
     current_pos: i32,
     boundary: i32,
+    /// the file names of the segments before the current one, oldest first
+    segments: Vec<String>,
+    /// the lsn of the next record
+    next_lsn: i32,
 }
 
 impl Iterator for LogIterator {
-    type Item = Vec<u8>;
+    type Item = DbResult<Vec<u8>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let has_next = self.current_pos < self.fm.block_size() || self.blk.number() > 0;
-        if !has_next {
-            return None;
-        }
-        if self.current_pos == self.fm.block_size() {
-            if self.blk.number() == 0 {
-                panic!("the end of the log has been reached");
+        while self.current_pos == self.fm.block_size() {
+            if self.blk.number() > 0 {
+                self.blk = BlockId::new(self.blk.filename(), self.blk.number() - 1);
+            } else if let Some(segment) = self.segments.pop() {
+                let size = match self.fm.length(segment.clone()) {
+                    Ok(size) => size,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                self.blk = BlockId::new(segment, size - 1);
+            } else {
+                // the start of the log has been reached
+                return None;
+            }
+            if let Err(e) = self.move_to_block() {
+                return Some(Err(e));
             }
-            self.blk = BlockId::new(self.blk.filename(), self.blk.number() - 1);
-            self.move_to_block();
         }
-        let rec = self
-            .p
-            .get_bytes(self.current_pos)
-            .expect("Error reading bytes");
+        let rec = match self.p.get_bytes(self.current_pos) {
+            Ok(rec) => rec,
+            Err(e) => return Some(Err(e.into())),
+        };
         self.current_pos += rec.len() as i32 + std::mem::size_of::<i32>() as i32;
-        Some(rec)
+        self.next_lsn -= 1;
+        Some(Ok(rec))
     }
 }
 
 impl LogIterator {
-    pub fn new(mut fm: FileMgr, blk: BlockId) -> Self {
+    /// @param segments: the file names of the segments before the one of blk, oldest first
+    /// @param blk: the last block of the log
+    /// @param latest_lsn: the lsn of the last record of the log
+    pub fn new(
+        mut fm: FileMgr,
+        segments: Vec<String>,
+        blk: BlockId,
+        latest_lsn: i32,
+    ) -> DbResult<Self> {
         let mut p = Page::new_log(vec![0; fm.block_size() as usize]);
-        fm.read(&blk, &mut p).expect("Error reading block");
-        let boundary = p.get_int(0).expect("Error reading boundary");
+        fm.read(&blk, &mut p)?;
+        let boundary = p.get_int(0)?;
         let current_pos = boundary;
-        Self {
+        Ok(Self {
             fm,
             blk,
             p,
             current_pos,
             boundary,
-        }
+            segments,
+            next_lsn: latest_lsn,
+        })
     }
 
     #[allow(dead_code)]
     pub fn has_next(&mut self) -> bool {
-        self.current_pos < self.fm.block_size() || self.blk.number() > 0
+        self.current_pos < self.fm.block_size()
+            || self.blk.number() > 0
+            || !self.segments.is_empty()
     }
 
-    fn move_to_block(&mut self) {
-        self.fm
-            .read(&self.blk, &mut self.p)
-            .expect("Error reading block");
-        self.boundary = self.p.get_int(0).expect("Error reading boundary");
+    /// the lsn of the record returned last by next
+    pub fn lsn(&self) -> i32 {
+        self.next_lsn + 1
+    }
+
+    fn move_to_block(&mut self) -> DbResult<()> {
+        self.fm.read(&self.blk, &mut self.p)?;
+        self.boundary = self.p.get_int(0)?;
         self.current_pos = self.boundary;
+        Ok(())
     }
 }
 
@@ -92,7 +123,7 @@ mod tests {
 
         // test new
         {
-            let mut log_iter = LogIterator::new(fm.clone(), blk.clone());
+            let mut log_iter = LogIterator::new(fm.clone(), Vec::new(), blk.clone(), 0)?;
             assert_eq!(log_iter.current_pos, 16);
             assert_eq!(log_iter.boundary, 16);
             assert_eq!(log_iter.p.get_bytes(0).unwrap(), vec![0; 16]);
@@ -1,9 +1,28 @@
+use crate::error::DbResult;
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::file_mgr::FileMgr;
 use crate::file_manager::page::Page;
 use crate::file_manager::FileManagerError;
 use crate::log_manager::log_iterator::LogIterator;
 
+/// the number of blocks of a log segment, unless set otherwise
+pub const SEGMENT_BLOCKS: i32 = 64;
+
+/// what becomes of the log segments that recovery no longer needs
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogRetention {
+    /// keep every segment
+    Keep,
+    /// delete the segments
+    Truncate,
+    /// move the segments into the given subdirectory of the database directory
+    Archive(String),
+}
+
+/// the log is split into segment files named log_file.<lsn of the first record of the segment>
+/// a new segment is started once the current one is full, so that the segments recovery
+/// no longer needs can be deleted or archived as a whole
+/// lsns keep increasing across segments and restarts
 #[derive(Clone, Debug)]
 pub struct LogMgr {
     fm: FileMgr,
@@ -12,28 +31,44 @@ pub struct LogMgr {
     current_blk: BlockId,
     latest_lsn: i32,
     last_saved_lsn: i32,
+    /// the first lsn of each segment, oldest first; the last segment is the current one
+    segments: Vec<i32>,
+    segment_blocks: i32,
+    blocks_written: i32,
+    retention: LogRetention,
 }
 
 impl LogMgr {
     /// Creates a new log manager for the specified log file.
-    /// If the log file does not yet exist, it is created with an empty first block.
+    /// If the log does not yet exist, its first segment is created with an empty first block.
     /// and set the block size to that of the specified file manager.
+    /// Otherwise the lsns continue from the last record of the last segment.
     pub fn new(mut fm: FileMgr, log_file: String) -> Result<Self, FileManagerError> {
         let mut log_page = Page::new_log(vec![0; fm.block_size() as usize]);
-        let log_size = fm.length(log_file.clone())?;
+        let mut segments = Self::find_segments(&fm, &log_file);
         let current_blk;
-        if log_size == 0 {
-            // log file does not exist
-            match Self::append_new_block(&mut fm, &log_file, &mut log_page) {
+        let latest_lsn;
+        match segments.last() {
+            None => {
+                // log file does not exist
+                segments.push(1);
+                let filename = Self::segment_name(&log_file, 1);
                 // create new block (appended block size to top of block) and set as current block
-                Ok(blk) => current_blk = blk,
-                Err(e) => return Err(e),
+                current_blk = Self::append_new_block(&mut fm, &filename, &mut log_page)?;
+                latest_lsn = 0;
             }
-        } else {
-            current_blk = BlockId::new(log_file.clone(), log_size - 1); // set current block to last block in log file
-            match fm.read(&current_blk, &mut log_page) {
-                Ok(_) => (),
-                Err(e) => return Err(e),
+            Some(first_lsn) => {
+                let filename = Self::segment_name(&log_file, *first_lsn);
+                let log_size = fm.length(filename.clone())?;
+                // count the records of the last segment, which are numbered from its first lsn
+                let mut count = 0;
+                for blknum in 0..log_size {
+                    fm.read(&BlockId::new(filename.clone(), blknum), &mut log_page)?;
+                    count += Self::count_records(&mut log_page, fm.block_size());
+                }
+                // the last block stays in the log page and is the current block
+                current_blk = BlockId::new(filename, log_size - 1);
+                latest_lsn = first_lsn - 1 + count;
             }
         }
         Ok(Self {
@@ -41,8 +76,12 @@ impl LogMgr {
             log_file,
             log_page,
             current_blk,
-            latest_lsn: 0,
-            last_saved_lsn: 0,
+            latest_lsn,
+            last_saved_lsn: latest_lsn,
+            segments,
+            segment_blocks: SEGMENT_BLOCKS,
+            blocks_written: 0,
+            retention: LogRetention::Keep,
         })
     }
 
@@ -51,7 +90,8 @@ impl LogMgr {
     /// 1. get remaining space in current block
     /// 2. get bytes needed to store logrec. add 4 bytes for size of logrec
     /// 3. if not enough space in current block
-    ///    a. create new block (appended block size to top of block) and set as current block
+    ///    a. create new block (appended block size to top of block) in a new segment if the current one is full,
+    ///    and set as current block
     ///    b. get remaining space in current block
     /// 4. get position to write logrec, backlog from end of block
     /// 5. write logrec to log page
@@ -66,11 +106,19 @@ impl LogMgr {
         if boundary - bytes_needed < std::mem::size_of::<i32>() as i32 {
             // if not enough space in current block
             self.flush_page();
+            let filename = if self.current_blk.number() + 1 >= self.segment_blocks {
+                // the record is the first of a new segment
+                let first_lsn = self.latest_lsn + 1;
+                self.segments.push(first_lsn);
+                Self::segment_name(&self.log_file, first_lsn)
+            } else {
+                self.current_blk.filename()
+            };
             // create new block (appended block size to top of block) and set as current block
             // log_page isn't 0 padding, but, it's ok because append_new_block will reset the cursor and will overwrite this page
-            self.current_blk =
-                Self::append_new_block(&mut self.fm, &self.log_file, &mut self.log_page)
-                    .expect("append new block error"); // create new block (appended block size to top of block) and set as current block
+            self.current_blk = Self::append_new_block(&mut self.fm, &filename, &mut self.log_page)
+                .expect("append new block error"); // create new block (appended block size to top of block) and set as current block
+            self.blocks_written += 1;
             boundary = self.log_page.get_int(0).expect("io Error"); // get remaining space in current block
         }
 
@@ -89,18 +137,125 @@ impl LogMgr {
         }
     }
 
-    /// flush all log records to disk and return iterator to read log records
-    pub fn iterator(&mut self) -> LogIterator {
+    /// flush all log records to disk and return iterator to read log records, newest first
+    pub fn iterator(&mut self) -> DbResult<LogIterator> {
         self.flush_page();
-        LogIterator::new(self.fm.clone(), self.current_blk.clone())
+        let older_segments = self.segment_names()[..self.segments.len() - 1].to_vec();
+        LogIterator::new(
+            self.fm.clone(),
+            older_segments,
+            self.current_blk.clone(),
+            self.latest_lsn,
+        )
+    }
+
+    pub fn latest_lsn(&self) -> i32 {
+        self.latest_lsn
+    }
+
+    /// the number of blocks appended to the log since the log manager was created
+    pub fn blocks_written(&self) -> i32 {
+        self.blocks_written
+    }
+
+    /// the file names of the segments, oldest first
+    pub fn segment_names(&self) -> Vec<String> {
+        self.segments
+            .iter()
+            .map(|first_lsn| Self::segment_name(&self.log_file, *first_lsn))
+            .collect()
+    }
+
+    /// the number of blocks of the segments started from now on
+    pub fn set_segment_blocks(&mut self, segment_blocks: i32) {
+        self.segment_blocks = segment_blocks;
+    }
+
+    pub fn retention(&self) -> &LogRetention {
+        &self.retention
+    }
+
+    /// choose what release does with the segments that are no longer needed
+    pub fn set_retention(&mut self, retention: LogRetention) {
+        self.retention = retention;
+    }
+
+    /// delete the segments holding only records older than oldest_lsn
+    /// the current segment is never deleted
+    /// @return: the number of deleted segments
+    pub fn truncate(&mut self, oldest_lsn: i32) -> Result<i32, FileManagerError> {
+        self.remove_segments(oldest_lsn, None)
+    }
+
+    /// move the segments holding only records older than oldest_lsn into a subdirectory
+    /// the current segment is never moved
+    /// @return: the number of archived segments
+    pub fn archive(&mut self, oldest_lsn: i32, dirname: String) -> Result<i32, FileManagerError> {
+        self.remove_segments(oldest_lsn, Some(dirname))
+    }
+
+    /// truncate, archive or keep the segments older than oldest_lsn, as the retention says
+    /// @return: the number of segments removed from the log
+    pub fn release(&mut self, oldest_lsn: i32) -> Result<i32, FileManagerError> {
+        match self.retention.clone() {
+            LogRetention::Keep => Ok(0),
+            LogRetention::Truncate => self.truncate(oldest_lsn),
+            LogRetention::Archive(dirname) => self.archive(oldest_lsn, dirname),
+        }
+    }
+
+    fn remove_segments(
+        &mut self,
+        oldest_lsn: i32,
+        dirname: Option<String>,
+    ) -> Result<i32, FileManagerError> {
+        let mut removed = 0;
+        // a segment ends right before the next one starts
+        while self.segments.len() > 1 && self.segments[1] <= oldest_lsn {
+            let filename = Self::segment_name(&self.log_file, self.segments[0]);
+            match dirname.clone() {
+                Some(dirname) => self.fm.move_to(filename, dirname)?,
+                None => self.fm.remove(filename)?,
+            }
+            self.segments.remove(0);
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    fn segment_name(log_file: &String, first_lsn: i32) -> String {
+        format!("{}.{}", log_file, first_lsn)
+    }
+
+    /// the first lsns of the segments of the log in the database directory, oldest first
+    fn find_segments(fm: &FileMgr, log_file: &String) -> Vec<i32> {
+        let prefix = format!("{}.", log_file);
+        let mut segments: Vec<i32> = fm
+            .file_names()
+            .iter()
+            .filter_map(|name| name.strip_prefix(&prefix)?.parse().ok())
+            .collect();
+        segments.sort();
+        segments
+    }
+
+    /// the number of records in a log page, which are stored from the boundary to the end
+    fn count_records(p: &mut Page, block_size: i32) -> i32 {
+        let mut count = 0;
+        let mut pos = p.get_int(0).expect("io Error");
+        while pos < block_size {
+            pos += p.get_int(pos).expect("io Error") + std::mem::size_of::<i32>() as i32;
+            count += 1;
+        }
+        count
     }
 
     fn append_new_block(
         fm: &mut FileMgr,
-        log_file: &String,
+        filename: &String,
         log_page: &mut Page,
     ) -> Result<BlockId, FileManagerError> {
-        let blk = match fm.append(log_file.clone()) {
+        let blk = match fm.append(filename.clone()) {
             Ok(blk) => blk,
             Err(e) => return Err(e),
         };
@@ -145,6 +300,8 @@ mod tests {
     fn test_log_mgr() -> Result<()> {
         let db_directory = "./db/logtest".to_string();
         let log_file = "testfile".to_string();
+        // the first segment starts with lsn 1
+        let segment = "testfile.1".to_string();
         let mut path = PathBuf::from(db_directory.clone());
         path.push(log_file.clone());
         // delete ./db/logtest
@@ -157,7 +314,7 @@ mod tests {
             let mut fm = FileMgr::new(db_directory.to_string(), 20);
             // log_size == 0
             let log_mgr = LogMgr::new(fm.clone(), log_file.clone()).unwrap();
-            assert_eq!(log_mgr.current_blk, BlockId::new(log_file.clone(), 0));
+            assert_eq!(log_mgr.current_blk, BlockId::new(segment.clone(), 0));
             assert_eq!(log_mgr.latest_lsn, 0);
             assert_eq!(log_mgr.last_saved_lsn, 0);
 
@@ -188,7 +345,7 @@ mod tests {
             // check log file
             log_mgr.flush(lsn);
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new(log_file.clone(), 0);
+            let blk = BlockId::new(segment.clone(), 0);
             fm.read(&blk, &mut p).unwrap();
             let len = p.get_int(0).unwrap();
             assert_eq!(p.get_bytes(len).unwrap(), vec![1, 2, 3, 4]);
@@ -198,15 +355,17 @@ mod tests {
         {
             let mut fm = FileMgr::new(db_directory.to_string(), 20);
             let mut log_mgr = LogMgr::new(fm.clone(), log_file.clone()).unwrap();
+            // the lsns continue from the record written before
+            assert_eq!(log_mgr.latest_lsn, 1);
+            assert_eq!(log_mgr.last_saved_lsn, 1);
             let logrec = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-            // log_mgr.append(logrec.clone());
             let lsn = log_mgr.append(logrec.clone());
-            assert_eq!(lsn, 1);
-            assert_eq!(log_mgr.latest_lsn, 1);
-            assert_eq!(log_mgr.last_saved_lsn, 0);
+            assert_eq!(lsn, 2);
+            assert_eq!(log_mgr.latest_lsn, 2);
+            assert_eq!(log_mgr.last_saved_lsn, 1);
             // check flush page at block 0
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new(log_file.clone(), 0);
+            let blk = BlockId::new(segment.clone(), 0);
             fm.read(&blk, &mut p).unwrap();
             let len = p.get_int(0).unwrap();
             assert_eq!(p.get_bytes(len).unwrap(), vec![1, 2, 3, 4]);
@@ -221,7 +380,7 @@ mod tests {
             // flush page and check log file
             log_mgr.flush(lsn);
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new(log_file.clone(), 1);
+            let blk = BlockId::new(segment.clone(), 1);
             fm.read(&blk, &mut p).unwrap();
             let len = p.get_int(0).unwrap();
             assert_eq!(p.get_bytes(len).unwrap(), vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
@@ -230,19 +389,68 @@ mod tests {
         // test new (already exist block)
         {
             let fm = FileMgr::new(db_directory.to_string(), 20);
-            // log_size == 2
+            // log_size == 2, in a segment starting with lsn 5
             let mut path = PathBuf::from(db_directory.clone());
-            path.push(log_file.clone());
+            path.push("otherlog.5");
             File::create(path.clone()).unwrap(); // create file
             let mut file = OpenOptions::new()
                 .write(true)
                 .read(true)
                 .open(path.clone())
                 .unwrap();
-            file.write_all(&vec![0; 40]).unwrap();
+            // two empty blocks, whose boundary is the block size
+            file.write_all(&[vec![0, 0, 0, 20], vec![0; 16]].concat().repeat(2))
+                .unwrap();
 
-            let log_mgr = LogMgr::new(fm.clone(), log_file.clone()).unwrap();
-            assert_eq!(log_mgr.current_blk, BlockId::new(log_file.clone(), 1));
+            let log_mgr = LogMgr::new(fm.clone(), "otherlog".to_string()).unwrap();
+            assert_eq!(
+                log_mgr.current_blk,
+                BlockId::new("otherlog.5".to_string(), 1)
+            );
+            assert_eq!(log_mgr.latest_lsn, 4);
+        }
+
+        // test segments
+        {
+            let fm = FileMgr::new(db_directory.to_string(), 20);
+            let mut log_mgr = LogMgr::new(fm.clone(), "seglog".to_string()).unwrap();
+            log_mgr.set_segment_blocks(2);
+            // one 13 byte record per block, so a segment holds 2 records
+            for i in 1..=5 {
+                assert_eq!(log_mgr.append(vec![i; 9]), i as i32);
+            }
+            assert_eq!(
+                log_mgr.segment_names(),
+                vec!["seglog.1", "seglog.3", "seglog.5"]
+            );
+
+            // the iterator reads back across the segments
+            let mut iter = log_mgr.iterator()?;
+            for i in (1..=5).rev() {
+                assert_eq!(iter.next().transpose()?, Some(vec![i; 9]));
+                assert_eq!(iter.lsn(), i as i32);
+            }
+            assert_eq!(iter.next().transpose()?, None);
+
+            // the lsns keep increasing after a restart
+            let mut log_mgr = LogMgr::new(fm.clone(), "seglog".to_string()).unwrap();
+            assert_eq!(log_mgr.append(vec![6; 9]), 6);
+
+            // only the segments holding no record from lsn 4 onward are removed
+            assert_eq!(log_mgr.archive(4, "archive".to_string()).unwrap(), 1);
+            assert_eq!(log_mgr.segment_names(), vec!["seglog.3", "seglog.5"]);
+            let mut archived = PathBuf::from(db_directory.clone());
+            archived.push("archive");
+            archived.push("seglog.1");
+            assert!(fs::metadata(archived).is_ok());
+            assert_eq!(log_mgr.truncate(100).unwrap(), 1);
+            assert_eq!(log_mgr.segment_names(), vec!["seglog.5"]);
+            assert!(!fm.file_names().contains(&"seglog.3".to_string()));
+
+            let mut iter = log_mgr.iterator()?;
+            assert_eq!(iter.next().transpose()?, Some(vec![6; 9]));
+            assert_eq!(iter.next().transpose()?, Some(vec![5; 9]));
+            assert_eq!(iter.next().transpose()?, None);
         }
 
         teardown("./db".to_string());
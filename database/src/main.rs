@@ -1,26 +1,322 @@
-use database::parser::parser::{Execute, Parser};
+use database::common::Constant;
+use database::error::{DbError, DbResult};
+use database::parser::parser::Parser;
+use database::parser::tokenize::Lexer;
+use database::record_management::schema::{Schema, Type};
+use database::session::result_set::{QueryResult, ResultSet};
+use database::session::Session;
 use database::SimpleDB;
-use std::io::{self, BufRead};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::time::Instant;
+
+const USAGE: &str = "usage: database [-c SQL] [DIR [FILE]]
+  DIR   the database directory, ./db by default
+  FILE  a script to run instead of reading statements interactively
+  -c    run the statements of SQL instead of reading them interactively";
+
+const HELP: &str = ".tables          list the tables
+.schema [TABLE]  show the create statement of a table, or of every table
+.indexes         list the indexes
+.stats           show the blocks and records the planner assumes per table
+.help            show this message
+.quit, .exit     leave the shell";
+
+/// the tables of the catalog itself, hidden from .tables and .schema
+const CATALOG_TABLES: [&str; 4] = ["tblcat", "fldcat", "idxcat", "viewcat"];
 
 fn main() {
-    let stdin = io::stdin();
-    let mut reader = stdin.lock();
-    let db = SimpleDB::new("logfile".to_string(), 400, 8);
+    let mut command = None;
+    let mut paths = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" => match args.next() {
+                Some(sql) => command = Some(sql),
+                None => exit_with_usage(),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => exit_with_usage(),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() > 2 || (command.is_some() && paths.len() > 1) {
+        exit_with_usage();
+    }
+    let mut builder = SimpleDB::builder();
+    if let Some(dir) = paths.first() {
+        builder = builder.dir(dir);
+    }
+    let db = match builder.open() {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut session = match Session::new(&db) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // a script given on the command line stops at the first error
+    let script = match (command, paths.get(1)) {
+        (Some(sql), _) => Some(sql),
+        (None, Some(file)) => match std::fs::read_to_string(file) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => None,
+    };
+    match script {
+        Some(text) => {
+            if !run(&mut session, text, false) {
+                std::process::exit(1);
+            }
+        }
+        None => interactive(&mut session),
+    }
+}
 
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
+/// read statements with line editing until .quit or the end of input
+/// a statement can span several lines and ends with a semicolon
+fn interactive(session: &mut Session) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let history = std::env::var("HOME")
+        .map(|home| std::path::Path::new(&home).join(".simpledb_history"))
+        .ok();
+    if let Some(path) = &history {
+        // there is no history file on the first run
+        let _ = editor.load_history(path);
+    }
+
+    println!("Enter \".help\" for usage hints.");
+    let mut input = String::new();
     loop {
-        let mut input = String::new();
-        if let Err(_) = reader.read_line(&mut input) {
-            break;
+        let prompt = if input.is_empty() {
+            "simpledb> "
+        } else {
+            "     ...> "
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // ctrl-c drops the statement being typed
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        };
+
+        if input.is_empty() && line.trim_start().starts_with('.') {
+            let _ = editor.add_history_entry(line.as_str());
+            if !dot_command(session, line.trim()) {
+                break;
+            }
+            continue;
+        }
+        input.push_str(&line);
+        input.push('\n');
+        if !Lexer::ends_statement(&input) {
+            continue;
         }
+        let text = std::mem::take(&mut input);
+        let _ = editor.add_history_entry(text.trim_end());
+        run(session, text, true);
+    }
 
-        if input.trim().is_empty() {
-            break;
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("could not save the history: {}", e);
         }
+    }
+}
 
-        // remove \n
-        input = input.trim().to_string();
+/// run each statement of the text and print its result and how long it took
+/// @param keep_going: whether to run the remaining statements after an error
+/// @return whether every statement succeeded
+fn run(session: &mut Session, text: String, keep_going: bool) -> bool {
+    let objects = match Parser::new(text).statements() {
+        Ok(objects) => objects,
+        Err(e) => {
+            print_error(e);
+            return false;
+        }
+    };
+    let mut ok = true;
+    for obj in objects {
+        let start = Instant::now();
+        let result = session.execute_statement(obj);
+        let elapsed = start.elapsed();
+        match result {
+            Ok(result) => print_result(result),
+            Err(e) => {
+                print_error(e);
+                ok = false;
+                if !keep_going {
+                    return false;
+                }
+            }
+        }
+        println!("Time: {:.3} ms", elapsed.as_secs_f64() * 1000.0);
+    }
+    return ok;
+}
+
+fn print_result(result: QueryResult) {
+    match result {
+        QueryResult::Rows(rs) => println!("{}", rs),
+        QueryResult::Affected(n) => println!("{} records processed", n),
+    }
+}
+
+fn print_error(e: DbError) {
+    match e {
+        // show where the statement went wrong
+        DbError::Parse(e) => eprintln!("syntax error at {}", e.render()),
+        e => eprintln!("{}", e),
+    }
+}
+
+/// @return false when the shell should quit
+fn dot_command(session: &mut Session, line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let arg = words.next();
+    let result = match name {
+        ".quit" | ".exit" => return false,
+        ".help" => {
+            println!("{}", HELP);
+            Ok(())
+        }
+        ".tables" => user_tables(session).map(|tables| {
+            for table in tables {
+                println!("{}", table);
+            }
+        }),
+        ".schema" => schema(session, arg),
+        ".indexes" => indexes(session),
+        ".stats" => stats(session),
+        _ => {
+            eprintln!("unknown command {}, enter \".help\" for usage hints", name);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        print_error(e);
+    }
+    return true;
+}
+
+/// the names of the tables that are not part of the catalog, sorted
+fn user_tables(session: &mut Session) -> DbResult<Vec<String>> {
+    let rs = session.execute_query("select tblname from tblcat".to_string())?;
+    let mut tables: Vec<String> = rs
+        .rows()
+        .map(|row| row[0].to_string())
+        .filter(|t| !CATALOG_TABLES.contains(&t.as_str()))
+        .collect();
+    tables.sort();
+    tables.dedup();
+    return Ok(tables);
+}
+
+/// print a create table statement for the table, or for every table
+fn schema(session: &mut Session, table: Option<&str>) -> DbResult<()> {
+    let tables = match table {
+        Some(t) => vec![t.to_string()],
+        None => user_tables(session)?,
+    };
+    for t in tables {
+        // the name is spliced into a string literal, so its quotes are doubled
+        let rs = session.execute_query(format!(
+            "select fldname, type, length, offset from fldcat where tblname = '{}'",
+            t.replace('\'', "''")
+        ))?;
+        if rs.is_empty() {
+            eprintln!("no such table: {}", t);
+            continue;
+        }
+        let mut fields: Vec<&Vec<Constant>> = rs.rows().collect();
+        fields.sort_by_key(|row| int(&row[3]));
+        let mut defs = Vec::new();
+        for row in fields {
+            defs.push(match Type::try_from(int(&row[1]))? {
+                Type::INTEGER => format!("{} int", row[0]),
+                Type::VARCHAR => format!("{} varchar({})", row[0], row[2]),
+            });
+        }
+        println!("create table {} ({});", t, defs.join(", "));
+    }
+    return Ok(());
+}
+
+/// the value of an int field of a catalog table
+fn int(val: &Constant) -> i32 {
+    match val {
+        Constant::Int(n) => *n,
+        _ => 0,
+    }
+}
+
+fn indexes(session: &mut Session) -> DbResult<()> {
+    let rs = session.execute_query(
+        "select indexname, tablename, fieldname, indextype from idxcat".to_string(),
+    )?;
+    let mut lines: Vec<String> = rs
+        .rows()
+        .map(|row| {
+            format!(
+                "create index {} on {} ({}) using {};",
+                row[0], row[1], row[2], row[3]
+            )
+        })
+        .collect();
+    lines.sort();
+    for line in lines {
+        println!("{}", line);
+    }
+    return Ok(());
+}
 
-        let parser = Parser::new(input);
-        parser.sql().execute(&db);
+fn stats(session: &mut Session) -> DbResult<()> {
+    let mut schema = Schema::new();
+    schema.add_string_field("table".to_string(), 16);
+    schema.add_int_field("blocks".to_string());
+    schema.add_int_field("records".to_string());
+    let mut rows = vec![];
+    for t in user_tables(session)? {
+        let (blocks, records) = session.table_stats(&t)?;
+        rows.push(vec![
+            Constant::String(t),
+            Constant::Int(blocks),
+            Constant::Int(records),
+        ]);
     }
+    println!("{}", ResultSet::new(&schema, rows));
+    return Ok(());
 }
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
+use crate::error::{DbError, DbResult};
+use crate::indexing::btree_index::BTreeIndex;
 use crate::indexing::hash_index::HashIndex;
+use crate::indexing::index::{IndexMethod, IndexType};
 use crate::metadata_management::state_mgr::StatMgr;
 use crate::metadata_management::state_mgr::StateInfo;
 use crate::metadata_management::table_mgr::TableMgr;
@@ -15,6 +18,7 @@ use super::table_mgr::MAX_NAME;
 pub struct IndexInfo {
     idxname: String,
     fldname: String,
+    method: IndexMethod,
     _tbl_schema: Schema,
     idx_layout: Layout,
     si: StateInfo,
@@ -27,19 +31,25 @@ pub struct IndexMgr {
 }
 
 impl IndexMgr {
-    pub fn new(is_new: bool, tbl_mgr: TableMgr, stat_mgr: StatMgr, tx: &mut Transaction) -> Self {
+    pub fn new(
+        is_new: bool,
+        tbl_mgr: TableMgr,
+        stat_mgr: StatMgr,
+        tx: &mut Transaction,
+    ) -> DbResult<Self> {
         if is_new {
             let mut sch = Schema::new();
             sch.add_string_field("indexname".to_string(), MAX_NAME);
             sch.add_string_field("tablename".to_string(), MAX_NAME);
             sch.add_string_field("fieldname".to_string(), MAX_NAME);
-            tbl_mgr.create_table("idxcat".to_string(), sch, tx);
+            sch.add_string_field("indextype".to_string(), MAX_NAME);
+            tbl_mgr.create_table("idxcat".to_string(), sch, tx)?;
         }
-        Self {
-            layout: tbl_mgr.get_layout("idxcat".to_string(), tx),
+        Ok(Self {
+            layout: tbl_mgr.get_layout("idxcat".to_string(), tx)?,
             tbl_mgr,
             stat_mgr,
-        }
+        })
     }
 
     pub fn create_index(
@@ -47,61 +57,109 @@ impl IndexMgr {
         idxname: String,
         tblname: String,
         fldname: String,
+        method: IndexMethod,
         tx: &mut Transaction,
-    ) {
-        let mut ts = TableScan::new(tx, "idxcat".to_string(), self.layout.clone());
-        ts.insert(tx);
-        ts.set_string(tx, &"indexname".to_string(), idxname);
-        ts.set_string(tx, &"tablename".to_string(), tblname);
-        ts.set_string(tx, &"fieldname".to_string(), fldname);
+    ) -> DbResult<()> {
+        let mut ts = TableScan::new(tx, "idxcat".to_string(), self.layout.clone())?;
+        ts.insert(tx)?;
+        ts.set_string(tx, &"indexname".to_string(), idxname)?;
+        ts.set_string(tx, &"tablename".to_string(), tblname)?;
+        ts.set_string(tx, &"fieldname".to_string(), fldname)?;
+        ts.set_string(tx, &"indextype".to_string(), method.to_string())?;
         ts.close(tx);
+        Ok(())
     }
 
     pub fn get_index_info(
         &mut self,
         tblname: String,
         tx: &mut Transaction,
-    ) -> HashMap<String, IndexInfo> {
+    ) -> DbResult<HashMap<String, IndexInfo>> {
         let mut result: HashMap<String, IndexInfo> = HashMap::new();
-        let mut ts = TableScan::new(tx, "idxcat".to_string(), self.layout.clone());
-        while ts.next(tx) {
-            if ts.get_string(tx, &"tablename".to_string()) == tblname {
-                let idxname = ts.get_string(tx, &"indexname".to_string());
-                let fldname = ts.get_string(tx, &"fieldname".to_string());
-                let tbl_layout = self.tbl_mgr.get_layout(tblname.clone(), tx);
+        let mut ts = TableScan::new(tx, "idxcat".to_string(), self.layout.clone())?;
+        while ts.next(tx)? {
+            if ts.get_string(tx, &"tablename".to_string())? == tblname {
+                let idxname = ts.get_string(tx, &"indexname".to_string())?;
+                let fldname = ts.get_string(tx, &"fieldname".to_string())?;
+                let typename = ts.get_string(tx, &"indextype".to_string())?;
+                let method = IndexMethod::from_name(&typename).ok_or_else(|| {
+                    DbError::Corruption(format!("unknown index type {}", typename))
+                })?;
+                let tbl_layout = self.tbl_mgr.get_layout(tblname.clone(), tx)?;
                 let tblsi = self
                     .stat_mgr
-                    .get_stat_info(tblname.clone(), tbl_layout.clone(), tx);
-                let index_info =
-                    IndexInfo::new(idxname.clone(), fldname.clone(), tbl_layout.schema(), tblsi);
+                    .get_stat_info(tblname.clone(), tbl_layout.clone(), tx)?;
+                let index_info = IndexInfo::new(
+                    idxname.clone(),
+                    fldname.clone(),
+                    method,
+                    tbl_layout.schema(),
+                    tblsi,
+                )?;
                 result.insert(fldname.clone(), index_info);
             }
         }
         ts.close(tx);
-        return result;
+        return Ok(result);
     }
 }
 
 impl IndexInfo {
-    pub fn new(idxname: String, fldname: String, tbl_schema: Schema, si: StateInfo) -> Self {
-        Self {
+    pub fn new(
+        idxname: String,
+        fldname: String,
+        method: IndexMethod,
+        tbl_schema: Schema,
+        si: StateInfo,
+    ) -> DbResult<Self> {
+        Ok(Self {
             idxname,
             fldname: fldname.clone(),
+            method,
             _tbl_schema: tbl_schema.clone(),
-            idx_layout: Self::create_idx_layout(fldname, tbl_schema),
+            idx_layout: Self::create_idx_layout(fldname, tbl_schema)?,
             si,
+        })
+    }
+
+    /// open the index with the implementation recorded in idxcat
+    pub fn open(&self, tx: &mut Transaction) -> DbResult<IndexType> {
+        match self.method {
+            IndexMethod::Hash => Ok(IndexType::HashIndex(HashIndex::new(
+                self.idxname.clone(),
+                self.idx_layout.clone(),
+            ))),
+            IndexMethod::BTree => Ok(IndexType::BTreeIndex(BTreeIndex::new(
+                tx,
+                self.idxname.clone(),
+                self.idx_layout.clone(),
+            )?)),
+        }
+    }
+
+    /// reclaim the index record versions no transaction can see any more
+    /// the B-tree pages are not versioned, so only a hash index has any
+    /// @return: the number of reclaimed versions
+    pub fn vacuum(&self, tx: &mut Transaction) -> DbResult<i32> {
+        match self.method {
+            IndexMethod::Hash => {
+                HashIndex::vacuum(self.idxname.clone(), self.idx_layout.clone(), tx)
+            }
+            IndexMethod::BTree => Ok(0),
         }
     }
 
-    pub fn open(&self) -> HashIndex {
-        let _sch = Schema::new();
-        return HashIndex::new(self.idxname.clone(), self.idx_layout.clone());
+    pub fn method(&self) -> IndexMethod {
+        self.method
     }
 
     pub fn blocks_accessed(&self, tx: &mut Transaction) -> i32 {
         let rpb = tx.block_size() / self.idx_layout.slot_size(); // get records per block
         let num_blocks = self.si.records_output() / rpb;
-        return HashIndex::search_cost(num_blocks, rpb);
+        match self.method {
+            IndexMethod::Hash => HashIndex::search_cost(num_blocks, rpb),
+            IndexMethod::BTree => BTreeIndex::search_cost(num_blocks, rpb),
+        }
     }
 
     pub fn records_output(&self) -> i32 {
@@ -116,17 +174,19 @@ impl IndexInfo {
         };
     }
 
-    fn create_idx_layout(fldname: String, tbl_schema: Schema) -> Layout {
+    /// fails if the indexed field is not in the schema of the table
+    fn create_idx_layout(fldname: String, tbl_schema: Schema) -> DbResult<Layout> {
         let mut sch = Schema::new();
         sch.add_int_field("block".to_string());
         sch.add_int_field("id".to_string());
-        if tbl_schema.get_type_(&fldname) == Type::INTEGER.into() {
-            sch.add_int_field("dataval".to_string());
-        } else {
-            let fldlen = tbl_schema.get_length(&fldname);
-            sch.add_string_field("dataval".to_string(), fldlen);
+        match tbl_schema.get_type_(&fldname)? {
+            Type::INTEGER => sch.add_int_field("dataval".to_string()),
+            Type::VARCHAR => {
+                let fldlen = tbl_schema.get_length(&fldname)?;
+                sch.add_string_field("dataval".to_string(), fldlen);
+            }
         }
-        return Layout::new_from_schema(sch);
+        return Ok(Layout::new_from_schema(sch));
     }
 }
 
@@ -154,43 +214,45 @@ mod tests {
     #[test]
     fn test_index_mgr() -> Result<()> {
         setup();
-        let db = SimpleDB::new("indexmgrtest".to_string(), 400, 8);
+        let db = SimpleDB::new("./db/logtest", "indexmgrtest", 400, 8)?;
         let mut tx = db.new_tx();
-        let tm = TableMgr::new(true, &mut tx);
+        let tm = TableMgr::new(true, &mut tx)?;
         let mut sch = Schema::new();
-        let statmgr = StatMgr::new(tm.clone(), &mut tx);
+        let statmgr = StatMgr::new(tm.clone(), &mut tx)?;
 
         sch.add_int_field("sid".to_string());
         sch.add_string_field("sname".to_string(), 10);
         sch.add_int_field("majorId".to_string());
-        tm.create_table("student".to_string(), sch, &mut tx);
-
-        let layout = tm.get_layout("student".to_string(), &mut tx);
-        let mut ts = TableScan::new(&mut tx, "student".to_string(), layout.clone());
-        ts.insert(&mut tx);
-        ts.set_int(&mut tx, &"sid".to_string(), 1);
-        ts.set_string(&mut tx, &"sname".to_string(), "Joe".to_string());
-        ts.set_int(&mut tx, &"majorId".to_string(), 20);
-        ts.insert(&mut tx);
-        ts.set_int(&mut tx, &"sid".to_string(), 2);
-        ts.set_string(&mut tx, &"sname".to_string(), "Aoi".to_string());
-        ts.set_int(&mut tx, &"majorId".to_string(), 10);
-
-        let mut idxmgr = IndexMgr::new(true, tm.clone(), statmgr.clone(), &mut tx);
+        tm.create_table("student".to_string(), sch, &mut tx)?;
+
+        let layout = tm.get_layout("student".to_string(), &mut tx)?;
+        let mut ts = TableScan::new(&mut tx, "student".to_string(), layout.clone())?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"sid".to_string(), 1)?;
+        ts.set_string(&mut tx, &"sname".to_string(), "Joe".to_string())?;
+        ts.set_int(&mut tx, &"majorId".to_string(), 20)?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"sid".to_string(), 2)?;
+        ts.set_string(&mut tx, &"sname".to_string(), "Aoi".to_string())?;
+        ts.set_int(&mut tx, &"majorId".to_string(), 10)?;
+
+        let mut idxmgr = IndexMgr::new(true, tm.clone(), statmgr.clone(), &mut tx)?;
         idxmgr.create_index(
             "sidIdx".to_string(),
             "student".to_string(),
             "sid".to_string(),
+            IndexMethod::Hash,
             &mut tx,
-        );
+        )?;
         idxmgr.create_index(
             "snameIdx".to_string(),
             "student".to_string(),
             "sname".to_string(),
+            IndexMethod::BTree,
             &mut tx,
-        );
+        )?;
 
-        let indexes = idxmgr.get_index_info("student".to_string(), &mut tx);
+        let indexes = idxmgr.get_index_info("student".to_string(), &mut tx)?;
         assert_eq!(indexes.len(), 2);
 
         let sid_info = indexes.get("sid").unwrap();
@@ -201,9 +263,10 @@ mod tests {
         let sname_info = indexes.get("sname").unwrap();
         assert_eq!(sid_info.si.records_output(), 2);
         assert_eq!(sid_info.si.distinct_values(&"sname".to_string()), 1);
-        assert_eq!(sname_info.blocks_accessed(&mut tx), 0);
+        assert_eq!(sname_info.method(), IndexMethod::BTree);
+        assert_eq!(sname_info.blocks_accessed(&mut tx), 1);
 
-        tx.commit();
+        tx.commit()?;
         teardown();
         Ok(())
     }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::error::DbResult;
+use crate::indexing::index::IndexMethod;
 use crate::metadata_management::index_mgr::IndexMgr;
 use crate::metadata_management::state_mgr::StatMgr;
 use crate::metadata_management::table_mgr::TableMgr;
@@ -19,33 +21,51 @@ pub struct MetadataMgr {
 }
 
 impl MetadataMgr {
-    pub fn new(is_new: bool, tx: &mut Transaction) -> Self {
+    pub fn new(is_new: bool, tx: &mut Transaction) -> DbResult<Self> {
         let tx = tx;
-        let table_mgr = TableMgr::new(is_new, tx);
-        let view_mgr = ViewMgr::new(is_new, table_mgr.clone(), tx);
-        let stat_mgr = StatMgr::new(table_mgr.clone(), tx);
-        let index_mgr = IndexMgr::new(is_new, table_mgr.clone(), stat_mgr.clone(), tx);
-        Self {
+        let table_mgr = TableMgr::new(is_new, tx)?;
+        let view_mgr = ViewMgr::new(is_new, table_mgr.clone(), tx)?;
+        let stat_mgr = StatMgr::new(table_mgr.clone(), tx)?;
+        let index_mgr = IndexMgr::new(is_new, table_mgr.clone(), stat_mgr.clone(), tx)?;
+        Ok(Self {
             table_mgr,
             view_mgr,
             stat_mgr,
             index_mgr,
-        }
+        })
+    }
+
+    pub fn create_table(
+        &mut self,
+        tblname: String,
+        sch: Schema,
+        tx: &mut Transaction,
+    ) -> DbResult<()> {
+        self.table_mgr.create_table(tblname, sch, tx)
     }
 
-    pub fn create_table(&mut self, tblname: String, sch: Schema, tx: &mut Transaction) {
-        self.table_mgr.create_table(tblname, sch, tx);
+    pub fn has_table(&mut self, tblname: String, tx: &mut Transaction) -> DbResult<bool> {
+        self.table_mgr.has_table(tblname, tx)
     }
 
-    pub fn get_layout(&mut self, tblname: String, tx: &mut Transaction) -> Layout {
+    pub fn get_layout(&mut self, tblname: String, tx: &mut Transaction) -> DbResult<Layout> {
         self.table_mgr.get_layout(tblname, tx)
     }
 
-    pub fn create_view(&mut self, viewname: String, tx: &mut Transaction, viewdef: String) {
-        self.view_mgr.create_view(viewname, viewdef, tx);
+    pub fn create_view(
+        &mut self,
+        viewname: String,
+        tx: &mut Transaction,
+        viewdef: String,
+    ) -> DbResult<()> {
+        self.view_mgr.create_view(viewname, viewdef, tx)
     }
 
-    pub fn get_view_def(&mut self, viewname: String, tx: &mut Transaction) -> Option<String> {
+    pub fn get_view_def(
+        &mut self,
+        viewname: String,
+        tx: &mut Transaction,
+    ) -> DbResult<Option<String>> {
         self.view_mgr.get_view_def(viewname, tx)
     }
 
@@ -54,16 +74,18 @@ impl MetadataMgr {
         idxname: String,
         tblname: String,
         fldname: String,
+        method: IndexMethod,
         tx: &mut Transaction,
-    ) {
-        self.index_mgr.create_index(idxname, tblname, fldname, tx);
+    ) -> DbResult<()> {
+        self.index_mgr
+            .create_index(idxname, tblname, fldname, method, tx)
     }
 
     pub fn get_index_info(
         &mut self,
         tblname: String,
         tx: &mut Transaction,
-    ) -> HashMap<String, IndexInfo> {
+    ) -> DbResult<HashMap<String, IndexInfo>> {
         self.index_mgr.get_index_info(tblname, tx)
     }
 
@@ -72,7 +94,7 @@ impl MetadataMgr {
         tblname: String,
         layout: Layout,
         tx: &mut Transaction,
-    ) -> StateInfo {
+    ) -> DbResult<StateInfo> {
         self.stat_mgr.get_stat_info(tblname, layout, tx)
     }
 }
@@ -104,53 +126,55 @@ mod tests {
     #[test]
     fn test_metadata_mgr() -> Result<()> {
         setup();
-        let db = SimpleDB::new("metadatamgrtest".to_string(), 400, 8);
+        let db = SimpleDB::new("./db/logtest", "metadatamgrtest", 400, 8)?;
         let mut tx = db.new_tx();
-        let mut mdm = MetadataMgr::new(true, &mut tx);
+        let mut mdm = MetadataMgr::new(true, &mut tx)?;
 
         let mut sch = Schema::new();
         sch.add_int_field("A".to_string());
         sch.add_string_field("B".to_string(), 9);
 
-        mdm.create_table("MyTable".to_string(), sch, &mut tx);
-        let layout = mdm.get_layout("MyTable".to_string(), &mut tx);
+        mdm.create_table("MyTable".to_string(), sch, &mut tx)?;
+        let layout = mdm.get_layout("MyTable".to_string(), &mut tx)?;
         let _size = layout.slot_size();
         let sch2 = layout.schema();
         // check sch == sch2
-        assert_eq!(sch2.get_type_(&"A".to_string()), Type::INTEGER.into());
-        assert_eq!(sch2.get_type_(&"B".to_string()), Type::VARCHAR.into());
-        assert_eq!(sch2.get_length(&"B".to_string()), 9);
+        assert_eq!(sch2.get_type_(&"A".to_string())?, Type::INTEGER);
+        assert_eq!(sch2.get_type_(&"B".to_string())?, Type::VARCHAR);
+        assert_eq!(sch2.get_length(&"B".to_string())?, 9);
 
-        let mut ts = TableScan::new(&mut tx, "MyTable".to_string(), layout.clone());
+        let mut ts = TableScan::new(&mut tx, "MyTable".to_string(), layout.clone())?;
         for i in 0..50 {
-            ts.insert(&mut tx);
-            ts.set_int(&mut tx, &"A".to_string(), i);
-            ts.set_string(&mut tx, &"B".to_string(), format!("rec{}", i));
+            ts.insert(&mut tx)?;
+            ts.set_int(&mut tx, &"A".to_string(), i)?;
+            ts.set_string(&mut tx, &"B".to_string(), format!("rec{}", i))?;
         }
-        let si = mdm.get_stat_info("MyTable".to_string(), layout, &mut tx);
+        let si = mdm.get_stat_info("MyTable".to_string(), layout, &mut tx)?;
         assert_eq!(si.distinct_values(&"A".to_string()), 1 + 50 / 3);
         assert_eq!(si.distinct_values(&"B".to_string()), 1 + 50 / 3);
         assert_eq!(si.records_output(), 50);
-        assert_eq!(si.blocks_accessed(), 3); // record size is 21 bytes, block size is 400 bytes, so 400 / 21 = 19.05 -> 19 records per block
+        assert_eq!(si.blocks_accessed(), 5); // record size is 33 bytes, block size is 400 bytes, so 400 / 33 = 12.12 -> 12 records per block
 
         let view_def = "select A from MyTable".to_string();
-        mdm.create_view("ViewA".to_string(), &mut tx, view_def.clone());
-        let view_def2 = mdm.get_view_def("ViewA".to_string(), &mut tx);
+        mdm.create_view("ViewA".to_string(), &mut tx, view_def.clone())?;
+        let view_def2 = mdm.get_view_def("ViewA".to_string(), &mut tx)?;
         assert_eq!(view_def, view_def2.unwrap());
 
         mdm.create_index(
             "indexA".to_string(),
             "MyTable".to_string(),
             "A".to_string(),
+            IndexMethod::Hash,
             &mut tx,
-        );
+        )?;
         mdm.create_index(
             "indexB".to_string(),
             "MyTable".to_string(),
             "B".to_string(),
+            IndexMethod::Hash,
             &mut tx,
-        );
-        let idxmap = mdm.get_index_info("MyTable".to_string(), &mut tx);
+        )?;
+        let idxmap = mdm.get_index_info("MyTable".to_string(), &mut tx)?;
 
         let ii = idxmap.get(&"A".to_string()).unwrap();
         let dis_val = 1 + 50 / 3;
@@ -165,7 +189,7 @@ mod tests {
         assert_eq!(ii.distinct_values("A".to_string()), 1 + 50 / 3);
         assert_eq!(ii.distinct_values("B".to_string()), 1);
 
-        tx.commit();
+        tx.commit()?;
         teardown();
         Ok(())
     }
@@ -1,3 +1,4 @@
+use crate::error::DbResult;
 use crate::metadata_management::table_mgr::{TableMgr, MAX_NAME};
 use crate::record_management::schema::Schema;
 use crate::record_management::table_scan::TableScan;
@@ -10,37 +11,38 @@ pub struct ViewMgr {
 }
 
 impl ViewMgr {
-    pub fn new(is_new: bool, tbl_mgr: TableMgr, tx: &mut Transaction) -> Self {
+    pub fn new(is_new: bool, tbl_mgr: TableMgr, tx: &mut Transaction) -> DbResult<Self> {
         if is_new {
             let mut sch = Schema::new();
             sch.add_string_field("viewname".to_string(), MAX_NAME);
             sch.add_string_field("viewdef".to_string(), MAX_VIEWDEF);
-            tbl_mgr.create_table("viewcat".to_string(), sch, tx);
+            tbl_mgr.create_table("viewcat".to_string(), sch, tx)?;
         }
-        Self { tbl_mgr }
+        Ok(Self { tbl_mgr })
     }
 
-    pub fn create_view(&self, vname: String, vdef: String, tx: &mut Transaction) {
-        let layout = self.tbl_mgr.get_layout("viewcat".to_string(), tx);
-        let mut ts = TableScan::new(tx, "viewcat".to_string(), layout);
-        ts.insert(tx);
-        ts.set_string(tx, &"viewname".to_string(), vname);
-        ts.set_string(tx, &"viewdef".to_string(), vdef);
+    pub fn create_view(&self, vname: String, vdef: String, tx: &mut Transaction) -> DbResult<()> {
+        let layout = self.tbl_mgr.get_layout("viewcat".to_string(), tx)?;
+        let mut ts = TableScan::new(tx, "viewcat".to_string(), layout)?;
+        ts.insert(tx)?;
+        ts.set_string(tx, &"viewname".to_string(), vname)?;
+        ts.set_string(tx, &"viewdef".to_string(), vdef)?;
         ts.close(tx);
+        Ok(())
     }
 
-    pub fn get_view_def(&self, vname: String, tx: &mut Transaction) -> Option<String> {
-        let layout = self.tbl_mgr.get_layout("viewcat".to_string(), tx);
-        let mut ts = TableScan::new(tx, "viewcat".to_string(), layout);
+    pub fn get_view_def(&self, vname: String, tx: &mut Transaction) -> DbResult<Option<String>> {
+        let layout = self.tbl_mgr.get_layout("viewcat".to_string(), tx)?;
+        let mut ts = TableScan::new(tx, "viewcat".to_string(), layout)?;
         let mut result = None;
-        while ts.next(tx) {
-            if ts.get_string(tx, &"viewname".to_string()) == vname {
-                let viewdef = ts.get_string(tx, &"viewdef".to_string());
+        while ts.next(tx)? {
+            if ts.get_string(tx, &"viewname".to_string())? == vname {
+                let viewdef = ts.get_string(tx, &"viewdef".to_string())?;
                 result = Some(viewdef);
             }
         }
         ts.close(tx);
-        return result;
+        return Ok(result);
     }
 }
 
@@ -52,19 +54,19 @@ mod tests {
 
     #[test]
     fn test_view_mgr() -> Result<()> {
-        let db = SimpleDB::new("viewmgrtest".to_string(), 400, 8);
+        let db = SimpleDB::new("./db/logtest", "viewmgrtest", 400, 8)?;
         let mut tx = db.new_tx();
         let tm = TableMgr::new(true, &mut tx);
 
-        let vm = ViewMgr::new(true, tm, &mut tx);
+        let vm = ViewMgr::new(true, tm?, &mut tx)?;
         // check layout
-        let layout = vm.tbl_mgr.get_layout("viewcat".to_string(), &mut tx);
+        let layout = vm.tbl_mgr.get_layout("viewcat".to_string(), &mut tx)?;
         assert_eq!(
-            layout.schema().get_length(&"viewname".to_string()),
+            layout.schema().get_length(&"viewname".to_string())?,
             MAX_NAME
         );
         assert_eq!(
-            layout.schema().get_length(&"viewdef".to_string()),
+            layout.schema().get_length(&"viewdef".to_string())?,
             MAX_VIEWDEF
         );
 
@@ -73,18 +75,18 @@ mod tests {
             "view1".to_string(),
             "select A from MyTable".to_string(),
             &mut tx,
-        );
+        )?;
         vm.create_view(
             "view2".to_string(),
             "select B from MyTable".to_string(),
             &mut tx,
-        );
+        )?;
         assert_eq!(
-            vm.get_view_def("view1".to_string(), &mut tx),
+            vm.get_view_def("view1".to_string(), &mut tx)?,
             Some("select A from MyTable".to_string())
         );
         assert_eq!(
-            vm.get_view_def("view2".to_string(), &mut tx),
+            vm.get_view_def("view2".to_string(), &mut tx)?,
             Some("select B from MyTable".to_string())
         );
 
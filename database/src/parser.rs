@@ -1,2 +1,3 @@
+pub mod parse_error;
 pub mod parser;
 pub mod tokenize;
@@ -0,0 +1,136 @@
+use std::fmt::Display;
+
+/// a statement that could not be parsed, with the place in the input where parsing stopped
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// byte offset of the offending token in the input
+    pub offset: usize,
+    /// 1-based line of the offset
+    pub line: usize,
+    /// 1-based column of the offset, counted in characters
+    pub column: usize,
+    /// the offending token as it is written, none at the end of the input
+    pub found: Option<String>,
+    /// the tokens that would have been accepted instead
+    pub expected: Vec<String>,
+    /// what is wrong when the token itself is acceptable, e.g. an unknown function name
+    pub message: Option<String>,
+    /// the line of the input holding the offset, for the caret rendering
+    source_line: String,
+    /// how many characters of the line the offending token covers
+    width: usize,
+}
+
+impl ParseError {
+    /// @param offset: byte offset of the offending token in input
+    /// @param len: byte length of the token, 0 at the end of the input
+    pub fn new(
+        input: &str,
+        offset: usize,
+        len: usize,
+        expected: Vec<String>,
+        message: Option<String>,
+    ) -> Self {
+        let offset = offset.min(input.len());
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        let token = &input[offset..(offset + len).min(line_end)];
+        let found = if len == 0 {
+            None
+        } else {
+            Some(token.to_string())
+        };
+        return Self {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            found,
+            expected,
+            message,
+            source_line: input[line_start..line_end].to_string(),
+            width: token.chars().count().max(1),
+        };
+    }
+
+    /// the error message followed by the offending line and a caret under the offending token
+    pub fn render(&self) -> String {
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        return format!(
+            "{}\n{}\n{}{}",
+            self,
+            self.source_line,
+            indent,
+            "^".repeat(self.width)
+        );
+    }
+
+    fn found_text(&self) -> String {
+        match &self.found {
+            Some(token) => format!("\"{}\"", token),
+            None => "end of input".to_string(),
+        }
+    }
+
+    /// "a", "a or b", "a, b or c"
+    fn expected_list(&self) -> String {
+        match self.expected.split_last() {
+            None => String::new(),
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.message {
+            Some(message) => write!(f, "{}", message),
+            None if self.expected.is_empty() => write!(f, "unexpected {}", self.found_text()),
+            None => write!(
+                f,
+                "expected {}, but found {}",
+                self.expected_list(),
+                self.found_text()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error() {
+        let input = "select a\nfrm T";
+        let e = ParseError::new(
+            input,
+            9,
+            3,
+            vec!["\",\"".to_string(), "FROM".to_string()],
+            None,
+        );
+        assert_eq!((e.line, e.column), (2, 1));
+        assert_eq!(e.found, Some("frm".to_string()));
+        assert_eq!(
+            e.render(),
+            "line 2, column 1: expected \",\" or FROM, but found \"frm\"\nfrm T\n^^^"
+        );
+
+        let e = ParseError::new(input, input.len(), 0, vec!["WHERE".to_string()], None);
+        assert_eq!((e.line, e.column), (2, 6));
+        assert_eq!(
+            e.render(),
+            "line 2, column 6: expected WHERE, but found end of input\nfrm T\n     ^"
+        );
+    }
+}
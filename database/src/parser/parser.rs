@@ -1,19 +1,34 @@
+use std::fmt::Display;
+
+use crate::common::Constant;
+use crate::error::{DbError, DbResult};
+use crate::indexing::index::IndexMethod;
+use crate::parser::parse_error::ParseError;
 use crate::parser::tokenize::Lexer;
 use crate::parser::tokenize::{Reserved, TokenKind};
-// use crate::scans::expression::Expression;
-// use crate::scans::predicate::Predicate;
-use crate::record_management::schema::{Schema, Type};
-use crate::record_management::table_scan::TableScan;
-use crate::scans::common::{Scan, ScanType};
-use crate::scans::production_scan::ProductionScan;
-use crate::SimpleDB;
-// use crate::scans::select_scan::SelectScan;
+use crate::record_management::schema::Schema;
+use crate::scans::expression::{BinaryOp, Expression, Function};
+use crate::scans::predicate::Predicate;
+use crate::scans::term::{CompOp, Term};
+use crate::transaction_manager::concurrency_manager::IsolationLevel;
 
 #[derive(Debug)]
 pub enum Object {
     CreateTable(CreateTableData),
+    CreateView(CreateViewData),
+    CreateIndex(CreateIndexData),
     Insert(InsertData),
+    Delete(DeleteData),
+    Modify(ModifyData),
     Query(QueryData),
+    SetIsolation(IsolationLevel),
+    Savepoint(String),
+    RollbackToSavepoint(String),
+    ReleaseSavepoint(String),
+    Vacuum(String),
+    Begin,
+    Commit,
+    Rollback,
 }
 
 #[derive(Debug)]
@@ -22,6 +37,27 @@ pub struct CreateTableData {
     pub schema: Schema,
 }
 
+#[derive(Debug)]
+pub struct CreateViewData {
+    pub viewname: String,
+    pub qry: QueryData,
+}
+
+impl CreateViewData {
+    /// the definition stored in viewcat
+    pub fn view_def(&self) -> String {
+        self.qry.to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateIndexData {
+    pub idxname: String,
+    pub tblname: String,
+    pub fldname: String,
+    pub method: IndexMethod,
+}
+
 #[derive(Debug)]
 pub struct InsertData {
     pub tblname: String,
@@ -30,123 +66,77 @@ pub struct InsertData {
 }
 
 #[derive(Debug)]
-pub struct QueryData {
-    pub fields: QueryFields,
-    pub tables: Vec<String>,
+pub struct DeleteData {
+    pub tblname: String,
+    pub pred: Predicate,
 }
 
 #[derive(Debug)]
-pub enum QueryFields {
-    AllFields,
-    Fields(Vec<String>),
+pub struct ModifyData {
+    pub tblname: String,
+    pub fldname: String,
+    pub newval: Expression,
+    pub pred: Predicate,
 }
 
-pub trait Execute {
-    fn execute(&mut self, db: &SimpleDB);
+#[derive(Debug)]
+pub struct QueryData {
+    pub fields: QueryFields,
+    pub tables: Vec<String>,
+    pub pred: Predicate,
 }
 
-impl Execute for Object {
-    fn execute(&mut self, db: &SimpleDB) {
-        match self {
-            Object::CreateTable(d) => {
-                d.execute(db);
-            }
-            Object::Insert(d) => {
-                d.execute(db);
-            }
-            Object::Query(d) => {
-                d.execute(db);
-            }
+/// reconstruct the query text
+/// the result can be parsed again, which is how view definitions are stored
+impl Display for QueryData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = match &self.fields {
+            QueryFields::AllFields => "*".to_string(),
+            QueryFields::Fields(fields) => fields
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+        write!(f, "select {} from {}", fields, self.tables.join(", "))?;
+        let pred = self.pred.to_string();
+        if !pred.is_empty() {
+            write!(f, " where {}", pred)?;
         }
+        Ok(())
     }
 }
 
-impl Execute for CreateTableData {
-    fn execute(&mut self, db: &SimpleDB) {
-        let mut tx = db.new_tx(); // new transaction
-        let mut mdm = db.new_metadata_mgr(&mut tx);
-        mdm.create_table(self.tblname.clone(), self.schema.clone(), &mut tx);
-        tx.commit();
-    }
+#[derive(Debug)]
+pub enum QueryFields {
+    AllFields,
+    Fields(Vec<SelectField>),
 }
 
-impl Execute for InsertData {
-    fn execute(&mut self, db: &SimpleDB) {
-        let mut tx = db.new_tx(); // new transaction
-        let mut mdm = db.new_metadata_mgr(&mut tx);
-        let layout = mdm.get_layout(self.tblname.clone(), &mut tx);
-        let mut ts = TableScan::new(&mut tx, self.tblname.clone(), layout.clone());
-        ts.insert(&mut tx);
-        for (i, fldname) in self.flds.iter().enumerate() {
-            if !ts.has_field(fldname) {
-                panic!("field {} not found.", fldname);
-            }
-            match layout.schema().get_type_(fldname).into() {
-                Type::INTEGER => {
-                    let val = self.vals[i].parse::<i32>();
-                    match val {
-                        Ok(v) => {
-                            ts.set_int(&mut tx, fldname, v);
-                        }
-                        Err(_) => {
-                            panic!("value {} is not integer.", self.vals[i]);
-                        }
-                    }
-                }
-                Type::VARCHAR => {
-                    ts.set_string(&mut tx, fldname, self.vals[i].clone());
-                }
-            }
-        }
-        ts.close(&mut tx);
-        tx.commit();
-    }
+/// an expression of the select list, optionally renamed
+#[derive(Debug)]
+pub struct SelectField {
+    pub expr: Expression,
+    pub alias: Option<String>,
 }
 
-impl Execute for QueryData {
-    fn execute(&mut self, db: &SimpleDB) {
-        let mut tx = db.new_tx(); // new transaction
-        let mut mdm = db.new_metadata_mgr(&mut tx);
-        let mut schema = Schema::new();
-
-        let mut scan = {
-            let layout = mdm.get_layout(self.tables[0].clone(), &mut tx);
-            schema.add_all(layout.schema().clone());
-            let ts = Box::new(ScanType::TableScan(TableScan::new(
-                &mut tx,
-                self.tables[0].clone(),
-                layout.clone(),
-            )));
-            ts
-        };
-
-        for tblname in self.tables.iter().skip(1) {
-            let layout = mdm.get_layout(tblname.clone(), &mut tx);
-            schema.add_all(layout.schema().clone());
-            let ts = Box::new(ScanType::TableScan(TableScan::new(
-                &mut tx,
-                tblname.clone(),
-                layout.clone(),
-            )));
-            scan = Box::new(ScanType::ProductionScan(ProductionScan::new(
-                scan, ts, &mut tx,
-            )));
+impl SelectField {
+    /// the name of the output field
+    /// a field keeps its name; any other expression is named by its text unless it has an alias
+    pub fn name(&self) -> String {
+        match (&self.alias, self.expr.as_field_name()) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(fldname)) => fldname,
+            (None, None) => self.expr.to_string(),
         }
+    }
+}
 
-        let mut select_scan = match self.fields {
-            QueryFields::AllFields => scan,
-            QueryFields::Fields(_) => {
-                todo!("select fields not implemented.");
-            }
-        };
-
-        // print row
-        while select_scan.next(&mut tx) {
-            for fldname in schema.get_fields() {
-                let val = select_scan.get_val(fldname.clone(), &mut tx);
-                print!("{} ", val);
-            }
-            println!();
+impl Display for SelectField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.alias {
+            Some(alias) => write!(f, "{} as {}", self.expr, alias),
+            None => write!(f, "{}", self.expr),
         }
     }
 }
@@ -160,191 +150,635 @@ impl Parser {
         Self { lex: Lexer::new(s) }
     }
 
-    pub fn field(&mut self) -> String {
+    pub fn field(&mut self) -> DbResult<String> {
         self.lex.eat_id()
     }
 
     /// < Constant > ::= StrTok
-    fn constant(&mut self) -> String {
+    fn constant(&mut self) -> DbResult<String> {
         return self.lex.eat_id();
     }
 
+    /// parse a single statement that makes up the whole input
+    pub fn sql(mut self) -> DbResult<Object> {
+        let obj = self.statement()?;
+        self.end_statement()?;
+        self.lex.eat_end()?;
+        return Ok(obj);
+    }
+
+    /// < Script > ::= [ < Sql > ] [ ; [ < Sql > ] ... ]
+    /// parse the statements of a script, which are separated by semicolons
+    /// @return: the statements, or the first error
+    pub fn statements(mut self) -> DbResult<Vec<Object>> {
+        let mut objects = Vec::new();
+        while self.skip_empty_statements() {
+            let obj = self.statement()?;
+            self.end_statement()?;
+            objects.push(obj);
+        }
+        return Ok(objects);
+    }
+
+    /// parse every statement of a script
+    /// after an error the parser skips to the next semicolon or token that starts a statement,
+    /// so that the errors of all the statements are reported
+    /// @return: the statements that could be parsed, and the errors of the others
+    /// an error other than a parse error stops the parsing
+    pub fn script(mut self) -> DbResult<(Vec<Object>, Vec<ParseError>)> {
+        let mut objects = Vec::new();
+        let mut errors = Vec::new();
+        while self.skip_empty_statements() {
+            let start = self.lex.position();
+            let result = self.statement();
+            match result.and_then(|obj| self.end_statement().map(|_| obj)) {
+                Ok(obj) => objects.push(obj),
+                Err(DbError::Parse(e)) => {
+                    errors.push(*e);
+                    self.skip_statement(start);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        return Ok((objects, errors));
+    }
+
+    /// skip the semicolons of empty statements
+    /// @return: whether a statement follows
+    fn skip_empty_statements(&mut self) -> bool {
+        while self.lex.peek() == Some(&TokenKind::SEMICOLON) {
+            self.lex.advance();
+        }
+        return !self.lex.is_at_end();
+    }
+
+    /// a statement ends with a semicolon or the end of the input
+    fn end_statement(&mut self) -> DbResult<()> {
+        if self.lex.match_keyword(TokenKind::SEMICOLON) {
+            return self.lex.eat_keyword(TokenKind::SEMICOLON);
+        }
+        return self.lex.eat_end();
+    }
+
+    /// skip the tokens up to the next semicolon or the start of the next statement
+    /// SET is not taken as a start, since it also occurs in an UPDATE
+    /// @param start: the position of the statement that failed, which is skipped at least
+    fn skip_statement(&mut self, start: usize) {
+        if self.lex.position() == start {
+            self.lex.advance();
+        }
+        while let Some(token) = self.lex.peek() {
+            let starts = match token {
+                TokenKind::SEMICOLON => true,
+                TokenKind::RESERVED(r) => matches!(
+                    r,
+                    Reserved::SELECT
+                        | Reserved::INSERT
+                        | Reserved::DELETE
+                        | Reserved::UPDATE
+                        | Reserved::CREATE
+                ),
+                TokenKind::TOK(t) => matches!(
+                    t.to_lowercase().as_str(),
+                    "begin" | "commit" | "rollback" | "savepoint" | "release" | "vacuum"
+                ),
+                _ => false,
+            };
+            if starts {
+                return;
+            }
+            self.lex.advance();
+        }
+    }
+
     // <Sql> ::= <Updatecmd> | <Query>
-    pub fn sql(mut self) -> Object {
+    fn statement(&mut self) -> DbResult<Object> {
         if self
             .lex
             .match_keyword(TokenKind::RESERVED(Reserved::SELECT))
         {
-            return Object::Query(self.query());
+            return Ok(Object::Query(self.query()?));
         } else {
             return self.update_cmd();
         }
     }
 
-    /// < UpdateCmd > ::= <Insert> | <Create> | ..
-    fn update_cmd(&mut self) -> Object {
+    /// < UpdateCmd > ::= <Insert> | <Delete> | <Modify> | <Create> | <SetTransaction> | <TransactionCmd> | <Vacuum>
+    /// < Vacuum > ::= VACUUM IdTok
+    fn update_cmd(&mut self) -> DbResult<Object> {
         if self
             .lex
             .match_keyword(TokenKind::RESERVED(Reserved::INSERT))
         {
-            return Object::Insert(self.insert());
+            return Ok(Object::Insert(self.insert()?));
+        } else if self
+            .lex
+            .match_keyword(TokenKind::RESERVED(Reserved::DELETE))
+        {
+            return Ok(Object::Delete(self.delete()?));
+        } else if self
+            .lex
+            .match_keyword(TokenKind::RESERVED(Reserved::UPDATE))
+        {
+            return Ok(Object::Modify(self.modify()?));
         } else if self
             .lex
             .match_keyword(TokenKind::RESERVED(Reserved::CREATE))
         {
             return self.create();
+        } else if self.lex.match_keyword(TokenKind::RESERVED(Reserved::SET)) {
+            return Ok(Object::SetIsolation(self.set_transaction()?));
+        } else if ["begin", "commit", "rollback", "savepoint", "release"]
+            .iter()
+            .any(|word| self.lex.match_id(word))
+        {
+            return self.transaction_cmd();
+        } else if self.lex.match_id("vacuum") {
+            self.lex.eat_id()?;
+            return Ok(Object::Vacuum(self.lex.eat_id()?));
         } else {
-            todo!("update_cmd not implemented.");
+            return Err(self.lex.unexpected());
+        }
+    }
+
+    /// < SetTransaction > ::= SET TRANSACTION ISOLATION LEVEL < IsolationLevel >
+    /// < IsolationLevel > ::= READ UNCOMMITTED | READ COMMITTED | REPEATABLE READ | SERIALIZABLE
+    /// the words after SET are not reserved, so they are read as identifiers
+    fn set_transaction(&mut self) -> DbResult<IsolationLevel> {
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::SET))?;
+        for word in ["transaction", "isolation", "level"] {
+            if !self.lex.match_id(word) {
+                return Err(self.lex.unexpected());
+            }
+            self.lex.eat_id()?;
+        }
+        let position = self.lex.position();
+        let mut name = self.lex.eat_id()?;
+        if matches!(name.to_lowercase().as_str(), "read" | "repeatable") {
+            name = name + " " + &self.lex.eat_id()?;
+        }
+        return IsolationLevel::from_name(&name).ok_or_else(|| {
+            self.lex
+                .error_at(position, format!("unknown isolation level {}", name))
+        });
+    }
+
+    /// < TransactionCmd > ::= BEGIN [ TRANSACTION | WORK ] | COMMIT [ TRANSACTION | WORK ]
+    ///                      | ROLLBACK [ TRANSACTION | WORK ] | <Savepoint>
+    /// < Savepoint > ::= SAVEPOINT IdTok | ROLLBACK TO [ SAVEPOINT ] IdTok | RELEASE [ SAVEPOINT ] IdTok
+    /// none of the words is reserved, so they are read as identifiers
+    fn transaction_cmd(&mut self) -> DbResult<Object> {
+        let command = self.lex.eat_id()?.to_lowercase();
+        if command == "savepoint" {
+            return Ok(Object::Savepoint(self.lex.eat_id()?));
         }
+        if command == "rollback" && self.lex.match_id("to") {
+            self.lex.eat_id()?;
+            return Ok(Object::RollbackToSavepoint(self.savepoint_name()?));
+        }
+        if command == "release" {
+            return Ok(Object::ReleaseSavepoint(self.savepoint_name()?));
+        }
+        if self.lex.match_id("transaction") || self.lex.match_id("work") {
+            self.lex.eat_id()?;
+        }
+        return match command.as_str() {
+            "begin" => Ok(Object::Begin),
+            "commit" => Ok(Object::Commit),
+            _ => Ok(Object::Rollback),
+        };
+    }
+
+    fn savepoint_name(&mut self) -> DbResult<String> {
+        if self.lex.match_id("savepoint") {
+            self.lex.eat_id()?;
+        }
+        return self.lex.eat_id();
     }
 
-    /// < Create > ::= <CreateTable> | ..
-    fn create(&mut self) -> Object {
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::CREATE));
+    /// < Create > ::= <CreateTable> | <CreateView> | ..
+    fn create(&mut self) -> DbResult<Object> {
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::CREATE))?;
         if self.lex.match_keyword(TokenKind::RESERVED(Reserved::TABLE)) {
-            let d = self.create_table();
-            return Object::CreateTable(d);
+            let d = self.create_table()?;
+            return Ok(Object::CreateTable(d));
+        } else if self.lex.match_keyword(TokenKind::RESERVED(Reserved::VIEW)) {
+            let d = self.create_view()?;
+            return Ok(Object::CreateView(d));
+        } else if self.lex.match_keyword(TokenKind::RESERVED(Reserved::INDEX)) {
+            let d = self.create_index()?;
+            return Ok(Object::CreateIndex(d));
         } else {
-            todo!("create not implemented.");
-        }
-    }
-
-    fn insert(&mut self) -> InsertData {
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INSERT));
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INTO));
-        let tblname = self.lex.eat_id();
-        self.lex.eat_keyword(TokenKind::LPAR);
-        let flds = self.field_list();
-        self.lex.eat_keyword(TokenKind::RPAR);
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::VALUES));
-        self.lex.eat_keyword(TokenKind::LPAR);
-        let vals = self.const_list();
-        self.lex.eat_keyword(TokenKind::RPAR);
-        return InsertData {
+            return Err(self.lex.unexpected());
+        }
+    }
+
+    fn insert(&mut self) -> DbResult<InsertData> {
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::INSERT))?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INTO))?;
+        let tblname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::LPAR)?;
+        let flds = self.field_list()?;
+        self.lex.eat_keyword(TokenKind::RPAR)?;
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::VALUES))?;
+        self.lex.eat_keyword(TokenKind::LPAR)?;
+        let vals = self.const_list()?;
+        self.lex.eat_keyword(TokenKind::RPAR)?;
+        return Ok(InsertData {
             tblname,
             flds,
             vals,
-        };
+        });
+    }
+
+    /// < Delete > ::= DELETE FROM IdTok [ WHERE < Predicate > ]
+    fn delete(&mut self) -> DbResult<DeleteData> {
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::DELETE))?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::FROM))?;
+        let tblname = self.lex.eat_id()?;
+        let mut pred = Predicate::new();
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::WHERE)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::WHERE))?;
+            pred = self.predicate()?;
+        }
+        return Ok(DeleteData { tblname, pred });
+    }
+
+    /// < Modify > ::= UPDATE IdTok SET < Field > = < Expression > [ WHERE < Predicate > ]
+    fn modify(&mut self) -> DbResult<ModifyData> {
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::UPDATE))?;
+        let tblname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::SET))?;
+        let fldname = self.field()?;
+        self.lex.eat_keyword(TokenKind::EQUAL)?;
+        let newval = self.expression()?;
+        let mut pred = Predicate::new();
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::WHERE)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::WHERE))?;
+            pred = self.predicate()?;
+        }
+        return Ok(ModifyData {
+            tblname,
+            fldname,
+            newval,
+            pred,
+        });
     }
 
     /// < FieldList > ::= < Field > [, < FieldList >]
-    fn field_list(&mut self) -> Vec<String> {
+    fn field_list(&mut self) -> DbResult<Vec<String>> {
         let mut l: Vec<String> = Vec::new();
-        l.push(self.field());
+        l.push(self.field()?);
         if self.lex.match_keyword(TokenKind::COMMA) {
-            self.lex.eat_keyword(TokenKind::COMMA);
-            l.extend(self.field_list());
+            self.lex.eat_keyword(TokenKind::COMMA)?;
+            l.extend(self.field_list()?);
         }
-        return l;
+        return Ok(l);
     }
 
     /// < ConstList > ::= < Constant > [, < ConstList > ]
-    fn const_list(&mut self) -> Vec<String> {
+    fn const_list(&mut self) -> DbResult<Vec<String>> {
         let mut l: Vec<String> = Vec::new();
-        l.push(self.constant());
+        l.push(self.constant()?);
         if self.lex.match_keyword(TokenKind::COMMA) {
-            self.lex.eat_keyword(TokenKind::COMMA);
-            l.extend(self.const_list());
+            self.lex.eat_keyword(TokenKind::COMMA)?;
+            l.extend(self.const_list()?);
         }
-        return l;
+        return Ok(l);
     }
 
     /// <CreateTable> ::= CREATE TABLE IdTok ( <FieldDefs> )
-    fn create_table(&mut self) -> CreateTableData {
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::TABLE));
-        let tblname = self.lex.eat_id();
-        self.lex.eat_keyword(TokenKind::LPAR);
-        let schema = self.field_defs();
-        self.lex.eat_keyword(TokenKind::RPAR);
-        return CreateTableData { tblname, schema };
+    fn create_table(&mut self) -> DbResult<CreateTableData> {
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::TABLE))?;
+        let tblname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::LPAR)?;
+        let schema = self.field_defs()?;
+        self.lex.eat_keyword(TokenKind::RPAR)?;
+        return Ok(CreateTableData { tblname, schema });
+    }
+
+    /// <CreateView> ::= CREATE VIEW IdTok AS <Query>
+    fn create_view(&mut self) -> DbResult<CreateViewData> {
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::VIEW))?;
+        let viewname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::AS))?;
+        let qry = self.query()?;
+        return Ok(CreateViewData { viewname, qry });
+    }
+
+    /// <CreateIndex> ::= CREATE INDEX IdTok ON IdTok ( <Field> ) [USING IdTok]
+    /// the index is a hash index unless another method is given
+    fn create_index(&mut self) -> DbResult<CreateIndexData> {
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INDEX))?;
+        let idxname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::ON))?;
+        let tblname = self.lex.eat_id()?;
+        self.lex.eat_keyword(TokenKind::LPAR)?;
+        let fldname = self.field()?;
+        self.lex.eat_keyword(TokenKind::RPAR)?;
+        let mut method = IndexMethod::Hash;
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::USING)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::USING))?;
+            let position = self.lex.position();
+            let name = self.lex.eat_id()?;
+            method = IndexMethod::from_name(&name).ok_or_else(|| {
+                self.lex
+                    .error_at(position, format!("unknown index type {}", name))
+            })?;
+        }
+        return Ok(CreateIndexData {
+            idxname,
+            tblname,
+            fldname,
+            method,
+        });
     }
 
     /// < FieldDefs > ::= < FieldDef > [, < FieldDefs >]
-    pub fn field_defs(&mut self) -> Schema {
-        let mut schema = self.field_def();
+    pub fn field_defs(&mut self) -> DbResult<Schema> {
+        let mut schema = self.field_def()?;
         if self.lex.match_keyword(TokenKind::COMMA) {
-            self.lex.eat_keyword(TokenKind::COMMA);
-            let schema2 = self.field_defs();
+            self.lex.eat_keyword(TokenKind::COMMA)?;
+            let schema2 = self.field_defs()?;
             schema.add_all(schema2);
         }
-        schema
+        Ok(schema)
     }
 
     /// < FieldDef > ::= IdTok < FieldType >
-    pub fn field_def(&mut self) -> Schema {
-        let fldname = self.lex.eat_id();
-        let schema = self.field_type(fldname);
-        return schema;
+    pub fn field_def(&mut self) -> DbResult<Schema> {
+        let fldname = self.lex.eat_id()?;
+        let schema = self.field_type(fldname)?;
+        return Ok(schema);
     }
 
     /// < FieldType > ::= INT | VARCHAR ( IntTok )
-    pub fn field_type(&mut self, fldname: String) -> Schema {
+    pub fn field_type(&mut self, fldname: String) -> DbResult<Schema> {
         let mut schema = Schema::new();
         if self.lex.match_keyword(TokenKind::RESERVED(Reserved::INT)) {
-            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INT));
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::INT))?;
             schema.add_int_field(fldname);
         } else if self
             .lex
             .match_keyword(TokenKind::RESERVED(Reserved::VARCHAR))
         {
-            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::VARCHAR));
-            self.lex.eat_keyword(TokenKind::LPAR);
-            let str_len = self.lex.eat_int_constant();
-            self.lex.eat_keyword(TokenKind::RPAR);
+            self.lex
+                .eat_keyword(TokenKind::RESERVED(Reserved::VARCHAR))?;
+            self.lex.eat_keyword(TokenKind::LPAR)?;
+            let str_len = self.lex.eat_int_constant()?;
+            self.lex.eat_keyword(TokenKind::RPAR)?;
             schema.add_string_field(fldname, str_len);
         } else {
-            panic!("field type not found.");
+            return Err(self.lex.unexpected());
         }
-        return schema;
+        return Ok(schema);
     }
 
-    pub fn query(&mut self) -> QueryData {
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::SELECT));
-        let fields = self.select_pattern();
-        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::FROM));
-        let tables = self.table_list();
-        return QueryData { fields, tables };
-        // let pred = Predicate::new();
-        // if self.lex.match_keyword(TokenKind::RESERVED(Reserved::WHERE)) {
-        //     self.lex.eat_keyword(TokenKind::RESERVED(Reserved::WHERE));
-        //     pred = self.predicate();
-        // }
+    /// parse the definition of a view, as it is stored in the catalog
+    /// the definition must be a single query without anything after it
+    pub fn view_def(mut self) -> DbResult<QueryData> {
+        let data = self.query()?;
+        self.lex.eat_end()?;
+        return Ok(data);
     }
 
-    fn select_pattern(&mut self) -> QueryFields {
+    pub fn query(&mut self) -> DbResult<QueryData> {
+        self.lex
+            .eat_keyword(TokenKind::RESERVED(Reserved::SELECT))?;
+        let fields = self.select_pattern()?;
+        self.lex.eat_keyword(TokenKind::RESERVED(Reserved::FROM))?;
+        let tables = self.table_list()?;
+        let mut pred = Predicate::new();
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::WHERE)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::WHERE))?;
+            pred = self.predicate()?;
+        }
+        return Ok(QueryData {
+            fields,
+            tables,
+            pred,
+        });
+    }
+
+    /// < Predicate > ::= < Conjunction > [ OR < Predicate > ]
+    pub fn predicate(&mut self) -> DbResult<Predicate> {
+        let pred = self.conjunction()?;
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::OR)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::OR))?;
+            return Ok(Predicate::disjoin(pred, self.predicate()?));
+        }
+        return Ok(pred);
+    }
+
+    /// < Conjunction > ::= < Factor > [ AND < Conjunction > ]
+    fn conjunction(&mut self) -> DbResult<Predicate> {
+        let mut pred = self.factor()?;
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::AND)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::AND))?;
+            pred.conjoin_with(self.conjunction()?);
+        }
+        return Ok(pred);
+    }
+
+    /// < Factor > ::= NOT < Factor > | ( < Predicate > ) | < Term >
+    /// parentheses followed by an operator enclose the first expression of a term
+    fn factor(&mut self) -> DbResult<Predicate> {
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::NOT)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::NOT))?;
+            return Ok(Predicate::negate(self.factor()?));
+        }
+        if self.lex.match_keyword(TokenKind::LPAR) && !self.lex.match_operand_group() {
+            self.lex.eat_keyword(TokenKind::LPAR)?;
+            let pred = self.predicate()?;
+            self.lex.eat_keyword(TokenKind::RPAR)?;
+            return Ok(pred);
+        }
+        let mut pred = Predicate::new();
+        pred.add_term(self.term()?);
+        return Ok(pred);
+    }
+
+    /// < Term > ::= < Expression > < CompOp > < Expression >
+    /// < CompOp > ::= = | != | <> | < | > | <= | >=
+    fn term(&mut self) -> DbResult<Term> {
+        let lhs = self.expression()?;
+        let op = self.comp_op()?;
+        let rhs = self.expression()?;
+        return Ok(Term::new_with_op(lhs, rhs, op));
+    }
+
+    fn comp_op(&mut self) -> DbResult<CompOp> {
+        let ops = [
+            (TokenKind::EQUAL, CompOp::Equal),
+            (TokenKind::NOTEQUAL, CompOp::NotEqual),
+            (TokenKind::LESS, CompOp::Less),
+            (TokenKind::GREATER, CompOp::Greater),
+            (TokenKind::LESSEQUAL, CompOp::LessEqual),
+            (TokenKind::GREATEREQUAL, CompOp::GreaterEqual),
+        ];
+        for (token, op) in ops {
+            if self.lex.match_keyword(token.clone()) {
+                self.lex.eat_keyword(token)?;
+                return Ok(op);
+            }
+        }
+        return Err(self.lex.unexpected());
+    }
+
+    /// < Expression > ::= < Sum > [ || < Sum > ... ]
+    fn expression(&mut self) -> DbResult<Expression> {
+        let mut e = self.sum()?;
+        while self.lex.match_keyword(TokenKind::CONCAT) {
+            self.lex.eat_keyword(TokenKind::CONCAT)?;
+            e = Expression::new_binary(BinaryOp::Concat, e, self.sum()?);
+        }
+        return Ok(e);
+    }
+
+    /// < Sum > ::= < Product > [ < AddOp > < Product > ... ]
+    /// < AddOp > ::= + | -
+    fn sum(&mut self) -> DbResult<Expression> {
+        let ops = [
+            (TokenKind::PLUS, BinaryOp::Add),
+            (TokenKind::MINUS, BinaryOp::Sub),
+        ];
+        let mut e = self.product()?;
+        while let Some(op) = self.binary_op(&ops)? {
+            e = Expression::new_binary(op, e, self.product()?);
+        }
+        return Ok(e);
+    }
+
+    /// < Product > ::= < Unary > [ < MulOp > < Unary > ... ]
+    /// < MulOp > ::= * | / | %
+    fn product(&mut self) -> DbResult<Expression> {
+        let ops = [
+            (TokenKind::RESERVED(Reserved::ASTER), BinaryOp::Mul),
+            (TokenKind::SLASH, BinaryOp::Div),
+            (TokenKind::PERCENT, BinaryOp::Mod),
+        ];
+        let mut e = self.unary()?;
+        while let Some(op) = self.binary_op(&ops)? {
+            e = Expression::new_binary(op, e, self.unary()?);
+        }
+        return Ok(e);
+    }
+
+    fn binary_op(&mut self, ops: &[(TokenKind, BinaryOp)]) -> DbResult<Option<BinaryOp>> {
+        for (token, op) in ops.iter() {
+            if self.lex.match_keyword(token.clone()) {
+                self.lex.eat_keyword(token.clone())?;
+                return Ok(Some(*op));
+            }
+        }
+        return Ok(None);
+    }
+
+    /// < Unary > ::= - < Unary > | < Primary >
+    fn unary(&mut self) -> DbResult<Expression> {
+        if self.lex.match_keyword(TokenKind::MINUS) {
+            self.lex.eat_keyword(TokenKind::MINUS)?;
+            return Ok(Expression::new_negate(self.unary()?));
+        }
+        return self.primary();
+    }
+
+    /// < Primary > ::= < Field > | < Constant > | NULL | ( < Expression > )
+    ///               | IdTok ( [ < Expression > [, < Expression > ... ] ] )
+    fn primary(&mut self) -> DbResult<Expression> {
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::NULL)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::NULL))?;
+            return Ok(Expression::new_from_val(Constant::Null));
+        } else if self.lex.match_string_constant() {
+            return Ok(Expression::new_from_val(Constant::String(
+                self.lex.eat_id()?,
+            )));
+        } else if self.lex.match_int_constant() {
+            return Ok(Expression::new_from_val(Constant::Int(
+                self.lex.eat_int_constant()?,
+            )));
+        } else if self.lex.match_keyword(TokenKind::LPAR) {
+            self.lex.eat_keyword(TokenKind::LPAR)?;
+            let e = self.expression()?;
+            self.lex.eat_keyword(TokenKind::RPAR)?;
+            return Ok(e);
+        } else if self.lex.match_function_call() {
+            let position = self.lex.position();
+            let name = self.lex.eat_id()?;
+            let func = Function::from_name(&name).ok_or_else(|| {
+                self.lex
+                    .error_at(position, format!("unknown function {}", name))
+            })?;
+            self.lex.eat_keyword(TokenKind::LPAR)?;
+            let mut args = Vec::new();
+            if !self.lex.match_keyword(TokenKind::RPAR) {
+                args.push(self.expression()?);
+                while self.lex.match_keyword(TokenKind::COMMA) {
+                    self.lex.eat_keyword(TokenKind::COMMA)?;
+                    args.push(self.expression()?);
+                }
+            }
+            self.lex.eat_keyword(TokenKind::RPAR)?;
+            return Ok(Expression::new_call(func, args));
+        } else {
+            return Ok(Expression::new_from_fldname(self.field()?));
+        }
+    }
+
+    fn select_pattern(&mut self) -> DbResult<QueryFields> {
         if self.lex.match_keyword(TokenKind::RESERVED(Reserved::ASTER)) {
-            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::ASTER));
-            return QueryFields::AllFields;
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::ASTER))?;
+            return Ok(QueryFields::AllFields);
         } else {
-            let fields = self.select_list();
-            return QueryFields::Fields(fields);
+            let fields = self.select_list()?;
+            return Ok(QueryFields::Fields(fields));
         }
     }
 
-    fn select_list(&mut self) -> Vec<String> {
-        let mut l: Vec<String> = Vec::new();
-        l.push(self.field());
+    /// < SelectList > ::= < SelectField > [, < SelectList > ]
+    fn select_list(&mut self) -> DbResult<Vec<SelectField>> {
+        let mut l: Vec<SelectField> = Vec::new();
+        l.push(self.select_field()?);
         if self.lex.match_keyword(TokenKind::COMMA) {
-            self.lex.eat_keyword(TokenKind::COMMA);
-            l.extend(self.select_list());
+            self.lex.eat_keyword(TokenKind::COMMA)?;
+            l.extend(self.select_list()?);
+        }
+        return Ok(l);
+    }
+
+    /// < SelectField > ::= < Expression > [ AS IdTok ]
+    fn select_field(&mut self) -> DbResult<SelectField> {
+        let expr = self.expression()?;
+        let mut alias = None;
+        if self.lex.match_keyword(TokenKind::RESERVED(Reserved::AS)) {
+            self.lex.eat_keyword(TokenKind::RESERVED(Reserved::AS))?;
+            alias = Some(self.lex.eat_id()?);
         }
-        return l;
+        return Ok(SelectField { expr, alias });
     }
 
-    fn table_list(&mut self) -> Vec<String> {
+    fn table_list(&mut self) -> DbResult<Vec<String>> {
         let mut l: Vec<String> = Vec::new();
-        l.push(self.lex.eat_id());
+        l.push(self.lex.eat_id()?);
         if self.lex.match_keyword(TokenKind::COMMA) {
-            self.lex.eat_keyword(TokenKind::COMMA);
-            l.extend(self.table_list());
+            self.lex.eat_keyword(TokenKind::COMMA)?;
+            l.extend(self.table_list()?);
         }
-        return l;
+        return Ok(l);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::planning::plan::Plan;
+    use crate::scans::common::Scan;
+    use crate::SimpleDB;
     use anyhow::Result;
     use std::fs;
 
@@ -365,22 +799,376 @@ mod tests {
     #[test]
     fn test_parse() -> Result<()> {
         setup();
-        let db = SimpleDB::new("parsetest".to_string(), 400, 8);
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("parsetest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
 
         let s = String::from("CREATE TABLE STUDENT (sid INT, name VARCHAR(20), age INT)");
-        let parser = Parser::new(s);
-        parser.sql().execute(&db);
+        let parser = Parser::new(s.clone());
+        match parser.sql()? {
+            Object::CreateTable(d) => {
+                assert_eq!(d.tblname, "STUDENT");
+                assert_eq!(d.schema.get_fields(), vec!["sid", "name", "age"]);
+            }
+            _ => panic!("expected create table"),
+        }
+        planner.execute_update(s, &mut tx)?;
 
         let s = String::from("INSERT INTO STUDENT (sid, name, age) VALUES (1, 'Alice', 18)");
-        let parser = Parser::new(s);
-        parser.sql().execute(&db);
+        let parser = Parser::new(s.clone());
+        match parser.sql()? {
+            Object::Insert(d) => {
+                assert_eq!(d.tblname, "STUDENT");
+                assert_eq!(d.flds, vec!["sid", "name", "age"]);
+                assert_eq!(d.vals, vec!["1", "Alice", "18"]);
+            }
+            _ => panic!("expected insert"),
+        }
+        planner.execute_update(s, &mut tx)?;
 
         let s = String::from("SELECT * FROM STUDENT");
-        let parser = Parser::new(s);
+        let parser = Parser::new(s.clone());
         // println!("{:?}", parser.sql());
-        parser.sql().execute(&db);
+        match parser.sql()? {
+            Object::Query(d) => {
+                assert!(matches!(d.fields, QueryFields::AllFields));
+                assert_eq!(d.tables, vec!["STUDENT"]);
+            }
+            _ => panic!("expected query"),
+        }
+        let p = planner.create_query_plan(s, &mut tx)?;
+        let mut scan = p.open(&mut tx)?;
+        assert!(scan.next(&mut tx)?);
+        assert_eq!(scan.get_string("name".to_string(), &mut tx)?, "Alice");
+        assert!(!scan.next(&mut tx)?);
+        scan.close(&mut tx);
+
+        let s = String::from("SELECT * FROM STUDENT WHERE age = 18 AND name = 'Alice'");
+        let parser = Parser::new(s.clone());
+        match parser.sql()? {
+            Object::Query(d) => {
+                assert_eq!(format!("{}", d.pred), "age = 18 and name = 'Alice'");
+                assert_eq!(
+                    d.pred.equates_with_constant("age".to_string()),
+                    Some(Constant::Int(18))
+                );
+            }
+            _ => panic!("expected query"),
+        }
+        let p = planner.create_query_plan(s, &mut tx)?;
+        let mut scan = p.open(&mut tx)?;
+        assert!(scan.next(&mut tx)?);
+        assert_eq!(scan.get_int("sid".to_string(), &mut tx)?, 1);
+        scan.close(&mut tx);
+
+        let s = String::from(
+            "CREATE VIEW ADULT AS SELECT sid, name FROM STUDENT WHERE age = 18 AND name = 'Alice'",
+        );
+        let parser = Parser::new(s);
+        match parser.sql()? {
+            Object::CreateView(d) => {
+                assert_eq!(d.viewname, "ADULT");
+                assert_eq!(
+                    d.view_def(),
+                    "select sid, name from STUDENT where age = 18 and name = 'Alice'"
+                );
+                // the definition can be parsed again
+                let qry = Parser::new(d.view_def()).query()?;
+                assert_eq!(qry.to_string(), d.view_def());
+            }
+            _ => panic!("expected create view"),
+        }
+
+        let s = String::from(
+            "SELECT * FROM STUDENT WHERE age >= 18 AND (name = 'x' OR NOT name <> 'y')",
+        );
+        match Parser::new(s).sql()? {
+            Object::Query(d) => {
+                assert_eq!(
+                    d.to_string(),
+                    "select * from STUDENT where age >= 18 and (name = 'x' or not name != 'y')"
+                );
+                // a disjunction cannot be used to find a constant
+                assert_eq!(d.pred.equates_with_constant("name".to_string()), None);
+            }
+            _ => panic!("expected query"),
+        }
+
+        let s = String::from("CREATE INDEX sididx ON STUDENT (sid)");
+        let parser = Parser::new(s);
+        match parser.sql()? {
+            Object::CreateIndex(d) => {
+                assert_eq!(d.idxname, "sididx");
+                assert_eq!(d.tblname, "STUDENT");
+                assert_eq!(d.fldname, "sid");
+                assert_eq!(d.method, IndexMethod::Hash);
+            }
+            _ => panic!("expected create index"),
+        }
+        let s = String::from("CREATE INDEX ageidx ON STUDENT (age) USING BTREE");
+        match Parser::new(s).sql()? {
+            Object::CreateIndex(d) => {
+                assert_eq!(d.fldname, "age");
+                assert_eq!(d.method, IndexMethod::BTree);
+            }
+            _ => panic!("expected create index"),
+        }
+
+        for (s, level) in [
+            (
+                "SET TRANSACTION ISOLATION LEVEL READ UNCOMMITTED",
+                IsolationLevel::ReadUncommitted,
+            ),
+            (
+                "set transaction isolation level read committed",
+                IsolationLevel::ReadCommitted,
+            ),
+            (
+                "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ",
+                IsolationLevel::RepeatableRead,
+            ),
+            (
+                "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+                IsolationLevel::Serializable,
+            ),
+        ] {
+            match Parser::new(s.to_string()).sql()? {
+                Object::SetIsolation(l) => assert_eq!(l, level),
+                _ => panic!("expected set transaction"),
+            }
+        }
+        let s = String::from("SET TRANSACTION ISOLATION LEVEL READ COMMITTED");
+        assert_eq!(planner.execute_update(s, &mut tx)?, 0);
+        assert_eq!(tx.isolation_level(), IsolationLevel::ReadCommitted);
+
+        let s = String::from("SELECT * FROM STUDENT WHERE name = 'Bob'");
+        let p = planner.create_query_plan(s, &mut tx)?;
+        let mut scan = p.open(&mut tx)?;
+        assert!(!scan.next(&mut tx)?);
+        scan.close(&mut tx);
+
+        for (s, name) in [
+            ("SAVEPOINT sp1", "sp1"),
+            ("ROLLBACK TO SAVEPOINT sp1", "sp1"),
+            ("rollback to sp2", "sp2"),
+            ("RELEASE SAVEPOINT sp1", "sp1"),
+            ("release sp2", "sp2"),
+        ] {
+            match Parser::new(s.to_string()).sql()? {
+                Object::Savepoint(n) => assert!(s.starts_with("SAVEPOINT") && n == name),
+                Object::RollbackToSavepoint(n) => {
+                    assert!(s.to_lowercase().starts_with("rollback") && n == name)
+                }
+                Object::ReleaseSavepoint(n) => {
+                    assert!(s.to_lowercase().starts_with("release") && n == name)
+                }
+                _ => panic!("expected savepoint"),
+            }
+        }
+        let count_bob = |planner: &mut crate::planning::planner::Planner,
+                         tx: &mut crate::transaction_manager::transaction::Transaction|
+         -> Result<i32> {
+            let s = String::from("SELECT * FROM STUDENT WHERE name = 'Bob'");
+            let p = planner.create_query_plan(s, tx)?;
+            let mut scan = p.open(tx)?;
+            let mut n = 0;
+            while scan.next(tx)? {
+                n += 1;
+            }
+            scan.close(tx);
+            Ok(n)
+        };
+        planner.execute_update("SAVEPOINT before_bob".to_string(), &mut tx)?;
+        let s = String::from("INSERT INTO STUDENT (sid, name, age) VALUES (2, 'Bob', 19)");
+        assert_eq!(planner.execute_update(s, &mut tx)?, 1);
+        assert_eq!(count_bob(&mut planner, &mut tx)?, 1);
+        planner.execute_update("ROLLBACK TO SAVEPOINT before_bob".to_string(), &mut tx)?;
+        assert_eq!(count_bob(&mut planner, &mut tx)?, 0);
+        planner.execute_update("RELEASE SAVEPOINT before_bob".to_string(), &mut tx)?;
+        assert!(matches!(
+            planner.execute_update("ROLLBACK TO before_bob".to_string(), &mut tx),
+            Err(crate::error::DbError::Transaction(_))
+        ));
+        tx.commit()?;
 
         teardown();
         Ok(())
     }
+
+    fn parse_error(sql: &str) -> ParseError {
+        match Parser::new(sql.to_string()).sql() {
+            Err(DbError::Parse(e)) => *e,
+            Err(e) => panic!("expected a parse error, but got {}", e),
+            Ok(obj) => panic!("expected a parse error, but got {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn test_parse_where() -> Result<()> {
+        let query = |s: &str| Parser::new(s.to_string()).query();
+
+        let d = query("select a, b from T where a = 1 and b = 'x' and c = a")?;
+        assert_eq!(d.tables, vec!["T"]);
+        assert_eq!(d.pred.to_string(), "a = 1 and b = 'x' and c = a");
+        assert_eq!(
+            d.pred.equates_with_constant("a".to_string()),
+            Some(Constant::Int(1))
+        );
+        assert_eq!(
+            d.pred.equates_with_constant("b".to_string()),
+            Some(Constant::String("x".to_string()))
+        );
+        assert_eq!(d.pred.equates_with_constant("c".to_string()), None);
+        assert_eq!(
+            d.pred.equates_with_field("c".to_string()),
+            Some("a".to_string())
+        );
+
+        // without a WHERE clause the predicate is always true
+        let d = query("select a from T")?;
+        assert_eq!(d.pred.to_string(), "");
+        assert_eq!(d.to_string(), "select a from T");
+
+        // constants may be on either side of the comparison
+        let d = query("select a from T where 1 = a")?;
+        assert_eq!(
+            d.pred.equates_with_constant("a".to_string()),
+            Some(Constant::Int(1))
+        );
+
+        for s in [
+            "select a from T where",
+            "select a from T where a = 1 and",
+            "select a from T where a =",
+            "select a from T where a = 1 b = 2",
+        ] {
+            assert!(
+                matches!(Parser::new(s.to_string()).sql(), Err(DbError::Parse(_))),
+                "{}",
+                s
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = parse_error("select a, b\nfrm T");
+        assert_eq!((e.offset, e.line, e.column), (12, 2, 1));
+        assert_eq!(e.found, Some("frm".to_string()));
+        assert!(e.expected.contains(&"FROM".to_string()));
+        assert!(e.expected.contains(&"\",\"".to_string()));
+        assert!(e.render().ends_with("\nfrm T\n^^^"));
+
+        let e = parse_error("select a from T where");
+        assert_eq!(e.found, None);
+        assert_eq!(e.column, 22);
+        assert!(e.expected.contains(&"an identifier".to_string()));
+
+        let e = parse_error("create tabel T (a int)");
+        assert_eq!(e.column, 8);
+        assert_eq!(e.expected, vec!["TABLE", "VIEW", "INDEX"]);
+
+        let e = parse_error("select a from T where a # 1");
+        assert_eq!(e.found, Some("#".to_string()));
+        assert!(e.expected.contains(&"\"=\"".to_string()));
+
+        let e = parse_error("select a from T b");
+        assert!(e.expected.contains(&"end of input".to_string()));
+
+        let e = parse_error("select lower(a), nosuch(b) from T");
+        assert_eq!(e.column, 18);
+        assert_eq!(e.message, Some("unknown function nosuch".to_string()));
+
+        let e = parse_error("SET TRANSACTION ISOLATION LEVL SERIALIZABLE");
+        assert_eq!(e.expected, vec!["LEVEL"]);
+    }
+
+    #[test]
+    fn test_parse_script() -> Result<()> {
+        let script = "create table T (a int);
+            insert into T (a) values (1
+            select a from T;
+            update T set a = 2 where;
+            delete from T where a = 1"
+            .to_string();
+        let (objects, errors) = Parser::new(script).script()?;
+        assert_eq!(objects.len(), 3);
+        assert!(matches!(objects[0], Object::CreateTable(_)));
+        assert!(matches!(objects[1], Object::Query(_)));
+        assert!(matches!(objects[2], Object::Delete(_)));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            (errors[0].line, errors[0].found.clone()),
+            (3, Some("select".to_string()))
+        );
+        assert_eq!(
+            (errors[1].line, errors[1].found.clone()),
+            (4, Some(";".to_string()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_statements() -> Result<()> {
+        let script = "-- the schema
+            create table T (
+                a int, /* the key */
+                b varchar(10)
+            );;
+            insert into T (a, b) values (1, 'one');
+            select a, b
+            from T /* every record */ where a = 1"
+            .to_string();
+        let objects = Parser::new(script).statements()?;
+        assert_eq!(objects.len(), 3);
+        assert!(matches!(objects[0], Object::CreateTable(_)));
+        assert!(matches!(objects[1], Object::Insert(_)));
+        assert!(matches!(objects[2], Object::Query(_)));
+        assert!(Parser::new("-- nothing\n;".to_string())
+            .statements()?
+            .is_empty());
+
+        // the first error stops the parsing
+        let e = match Parser::new("select a from T; select from T; selct".to_string()).statements()
+        {
+            Err(DbError::Parse(e)) => e,
+            _ => panic!("expected a parse error"),
+        };
+        assert_eq!(e.column, 25);
+
+        // a missing semicolon between statements
+        let e = match Parser::new("select a from T select b from T".to_string()).statements() {
+            Err(DbError::Parse(e)) => e,
+            _ => panic!("expected a parse error"),
+        };
+        assert!(e.expected.contains(&"\";\"".to_string()));
+
+        assert!(Parser::new("select a from T;".to_string()).sql().is_ok());
+        assert!(Lexer::ends_statement("select a\nfrom T; -- done"));
+        assert!(!Lexer::ends_statement("select a; from T"));
+        assert!(!Lexer::ends_statement("select a /* ; */"));
+
+        // comments and semicolons inside quotes are part of the constant
+        let objects = Parser::new(
+            "insert into T (a, b) values (4, 'a--b'); insert into T (a, b) values (5, '');
+            insert into T (a, b) values (6, 'a b; /* c */')"
+                .to_string(),
+        )
+        .statements()?;
+        let vals: Vec<String> = objects
+            .into_iter()
+            .map(|obj| match obj {
+                Object::Insert(d) => d.vals[1].clone(),
+                _ => panic!("expected insert"),
+            })
+            .collect();
+        assert_eq!(vals, vec!["a--b", "", "a b; /* c */"]);
+        assert!(!Lexer::ends_statement("insert into T (b) values ('a;"));
+        assert!(!Lexer::ends_statement("select a from T where b = ';'"));
+        Ok(())
+    }
 }
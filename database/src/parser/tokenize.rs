@@ -1,20 +1,59 @@
 use std::collections::VecDeque;
 
+use crate::error::{DbError, DbResult};
+use crate::parser::parse_error::ParseError;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     RESERVED(Reserved),
-    SINGLEQUOTE,
-    DOUBLEQUOTE,
+    /// a constant in single or double quotes, without the quotes
+    STRING(String),
     EQUAL,
     LESS,
     GREATER,
     LESSEQUAL,
     GREATEREQUAL,
     NOTEQUAL,
+    PLUS,
+    MINUS,
+    SLASH,
+    PERCENT,
+    CONCAT,
     LPAR,
     RPAR,
     COMMA,
+    SEMICOLON,
     TOK(String),
+    /// a character that cannot start any token
+    INVALID(String),
+}
+
+impl TokenKind {
+    /// the token as it is listed among the expected tokens of an error
+    pub fn describe(&self) -> String {
+        let symbol = match self {
+            TokenKind::RESERVED(Reserved::ASTER) => "*",
+            TokenKind::RESERVED(r) => return r.to_str().to_uppercase(),
+            TokenKind::STRING(s) => return format!("'{}'", s),
+            TokenKind::EQUAL => "=",
+            TokenKind::LESS => "<",
+            TokenKind::GREATER => ">",
+            TokenKind::LESSEQUAL => "<=",
+            TokenKind::GREATEREQUAL => ">=",
+            TokenKind::NOTEQUAL => "!=",
+            TokenKind::PLUS => "+",
+            TokenKind::MINUS => "-",
+            TokenKind::SLASH => "/",
+            TokenKind::PERCENT => "%",
+            TokenKind::CONCAT => "||",
+            TokenKind::LPAR => "(",
+            TokenKind::RPAR => ")",
+            TokenKind::COMMA => ",",
+            TokenKind::SEMICOLON => ";",
+            TokenKind::TOK(t) | TokenKind::INVALID(t) => t,
+        };
+        return format!("\"{}\"", symbol);
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -23,6 +62,9 @@ pub enum Reserved {
     FROM,
     WHERE,
     AND,
+    OR,
+    NOT,
+    NULL,
     INSERT,
     INTO,
     VALUES,
@@ -37,6 +79,7 @@ pub enum Reserved {
     AS,
     INDEX,
     ON,
+    USING,
     ASTER,
 }
 
@@ -47,6 +90,9 @@ impl Reserved {
             Reserved::FROM => 4,
             Reserved::WHERE => 5,
             Reserved::AND => 3,
+            Reserved::OR => 2,
+            Reserved::NOT => 3,
+            Reserved::NULL => 4,
             Reserved::INSERT => 6,
             Reserved::INTO => 4,
             Reserved::VALUES => 6,
@@ -61,6 +107,7 @@ impl Reserved {
             Reserved::AS => 2,
             Reserved::INDEX => 5,
             Reserved::ON => 2,
+            Reserved::USING => 5,
             Reserved::ASTER => 1,
         }
     }
@@ -70,6 +117,9 @@ impl Reserved {
             Reserved::FROM => "from",
             Reserved::WHERE => "where",
             Reserved::AND => "and",
+            Reserved::OR => "or",
+            Reserved::NOT => "not",
+            Reserved::NULL => "null",
             Reserved::INSERT => "insert",
             Reserved::INTO => "into",
             Reserved::VALUES => "values",
@@ -84,6 +134,7 @@ impl Reserved {
             Reserved::AS => "as",
             Reserved::INDEX => "index",
             Reserved::ON => "on",
+            Reserved::USING => "using",
             Reserved::ASTER => "*",
         }
     }
@@ -93,6 +144,12 @@ pub struct Lexer {
     tokenized_position: usize,
     lex_position: usize,
     tokenized: VecDeque<TokenKind>,
+    /// byte offset and byte length of each token in the input
+    spans: Vec<(usize, usize)>,
+    /// what the parser looked for at the current token, for the error if it finds none of it
+    expected: Vec<String>,
+    /// the token the expected list belongs to
+    expected_at: usize,
 }
 
 impl Lexer {
@@ -102,6 +159,9 @@ impl Lexer {
             tokenized_position: 0,
             lex_position: 0,
             tokenized: VecDeque::new(),
+            spans: Vec::new(),
+            expected: Vec::new(),
+            expected_at: 0,
         };
         lex.tokenize(); // tokenize input
         return lex;
@@ -113,140 +173,149 @@ impl Lexer {
         while s.len() > 0 {
             self.tokenized_position += 1;
             // skip whitespace
-            Self::skip_whitespace(&mut s);
+            Self::skip_whitespace_and_comments(&mut s);
+            if s.is_empty() {
+                break;
+            }
+            let start = self.input.len() - s.len();
+
+            // match a quoted constant first, since it may hold anything else
+            if s.starts_with('\'') || s.starts_with('"') {
+                match Self::quoted(&mut s) {
+                    Some(constant) => self.push(TokenKind::STRING(constant), start, &s),
+                    // the parser fails at a constant that is never closed
+                    None => self.push(TokenKind::INVALID(std::mem::take(&mut s)), start, &s),
+                }
+                continue;
+            }
 
             // match reserved words
             if Self::is_reserved_word(&mut s, Reserved::SELECT) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::SELECT));
+                self.push(TokenKind::RESERVED(Reserved::SELECT), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::FROM) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::FROM));
+                self.push(TokenKind::RESERVED(Reserved::FROM), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::WHERE) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::WHERE));
+                self.push(TokenKind::RESERVED(Reserved::WHERE), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::AND) {
-                self.tokenized.push_back(TokenKind::RESERVED(Reserved::AND));
+                self.push(TokenKind::RESERVED(Reserved::AND), start, &s);
+                continue;
+            }
+            if Self::is_reserved_word(&mut s, Reserved::OR) {
+                self.push(TokenKind::RESERVED(Reserved::OR), start, &s);
+                continue;
+            }
+            if Self::is_reserved_word(&mut s, Reserved::NOT) {
+                self.push(TokenKind::RESERVED(Reserved::NOT), start, &s);
+                continue;
+            }
+            if Self::is_reserved_word(&mut s, Reserved::NULL) {
+                self.push(TokenKind::RESERVED(Reserved::NULL), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::INSERT) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::INSERT));
+                self.push(TokenKind::RESERVED(Reserved::INSERT), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::INTO) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::INTO));
+                self.push(TokenKind::RESERVED(Reserved::INTO), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::VALUES) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::VALUES));
+                self.push(TokenKind::RESERVED(Reserved::VALUES), start, &s);
 
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::DELETE) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::DELETE));
+                self.push(TokenKind::RESERVED(Reserved::DELETE), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::UPDATE) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::UPDATE));
+                self.push(TokenKind::RESERVED(Reserved::UPDATE), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::SET) {
-                self.tokenized.push_back(TokenKind::RESERVED(Reserved::SET));
+                self.push(TokenKind::RESERVED(Reserved::SET), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::CREATE) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::CREATE));
+                self.push(TokenKind::RESERVED(Reserved::CREATE), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::TABLE) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::TABLE));
+                self.push(TokenKind::RESERVED(Reserved::TABLE), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::VARCHAR) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::VARCHAR));
+                self.push(TokenKind::RESERVED(Reserved::VARCHAR), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::INT) {
-                self.tokenized.push_back(TokenKind::RESERVED(Reserved::INT));
+                self.push(TokenKind::RESERVED(Reserved::INT), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::VIEW) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::VIEW));
+                self.push(TokenKind::RESERVED(Reserved::VIEW), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::AS) {
-                self.tokenized.push_back(TokenKind::RESERVED(Reserved::AS));
+                self.push(TokenKind::RESERVED(Reserved::AS), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::INDEX) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::INDEX));
+                self.push(TokenKind::RESERVED(Reserved::INDEX), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::ON) {
-                self.tokenized.push_back(TokenKind::RESERVED(Reserved::ON));
+                self.push(TokenKind::RESERVED(Reserved::ON), start, &s);
+                continue;
+            }
+            if Self::is_reserved_word(&mut s, Reserved::USING) {
+                self.push(TokenKind::RESERVED(Reserved::USING), start, &s);
                 continue;
             }
             if Self::is_reserved_word(&mut s, Reserved::ASTER) {
-                self.tokenized
-                    .push_back(TokenKind::RESERVED(Reserved::ASTER));
+                self.push(TokenKind::RESERVED(Reserved::ASTER), start, &s);
                 continue;
             }
 
             // match left parenthesis
             if s.chars().next().unwrap() == '(' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::LPAR);
+                self.push(TokenKind::LPAR, start, &s);
                 continue;
             }
 
             // match right parenthesis
             if s.chars().next().unwrap() == ')' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::RPAR);
+                self.push(TokenKind::RPAR, start, &s);
                 continue;
             }
 
             // match comma
             if s.chars().next().unwrap() == ',' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::COMMA);
+                self.push(TokenKind::COMMA, start, &s);
                 continue;
             }
 
-            // match single quote
-            if s.chars().next().unwrap() == '\'' {
+            // match semicolon
+            if s.chars().next().unwrap() == ';' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::SINGLEQUOTE);
-                continue;
-            }
-
-            // match double quote
-            if s.chars().next().unwrap() == '"' {
-                s.remove(0);
-                self.tokenized.push_back(TokenKind::DOUBLEQUOTE);
+                self.push(TokenKind::SEMICOLON, start, &s);
                 continue;
             }
 
             // match equal
             if s.chars().next().unwrap() == '=' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::EQUAL);
+                self.push(TokenKind::EQUAL, start, &s);
                 continue;
             }
 
@@ -254,7 +323,15 @@ impl Lexer {
             if s.len() > 1 && s.chars().next().unwrap() == '!' && s.chars().nth(1).unwrap() == '=' {
                 s.remove(0);
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::NOTEQUAL);
+                self.push(TokenKind::NOTEQUAL, start, &s);
+                continue;
+            }
+
+            // match not equal written as <>
+            if s.len() > 1 && s.chars().next().unwrap() == '<' && s.chars().nth(1).unwrap() == '>' {
+                s.remove(0);
+                s.remove(0);
+                self.push(TokenKind::NOTEQUAL, start, &s);
                 continue;
             }
 
@@ -262,7 +339,7 @@ impl Lexer {
             if s.len() > 1 && s.chars().next().unwrap() == '>' && s.chars().nth(1).unwrap() == '=' {
                 s.remove(0);
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::GREATEREQUAL);
+                self.push(TokenKind::GREATEREQUAL, start, &s);
                 continue;
             }
 
@@ -270,21 +347,44 @@ impl Lexer {
             if s.len() > 1 && s.chars().next().unwrap() == '<' && s.chars().nth(1).unwrap() == '=' {
                 s.remove(0);
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::LESSEQUAL);
+                self.push(TokenKind::LESSEQUAL, start, &s);
                 continue;
             }
 
             // match greater
             if s.chars().next().unwrap() == '>' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::GREATER);
+                self.push(TokenKind::GREATER, start, &s);
                 continue;
             }
 
             // match less
             if s.chars().next().unwrap() == '<' {
                 s.remove(0);
-                self.tokenized.push_back(TokenKind::LESS);
+                self.push(TokenKind::LESS, start, &s);
+                continue;
+            }
+
+            // match concatenation
+            if s.len() > 1 && s.starts_with("||") {
+                s.remove(0);
+                s.remove(0);
+                self.push(TokenKind::CONCAT, start, &s);
+                continue;
+            }
+
+            // match arithmetic operators
+            // * is the reserved word ASTER, which also stands for all fields
+            let arith = match s.chars().next().unwrap() {
+                '+' => Some(TokenKind::PLUS),
+                '-' => Some(TokenKind::MINUS),
+                '/' => Some(TokenKind::SLASH),
+                '%' => Some(TokenKind::PERCENT),
+                _ => None,
+            };
+            if let Some(token) = arith {
+                s.remove(0);
+                self.push(token, start, &s);
                 continue;
             }
 
@@ -299,27 +399,132 @@ impl Lexer {
                 {
                     str.push(s.remove(0));
                 }
-                self.tokenized.push_back(TokenKind::TOK(str));
+                self.push(TokenKind::TOK(str), start, &s);
+                continue;
+            } else {
+                // the parser fails when it gets to the invalid token
+                let c = s.remove(0);
+                self.push(TokenKind::INVALID(c.to_string()), start, &s);
                 continue;
+            }
+        }
+    }
+
+    /// remove the quoted constant at the start of s, where a doubled quote stands for one quote
+    /// @return: the constant without its quotes, or None if it is never closed
+    fn quoted(s: &mut String) -> Option<String> {
+        let quote = s.chars().next().unwrap();
+        let mut constant = String::new();
+        let mut chars = s.char_indices().skip(1).peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != quote {
+                constant.push(c);
+            } else if chars.peek().map(|(_, c)| *c) == Some(quote) {
+                constant.push(quote);
+                chars.next();
             } else {
-                panic!("invalid token: {}", s);
+                s.replace_range(..i + c.len_utf8(), "");
+                return Some(constant);
             }
         }
+        return None;
     }
 
-    pub fn eat_int_constant(&mut self) -> i32 {
-        let pos = self.lex_position;
-        let front_token = self.tokenized[pos].clone();
-        if let TokenKind::TOK(t) = front_token {
+    /// append a token that starts at the byte offset start and ends where rest begins
+    fn push(&mut self, token: TokenKind, start: usize, rest: &str) {
+        self.spans
+            .push((start, self.input.len() - rest.len() - start));
+        self.tokenized.push_back(token);
+    }
+
+    /// the current token
+    fn front(&self) -> Option<TokenKind> {
+        return self.tokenized.get(self.lex_position).cloned();
+    }
+
+    /// whether all the tokens have been read
+    pub fn is_at_end(&self) -> bool {
+        return self.lex_position >= self.tokenized.len();
+    }
+
+    /// the index of the current token, to report an error at it later
+    pub fn position(&self) -> usize {
+        return self.lex_position;
+    }
+
+    /// skip the current token
+    pub fn advance(&mut self) {
+        if !self.is_at_end() {
             self.lex_position += 1;
-            // return t.parse::<i32>().unwrap();
-            match t.parse::<i32>() {
-                Ok(i) => return i,
-                Err(_) => panic!("expected int"),
+        }
+    }
+
+    /// the current token, without noting that the parser looked for it
+    pub fn peek(&self) -> Option<&TokenKind> {
+        return self.tokenized.get(self.lex_position);
+    }
+
+    /// note that the parser looked for what at the current token
+    fn expect(&mut self, what: String) {
+        if self.expected_at != self.lex_position {
+            self.expected.clear();
+            self.expected_at = self.lex_position;
+        }
+        if !self.expected.contains(&what) {
+            self.expected.push(what);
+        }
+    }
+
+    /// the error for a current token that is none of the tokens the parser looked for
+    pub fn unexpected(&mut self) -> DbError {
+        if self.expected_at != self.lex_position {
+            self.expected.clear();
+        }
+        return self.error_at_token(self.lex_position, self.expected.clone(), None);
+    }
+
+    /// the error for the token at position, which is a token the parser accepts but cannot use
+    pub fn error_at(&self, position: usize, message: String) -> DbError {
+        return self.error_at_token(position, Vec::new(), Some(message));
+    }
+
+    fn error_at_token(
+        &self,
+        position: usize,
+        expected: Vec<String>,
+        message: Option<String>,
+    ) -> DbError {
+        let (offset, len) = match self.spans.get(position) {
+            Some(span) => *span,
+            None => (self.input.trim_end().len(), 0),
+        };
+        return DbError::Parse(Box::new(ParseError::new(
+            &self.input,
+            offset,
+            len,
+            expected,
+            message,
+        )));
+    }
+
+    /// fail unless all the tokens have been read
+    pub fn eat_end(&mut self) -> DbResult<()> {
+        if self.is_at_end() {
+            return Ok(());
+        }
+        self.expect("end of input".to_string());
+        return Err(self.unexpected());
+    }
+
+    pub fn eat_int_constant(&mut self) -> DbResult<i32> {
+        if let Some(TokenKind::TOK(t)) = self.front() {
+            if let Ok(i) = t.parse::<i32>() {
+                self.lex_position += 1;
+                return Ok(i);
             }
-        } else {
-            panic!("expected int, but got {:?}", front_token);
         }
+        self.expect("an integer".to_string());
+        return Err(self.unexpected());
     }
 
     // pub fn eat_id(&mut self) -> String {
@@ -342,68 +547,114 @@ impl Lexer {
     //     }
     // }
 
-    pub fn eat_id(&mut self) -> String {
-        let pos = self.lex_position;
-        let front_token = self.tokenized[pos].clone();
-        if front_token == TokenKind::SINGLEQUOTE {
-            self.lex_position += 1;
-            let s = self.eat_string();
-            let pos = self.lex_position;
-            let front_token = self.tokenized[pos].clone();
-            if front_token == TokenKind::SINGLEQUOTE {
-                self.lex_position += 1;
-                return s;
-            } else {
-                panic!("expected single quote, but got {:?}", front_token);
-            }
-        } else if front_token == TokenKind::DOUBLEQUOTE {
+    pub fn eat_id(&mut self) -> DbResult<String> {
+        if let Some(TokenKind::STRING(s)) = self.front() {
             self.lex_position += 1;
-            let s = self.eat_string();
-            let pos = self.lex_position;
-            let front_token = self.tokenized[pos].clone();
-            if front_token == TokenKind::DOUBLEQUOTE {
-                self.lex_position += 1;
-                return s;
-            } else {
-                panic!("expected single quote, but got {:?}", front_token);
-            }
+            return Ok(s);
         } else {
             return self.eat_string();
         }
     }
 
-    fn eat_string(&mut self) -> String {
-        let pos = self.lex_position;
-        let front_token = self.tokenized[pos].clone();
-        if let TokenKind::TOK(t) = front_token {
+    fn eat_string(&mut self) -> DbResult<String> {
+        if let Some(TokenKind::TOK(t)) = self.front() {
             self.lex_position += 1;
-            return t.clone();
+            return Ok(t);
         } else {
-            panic!("expected string, but got {:?}", front_token);
+            self.expect("an identifier".to_string());
+            return Err(self.unexpected());
         }
     }
 
-    pub fn eat_keyword(&mut self, keyword: TokenKind) {
-        let pos = self.lex_position;
-        let front_token = self.tokenized[pos].clone();
-        if keyword == front_token {
+    pub fn eat_keyword(&mut self, keyword: TokenKind) -> DbResult<()> {
+        if self.front().as_ref() == Some(&keyword) {
             self.lex_position += 1;
+            return Ok(());
         } else {
-            panic!("expected {:?}, but got {:?}", keyword, front_token);
+            self.expect(keyword.describe());
+            return Err(self.unexpected());
         }
     }
 
-    pub fn match_keyword(&mut self, keyword: TokenKind) -> bool {
+    /// whether the current token is a quoted string constant
+    pub fn match_string_constant(&mut self) -> bool {
+        self.expect("a string".to_string());
+        return matches!(self.peek(), Some(TokenKind::STRING(_)));
+    }
+
+    /// whether the current token is an integer constant
+    pub fn match_int_constant(&mut self) -> bool {
+        self.expect("an integer".to_string());
+        match self.peek() {
+            Some(TokenKind::TOK(t)) => t.parse::<i32>().is_ok(),
+            _ => false,
+        }
+    }
+
+    /// whether the current token is the given non-reserved word, in any case
+    pub fn match_id(&mut self, word: &str) -> bool {
+        self.expect(word.to_uppercase());
+        match self.peek() {
+            Some(TokenKind::TOK(t)) => t.to_lowercase() == word,
+            _ => false,
+        }
+    }
+
+    /// whether the current token is the name of a function being called
+    pub fn match_function_call(&mut self) -> bool {
         let pos = self.lex_position;
-        if pos >= self.tokenized.len() {
+        if pos + 1 >= self.tokenized.len() || self.match_int_constant() {
             return false;
         }
-        let front_token = self.tokenized[pos].clone();
-        if keyword == front_token {
-            return true;
-        } else {
+        return matches!(self.tokenized[pos], TokenKind::TOK(_))
+            && self.tokenized[pos + 1] == TokenKind::LPAR;
+    }
+
+    /// whether the current token opens a parenthesized expression,
+    /// that is, the matching right parenthesis is followed by an operator
+    /// otherwise the parentheses enclose a predicate
+    pub fn match_operand_group(&mut self) -> bool {
+        if !self.match_keyword(TokenKind::LPAR) {
             return false;
         }
+        let mut depth = 0;
+        for pos in self.lex_position..self.tokenized.len() {
+            match self.tokenized[pos] {
+                TokenKind::LPAR => depth += 1,
+                TokenKind::RPAR => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return match self.tokenized.get(pos + 1) {
+                    Some(token) => Self::is_operator(token),
+                    None => false,
+                };
+            }
+        }
+        return false;
+    }
+
+    fn is_operator(token: &TokenKind) -> bool {
+        matches!(
+            token,
+            TokenKind::EQUAL
+                | TokenKind::NOTEQUAL
+                | TokenKind::LESS
+                | TokenKind::GREATER
+                | TokenKind::LESSEQUAL
+                | TokenKind::GREATEREQUAL
+                | TokenKind::PLUS
+                | TokenKind::MINUS
+                | TokenKind::SLASH
+                | TokenKind::PERCENT
+                | TokenKind::CONCAT
+                | TokenKind::RESERVED(Reserved::ASTER)
+        )
+    }
+
+    pub fn match_keyword(&mut self, keyword: TokenKind) -> bool {
+        self.expect(keyword.describe());
+        return self.peek() == Some(&keyword);
     }
 
     fn is_reserved_word(s: &mut String, reserved_word: Reserved) -> bool {
@@ -430,11 +681,31 @@ impl Lexer {
         return true;
     }
 
-    fn skip_whitespace(s: &mut String) {
-        while s.len() > 0 && s.chars().next().unwrap().is_whitespace() {
-            s.remove(0);
+    /// skip whitespace, -- comments up to the end of the line and /* */ comments
+    /// a /* comment that is never closed runs to the end of the input
+    fn skip_whitespace_and_comments(s: &mut String) {
+        loop {
+            while s.len() > 0 && s.chars().next().unwrap().is_whitespace() {
+                s.remove(0);
+            }
+            if s.starts_with("--") {
+                let end = s.find('\n').unwrap_or(s.len());
+                s.replace_range(..end, "");
+            } else if s.starts_with("/*") {
+                let end = s[2..].find("*/").map_or(s.len(), |i| i + 4);
+                s.replace_range(..end, "");
+            } else {
+                return;
+            }
         }
     }
+
+    /// whether the input ends with a semicolon, outside of any comment or quoted constant
+    /// an interactive client reads lines until a statement is complete
+    pub fn ends_statement(input: &str) -> bool {
+        let lex = Lexer::new(input.to_string());
+        return lex.tokenized.back() == Some(&TokenKind::SEMICOLON);
+    }
 }
 
 #[cfg(test)]
@@ -447,7 +718,7 @@ mod tests {
         let s = "SeLect * from student where sname = \"Alice\" and sage < 20 and sname = 'Bob' and sage > 10 and sname != 'Cindy' and sage <= 30 and sname >= 'David'".to_string();
         let lex = Lexer::new(s);
         let v = lex.tokenized;
-        assert_eq!(v.len(), 40);
+        assert_eq!(v.len(), 32);
         assert_eq!(v[0], TokenKind::RESERVED(Reserved::SELECT));
         assert_eq!(v[1], TokenKind::RESERVED(Reserved::ASTER));
         assert_eq!(v[2], TokenKind::RESERVED(Reserved::FROM));
@@ -455,40 +726,84 @@ mod tests {
         assert_eq!(v[4], TokenKind::RESERVED(Reserved::WHERE));
         assert_eq!(v[5], TokenKind::TOK("sname".to_string()));
         assert_eq!(v[6], TokenKind::EQUAL);
-        assert_eq!(v[7], TokenKind::DOUBLEQUOTE);
-        assert_eq!(v[8], TokenKind::TOK("Alice".to_string()));
-        assert_eq!(v[9], TokenKind::DOUBLEQUOTE);
-        assert_eq!(v[10], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[11], TokenKind::TOK("sage".to_string()));
-        assert_eq!(v[12], TokenKind::LESS);
-        assert_eq!(v[13], TokenKind::TOK("20".to_string()));
-        assert_eq!(v[14], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[15], TokenKind::TOK("sname".to_string()));
-        assert_eq!(v[16], TokenKind::EQUAL);
-        assert_eq!(v[17], TokenKind::SINGLEQUOTE);
-        assert_eq!(v[18], TokenKind::TOK("Bob".to_string()));
-        assert_eq!(v[19], TokenKind::SINGLEQUOTE);
+        assert_eq!(v[7], TokenKind::STRING("Alice".to_string()));
+        assert_eq!(v[8], TokenKind::RESERVED(Reserved::AND));
+        assert_eq!(v[9], TokenKind::TOK("sage".to_string()));
+        assert_eq!(v[10], TokenKind::LESS);
+        assert_eq!(v[11], TokenKind::TOK("20".to_string()));
+        assert_eq!(v[12], TokenKind::RESERVED(Reserved::AND));
+        assert_eq!(v[13], TokenKind::TOK("sname".to_string()));
+        assert_eq!(v[14], TokenKind::EQUAL);
+        assert_eq!(v[15], TokenKind::STRING("Bob".to_string()));
+        assert_eq!(v[16], TokenKind::RESERVED(Reserved::AND));
+        assert_eq!(v[17], TokenKind::TOK("sage".to_string()));
+        assert_eq!(v[18], TokenKind::GREATER);
+        assert_eq!(v[19], TokenKind::TOK("10".to_string()));
         assert_eq!(v[20], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[21], TokenKind::TOK("sage".to_string()));
-        assert_eq!(v[22], TokenKind::GREATER);
-        assert_eq!(v[23], TokenKind::TOK("10".to_string()));
+        assert_eq!(v[21], TokenKind::TOK("sname".to_string()));
+        assert_eq!(v[22], TokenKind::NOTEQUAL);
+        assert_eq!(v[23], TokenKind::STRING("Cindy".to_string()));
         assert_eq!(v[24], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[25], TokenKind::TOK("sname".to_string()));
-        assert_eq!(v[26], TokenKind::NOTEQUAL);
-        assert_eq!(v[27], TokenKind::SINGLEQUOTE);
-        assert_eq!(v[28], TokenKind::TOK("Cindy".to_string()));
-        assert_eq!(v[29], TokenKind::SINGLEQUOTE);
-        assert_eq!(v[30], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[31], TokenKind::TOK("sage".to_string()));
-        assert_eq!(v[32], TokenKind::LESSEQUAL);
-        assert_eq!(v[33], TokenKind::TOK("30".to_string()));
-        assert_eq!(v[34], TokenKind::RESERVED(Reserved::AND));
-        assert_eq!(v[35], TokenKind::TOK("sname".to_string()));
-        assert_eq!(v[36], TokenKind::GREATEREQUAL);
-        assert_eq!(v[37], TokenKind::SINGLEQUOTE);
-        assert_eq!(v[38], TokenKind::TOK("David".to_string()));
-        assert_eq!(v[39], TokenKind::SINGLEQUOTE);
+        assert_eq!(v[25], TokenKind::TOK("sage".to_string()));
+        assert_eq!(v[26], TokenKind::LESSEQUAL);
+        assert_eq!(v[27], TokenKind::TOK("30".to_string()));
+        assert_eq!(v[28], TokenKind::RESERVED(Reserved::AND));
+        assert_eq!(v[29], TokenKind::TOK("sname".to_string()));
+        assert_eq!(v[30], TokenKind::GREATEREQUAL);
+        assert_eq!(v[31], TokenKind::STRING("David".to_string()));
+
+        // a quoted constant is a single token, whatever it holds
+        let lex = Lexer::new("'a--b' '' 'a b' 'it''s' \"x;/*y\" 'open; --".to_string());
+        assert_eq!(
+            lex.tokenized,
+            vec![
+                TokenKind::STRING("a--b".to_string()),
+                TokenKind::STRING("".to_string()),
+                TokenKind::STRING("a b".to_string()),
+                TokenKind::STRING("it's".to_string()),
+                TokenKind::STRING("x;/*y".to_string()),
+                TokenKind::INVALID("'open; --".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
 
+    #[test]
+    fn test_tokenize_operators() -> Result<()> {
+        let s = "(a+b)*-2/c%d || upper(e)".to_string();
+        let mut lex = Lexer::new(s);
+        assert!(lex.match_operand_group());
+        assert!(!lex.match_function_call());
+        let v = lex.tokenized.clone();
+        assert_eq!(
+            v,
+            vec![
+                TokenKind::LPAR,
+                TokenKind::TOK("a".to_string()),
+                TokenKind::PLUS,
+                TokenKind::TOK("b".to_string()),
+                TokenKind::RPAR,
+                TokenKind::RESERVED(Reserved::ASTER),
+                TokenKind::MINUS,
+                TokenKind::TOK("2".to_string()),
+                TokenKind::SLASH,
+                TokenKind::TOK("c".to_string()),
+                TokenKind::PERCENT,
+                TokenKind::TOK("d".to_string()),
+                TokenKind::CONCAT,
+                TokenKind::TOK("upper".to_string()),
+                TokenKind::LPAR,
+                TokenKind::TOK("e".to_string()),
+                TokenKind::RPAR,
+            ]
+        );
+        lex.lex_position = 13;
+        assert!(lex.match_function_call());
+
+        // parentheses around a predicate
+        let mut lex = Lexer::new("(a = 1) and b = 2".to_string());
+        assert!(!lex.match_operand_group());
         Ok(())
     }
 }
@@ -1 +1,11 @@
+pub mod extend_plan;
+pub mod index_join_plan;
+pub mod index_select_plan;
 pub mod plan;
+pub mod planner;
+pub mod product_plan;
+pub mod project_plan;
+pub mod query_planner;
+pub mod select_plan;
+pub mod table_plan;
+pub mod update_planner;
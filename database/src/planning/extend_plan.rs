@@ -0,0 +1,65 @@
+use crate::error::DbResult;
+use crate::planning::plan::{Plan, PlanType};
+use crate::record_management::schema::{Schema, Type};
+use crate::scans::common::ScanType;
+use crate::scans::expression::Expression;
+use crate::scans::extend_scan::ExtendScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the extend relational algebra operator
+/// it adds a field computed from an expression for each (name, expression) pair
+#[derive(Clone)]
+pub struct ExtendPlan {
+    p: Box<PlanType>,
+    fields: Vec<(String, Expression)>,
+    schema: Schema,
+}
+
+impl ExtendPlan {
+    /// fails if an expression does not type check against the schema of p
+    pub fn new(p: Box<PlanType>, fields: Vec<(String, Expression)>) -> DbResult<Self> {
+        let sch = p.schema();
+        let mut schema = sch.clone();
+        for (fldname, expr) in fields.iter() {
+            // an expression that is always null is given the integer type
+            match expr.type_of(&sch)? {
+                Some(Type::VARCHAR) => schema.add_string_field(fldname.clone(), expr.length(&sch)?),
+                _ => schema.add_int_field(fldname.clone()),
+            }
+        }
+        Ok(Self { p, fields, schema })
+    }
+}
+
+impl Plan for ExtendPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let s = self.p.open(tx)?;
+        Ok(ScanType::ExtendScan(ExtendScan::new(
+            Box::new(s),
+            self.fields.clone(),
+        )))
+    }
+
+    fn blocks_accessed(&self) -> i32 {
+        self.p.blocks_accessed()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.p.records_output()
+    }
+
+    /// a computed field has at most as many values as there are records
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        match self.fields.iter().find(|(name, _)| name == fldname) {
+            Some((_, expr)) => match expr.as_field_name() {
+                Some(f) => self.p.distinct_values(&f),
+                None => self.p.records_output(),
+            },
+            None => self.p.distinct_values(fldname),
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
@@ -0,0 +1,82 @@
+use crate::error::DbResult;
+use crate::metadata_management::index_mgr::IndexInfo;
+use crate::planning::plan::{Plan, PlanType};
+use crate::planning::table_plan::TablePlan;
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::scans::index_join_scan::IndexJoinScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the join relational algebra operator using an index of the right-hand table
+/// joinfield is the field of p1 that is compared with the indexed field of p2
+#[derive(Clone)]
+pub struct IndexJoinPlan {
+    p1: Box<PlanType>,
+    p2: TablePlan,
+    ii: IndexInfo,
+    joinfield: String,
+    idx_blocks: i32,
+    schema: Schema,
+}
+
+impl IndexJoinPlan {
+    pub fn new(
+        p1: Box<PlanType>,
+        p2: TablePlan,
+        ii: IndexInfo,
+        joinfield: String,
+        tx: &mut Transaction,
+    ) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        let idx_blocks = ii.blocks_accessed(tx);
+        Self {
+            p1,
+            p2,
+            ii,
+            joinfield,
+            idx_blocks,
+            schema,
+        }
+    }
+}
+
+impl Plan for IndexJoinPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let s = self.p1.open(tx)?;
+        let ts = self.p2.table_scan(tx)?;
+        let idx = self.ii.open(tx)?;
+        Ok(ScanType::IndexJoinScan(IndexJoinScan::new(
+            Box::new(s),
+            idx,
+            self.joinfield.clone(),
+            ts,
+            tx,
+        )?))
+    }
+
+    /// the index is traversed once per record of p1, then one block is read for each output record
+    /// B(indexjoin(p1, p2)) = B(p1) + R(p1) * B(index) + R(indexjoin(p1, p2))
+    fn blocks_accessed(&self) -> i32 {
+        self.p1.blocks_accessed()
+            + self.p1.records_output() * self.idx_blocks
+            + self.records_output()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.p1.records_output() * self.ii.records_output()
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        if self.p1.schema().has_field(fldname) {
+            self.p1.distinct_values(fldname)
+        } else {
+            self.p2.distinct_values(fldname)
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
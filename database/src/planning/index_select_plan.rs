@@ -0,0 +1,98 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::metadata_management::index_mgr::IndexInfo;
+use crate::planning::plan::Plan;
+use crate::planning::table_plan::TablePlan;
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::scans::index_select_scan::IndexSelectScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the select relational algebra operator that reads the table through an index
+/// it selects the records whose indexed field equals the constant,
+/// or lies in a range of values if the index supports ranges
+#[derive(Clone)]
+pub struct IndexSelectPlan {
+    p: TablePlan,
+    ii: IndexInfo,
+    lo: Option<Constant>,
+    hi: Option<Constant>,
+    idx_blocks: i32,
+}
+
+impl IndexSelectPlan {
+    pub fn new(p: TablePlan, ii: IndexInfo, val: Constant, tx: &mut Transaction) -> Self {
+        return Self::new_range(p, ii, Some(val.clone()), Some(val), tx);
+    }
+
+    /// @param lo, hi: the inclusive bounds of the indexed field, none if unbounded
+    pub fn new_range(
+        p: TablePlan,
+        ii: IndexInfo,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> Self {
+        // the index cost depends on the block size, so it is estimated here
+        let idx_blocks = ii.blocks_accessed(tx);
+        Self {
+            p,
+            ii,
+            lo,
+            hi,
+            idx_blocks,
+        }
+    }
+
+    fn is_equality(&self) -> bool {
+        self.lo.is_some() && self.lo == self.hi
+    }
+}
+
+impl Plan for IndexSelectPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let ts = self.p.table_scan(tx)?;
+        let idx = self.ii.open(tx)?;
+        Ok(ScanType::IndexSelectScan(IndexSelectScan::new(
+            ts,
+            idx,
+            self.lo.clone(),
+            self.hi.clone(),
+            tx,
+        )?))
+    }
+
+    /// the index is traversed, then one block is read for each matching record
+    fn blocks_accessed(&self) -> i32 {
+        self.idx_blocks + self.records_output()
+    }
+
+    /// like a range comparison in a predicate, each bound keeps about a third of the records
+    /// a range of integers holds at most one distinct value per integer
+    fn records_output(&self) -> i32 {
+        if self.is_equality() {
+            return self.ii.records_output();
+        }
+        let bounds = self.lo.is_some() as u32 + self.hi.is_some() as u32;
+        let records = self.p.records_output() / 3_i32.pow(bounds);
+        match (&self.lo, &self.hi) {
+            (Some(Constant::Int(lo)), Some(Constant::Int(hi))) => {
+                let width = (*hi as i64 - *lo as i64 + 1).max(0);
+                let in_width = width.saturating_mul(self.ii.records_output() as i64);
+                std::cmp::min(records as i64, in_width) as i32
+            }
+            _ => records,
+        }
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        if self.is_equality() {
+            return self.ii.distinct_values(fldname.clone());
+        }
+        std::cmp::min(self.p.distinct_values(fldname), self.records_output())
+    }
+
+    fn schema(&self) -> Schema {
+        self.p.schema()
+    }
+}
@@ -1 +1,99 @@
-pub struct Plan {}
+use crate::error::DbResult;
+use crate::planning::extend_plan::ExtendPlan;
+use crate::planning::index_join_plan::IndexJoinPlan;
+use crate::planning::index_select_plan::IndexSelectPlan;
+use crate::planning::product_plan::ProductPlan;
+use crate::planning::project_plan::ProjectPlan;
+use crate::planning::select_plan::SelectPlan;
+use crate::planning::table_plan::TablePlan;
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::transaction_manager::transaction::Transaction;
+
+/// A plan is a node of a query tree.
+/// It can estimate the cost of the query without running it, and open a scan for it.
+pub trait Plan {
+    /// open a scan corresponding to this plan
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType>;
+    /// estimated number of block accesses needed to run the query
+    fn blocks_accessed(&self) -> i32;
+    /// estimated number of records in the query's output table
+    fn records_output(&self) -> i32;
+    /// estimated number of distinct values of the field in the query's output table
+    fn distinct_values(&self, fldname: &String) -> i32;
+    /// schema of the query's output table
+    fn schema(&self) -> Schema;
+}
+
+#[derive(Clone)]
+pub enum PlanType {
+    TablePlan(TablePlan),
+    SelectPlan(SelectPlan),
+    ProjectPlan(ProjectPlan),
+    ProductPlan(ProductPlan),
+    IndexSelectPlan(IndexSelectPlan),
+    IndexJoinPlan(IndexJoinPlan),
+    ExtendPlan(ExtendPlan),
+}
+
+impl Plan for PlanType {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        match self {
+            PlanType::TablePlan(p) => p.open(tx),
+            PlanType::SelectPlan(p) => p.open(tx),
+            PlanType::ProjectPlan(p) => p.open(tx),
+            PlanType::ProductPlan(p) => p.open(tx),
+            PlanType::IndexSelectPlan(p) => p.open(tx),
+            PlanType::IndexJoinPlan(p) => p.open(tx),
+            PlanType::ExtendPlan(p) => p.open(tx),
+        }
+    }
+
+    fn blocks_accessed(&self) -> i32 {
+        match self {
+            PlanType::TablePlan(p) => p.blocks_accessed(),
+            PlanType::SelectPlan(p) => p.blocks_accessed(),
+            PlanType::ProjectPlan(p) => p.blocks_accessed(),
+            PlanType::ProductPlan(p) => p.blocks_accessed(),
+            PlanType::IndexSelectPlan(p) => p.blocks_accessed(),
+            PlanType::IndexJoinPlan(p) => p.blocks_accessed(),
+            PlanType::ExtendPlan(p) => p.blocks_accessed(),
+        }
+    }
+
+    fn records_output(&self) -> i32 {
+        match self {
+            PlanType::TablePlan(p) => p.records_output(),
+            PlanType::SelectPlan(p) => p.records_output(),
+            PlanType::ProjectPlan(p) => p.records_output(),
+            PlanType::ProductPlan(p) => p.records_output(),
+            PlanType::IndexSelectPlan(p) => p.records_output(),
+            PlanType::IndexJoinPlan(p) => p.records_output(),
+            PlanType::ExtendPlan(p) => p.records_output(),
+        }
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        match self {
+            PlanType::TablePlan(p) => p.distinct_values(fldname),
+            PlanType::SelectPlan(p) => p.distinct_values(fldname),
+            PlanType::ProjectPlan(p) => p.distinct_values(fldname),
+            PlanType::ProductPlan(p) => p.distinct_values(fldname),
+            PlanType::IndexSelectPlan(p) => p.distinct_values(fldname),
+            PlanType::IndexJoinPlan(p) => p.distinct_values(fldname),
+            PlanType::ExtendPlan(p) => p.distinct_values(fldname),
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        match self {
+            PlanType::TablePlan(p) => p.schema(),
+            PlanType::SelectPlan(p) => p.schema(),
+            PlanType::ProjectPlan(p) => p.schema(),
+            PlanType::ProductPlan(p) => p.schema(),
+            PlanType::IndexSelectPlan(p) => p.schema(),
+            PlanType::IndexJoinPlan(p) => p.schema(),
+            PlanType::ExtendPlan(p) => p.schema(),
+        }
+    }
+}
@@ -0,0 +1,999 @@
+use crate::error::{DbError, DbResult};
+use crate::parser::parse_error::ParseError;
+use crate::parser::parser::{Object, Parser, QueryData};
+use crate::planning::plan::PlanType;
+use crate::planning::query_planner::QueryPlanner;
+use crate::planning::update_planner::UpdatePlanner;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the entry point for running SQL statements
+/// it parses the statement and hands the parser output to the query or update planner
+pub struct Planner {
+    qplanner: QueryPlanner,
+    uplanner: UpdatePlanner,
+}
+
+impl Planner {
+    pub fn new(qplanner: QueryPlanner, uplanner: UpdatePlanner) -> Self {
+        Self { qplanner, uplanner }
+    }
+
+    /// create a plan for the SQL select statement
+    pub fn create_query_plan(&mut self, qry: String, tx: &mut Transaction) -> DbResult<PlanType> {
+        let mut parser = Parser::new(qry);
+        let data = parser.query()?;
+        self.qplanner.create_plan(data, tx)
+    }
+
+    /// create a plan for the parsed select statement
+    pub fn create_plan(&mut self, data: QueryData, tx: &mut Transaction) -> DbResult<PlanType> {
+        self.qplanner.create_plan(data, tx)
+    }
+
+    /// execute the SQL insert, delete, update or create statement
+    /// @return: the number of affected records
+    pub fn execute_update(&mut self, cmd: String, tx: &mut Transaction) -> DbResult<i32> {
+        let parser = Parser::new(cmd);
+        self.execute(parser.sql()?, tx)
+    }
+
+    /// execute the parsed statement other than a select, in the transaction
+    /// @return: the number of affected records
+    pub fn execute(&mut self, obj: Object, tx: &mut Transaction) -> DbResult<i32> {
+        match obj {
+            Object::Insert(d) => self.uplanner.execute_insert(d, tx),
+            Object::Delete(d) => self.uplanner.execute_delete(d, tx),
+            Object::Modify(d) => self.uplanner.execute_modify(d, tx),
+            Object::CreateTable(d) => self.uplanner.execute_create_table(d, tx),
+            Object::CreateView(d) => self.uplanner.execute_create_view(d, tx),
+            Object::CreateIndex(d) => self.uplanner.execute_create_index(d, tx),
+            Object::Vacuum(tblname) => self.uplanner.execute_vacuum(tblname, tx),
+            Object::SetIsolation(level) => {
+                tx.set_isolation_level(level);
+                Ok(0)
+            }
+            Object::Savepoint(name) => {
+                tx.savepoint(name);
+                Ok(0)
+            }
+            Object::RollbackToSavepoint(name) => match tx.rollback_to_savepoint(&name)? {
+                true => Ok(0),
+                false => Err(DbError::Transaction(format!(
+                    "savepoint {} not found",
+                    name
+                ))),
+            },
+            Object::ReleaseSavepoint(name) => match tx.release_savepoint(&name) {
+                true => Ok(0),
+                false => Err(DbError::Transaction(format!(
+                    "savepoint {} not found",
+                    name
+                ))),
+            },
+            Object::Query(_) => Err(DbError::Parse(Box::new(ParseError::new(
+                "select",
+                0,
+                "select".len(),
+                Vec::new(),
+                Some("a select statement is not an update command".to_string()),
+            )))),
+            Object::Begin | Object::Commit | Object::Rollback => Err(DbError::Transaction(
+                "the transaction of a statement cannot begin or end it".to_string(),
+            )),
+        }
+    }
+
+    /// reclaim the record versions of the table that no transaction can see any more
+    /// @return: the number of reclaimed versions
+    pub fn vacuum(&mut self, tblname: String, tx: &mut Transaction) -> DbResult<i32> {
+        self.uplanner.execute_vacuum(tblname, tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Constant;
+    use crate::indexing::index::Index;
+    use crate::metadata_management::metadata_mgr::MetadataMgr;
+    use crate::planning::plan::Plan;
+    use crate::record_management::table_scan::TableScan;
+    use crate::scans::common::Scan;
+    use crate::transaction_manager::version_manager::TxMode;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_planner() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannertest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        let n = planner.execute_update(
+            "CREATE TABLE student (sid INT, sname VARCHAR(10), majorid INT)".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 0);
+        let n = planner.execute_update(
+            "CREATE TABLE dept (did INT, dname VARCHAR(10))".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 0);
+        for (sid, sname, majorid) in [(1, "joe", 10), (2, "amy", 20), (3, "max", 10)] {
+            let n = planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, sname, majorid) VALUES ({}, '{}', {})",
+                    sid, sname, majorid
+                ),
+                &mut tx,
+            )?;
+            assert_eq!(n, 1);
+        }
+        planner.execute_update(
+            "INSERT INTO dept (did, dname) VALUES (10, 'compsci')".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "INSERT INTO dept (did, dname) VALUES (20, 'math')".to_string(),
+            &mut tx,
+        )?;
+
+        // unknown columns are rejected before the scan is opened
+        assert!(matches!(
+            planner.create_query_plan("SELECT sid, age FROM student".to_string(), &mut tx),
+            Err(DbError::Catalog(msg)) if msg.contains("age")
+        ));
+
+        // a bad insert is rejected before any record is inserted
+        for (cmd, is_type_error) in [
+            ("INSERT INTO student (sid, sname) VALUES (4)", true),
+            ("INSERT INTO student (sid, sid) VALUES (4, 5)", false),
+            ("INSERT INTO student (sname, sid) VALUES ('amy', 'x')", true),
+            (
+                "INSERT INTO student (sid, sname) VALUES (4, 'abcdefghijk')",
+                true,
+            ),
+            ("INSERT INTO student (sid, age) VALUES (4, 20)", false),
+        ] {
+            match planner.execute_update(cmd.to_string(), &mut tx) {
+                Err(DbError::Type(_)) => assert!(is_type_error, "{}", cmd),
+                Err(DbError::Catalog(_)) => assert!(!is_type_error, "{}", cmd),
+                r => panic!("{} gave {:?}", cmd, r),
+            }
+        }
+
+        // a table cannot be created twice
+        assert!(matches!(
+            planner.execute_update("CREATE TABLE dept (did INT)".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        // field order follows the select list
+        let p = planner.create_query_plan("SELECT sname, sid FROM student".to_string(), &mut tx)?;
+        assert_eq!(p.schema().get_fields(), vec!["sname", "sid"]);
+
+        // single table
+        let p = planner.create_query_plan("SELECT sid, sname FROM student".to_string(), &mut tx)?;
+        assert_eq!(p.records_output(), 3);
+        assert_eq!(p.schema().get_fields().len(), 2);
+        let mut s = p.open(&mut tx)?;
+        let mut sids = Vec::new();
+        while s.next(&mut tx)? {
+            sids.push(s.get_val("sid".to_string(), &mut tx)?);
+        }
+        s.close(&mut tx);
+        assert_eq!(
+            sids,
+            vec![Constant::Int(1), Constant::Int(2), Constant::Int(3)]
+        );
+
+        // product
+        let p = planner.create_query_plan("SELECT * FROM student, dept".to_string(), &mut tx)?;
+        assert_eq!(p.records_output(), 6);
+        assert_eq!(p.schema().get_fields().len(), 5);
+        let mut s = p.open(&mut tx)?;
+        let mut n = 0;
+        while s.next(&mut tx)? {
+            n += 1;
+        }
+        s.close(&mut tx);
+        assert_eq!(n, 6);
+
+        // product with selection
+        let p = planner.create_query_plan(
+            "SELECT sname, dname FROM student, dept WHERE majorid = did AND dname = 'math'"
+                .to_string(),
+            &mut tx,
+        )?;
+        let mut s = p.open(&mut tx)?;
+        assert!(s.next(&mut tx)?);
+        assert_eq!(
+            s.get_val("sname".to_string(), &mut tx)?,
+            Constant::String("amy".to_string())
+        );
+        assert!(!s.next(&mut tx)?);
+        s.close(&mut tx);
+
+        // update
+        let n = planner.execute_update(
+            "UPDATE student SET majorid = 20 WHERE sname = 'max'".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 1);
+        let n = planner.execute_update("UPDATE student SET sname = 'bob'".to_string(), &mut tx)?;
+        assert_eq!(n, 3);
+
+        // delete
+        let n = planner.execute_update(
+            "DELETE FROM student WHERE majorid = 20".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 2);
+        let p = planner.create_query_plan("SELECT sid, sname FROM student".to_string(), &mut tx)?;
+        let mut s = p.open(&mut tx)?;
+        assert!(s.next(&mut tx)?);
+        assert_eq!(s.get_val("sid".to_string(), &mut tx)?, Constant::Int(1));
+        assert_eq!(
+            s.get_val("sname".to_string(), &mut tx)?,
+            Constant::String("bob".to_string())
+        );
+        assert!(!s.next(&mut tx)?);
+        s.close(&mut tx);
+        let n = planner.execute_update("DELETE FROM student".to_string(), &mut tx)?;
+        assert_eq!(n, 1);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_planner_views() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannerviewtest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, sname VARCHAR(10), majorid INT)".to_string(),
+            &mut tx,
+        )?;
+        for (sid, sname, majorid) in [(1, "joe", 10), (2, "amy", 20), (3, "max", 10)] {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, sname, majorid) VALUES ({}, '{}', {})",
+                    sid, sname, majorid
+                ),
+                &mut tx,
+            )?;
+        }
+
+        let n = planner.execute_update(
+            "CREATE VIEW cs AS SELECT sid, sname FROM student WHERE majorid = 10".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 0);
+        // a view on a view
+        planner.execute_update(
+            "CREATE VIEW joe AS SELECT sid FROM cs WHERE sname = 'joe'".to_string(),
+            &mut tx,
+        )?;
+
+        let p = planner.create_query_plan("SELECT sname FROM cs".to_string(), &mut tx)?;
+        let mut s = p.open(&mut tx)?;
+        let mut names = Vec::new();
+        while s.next(&mut tx)? {
+            names.push(s.get_val("sname".to_string(), &mut tx)?);
+        }
+        s.close(&mut tx);
+        assert_eq!(
+            names,
+            vec![
+                Constant::String("joe".to_string()),
+                Constant::String("max".to_string())
+            ]
+        );
+
+        let p = planner.create_query_plan("SELECT * FROM joe".to_string(), &mut tx)?;
+        assert_eq!(p.schema().get_fields(), vec!["sid"]);
+        let mut s = p.open(&mut tx)?;
+        assert!(s.next(&mut tx)?);
+        assert_eq!(s.get_val("sid".to_string(), &mut tx)?, Constant::Int(1));
+        assert!(!s.next(&mut tx)?);
+        s.close(&mut tx);
+
+        // fields hidden by the view are not visible through it
+        assert!(matches!(
+            planner.create_query_plan("SELECT majorid FROM cs".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        // a view on a missing table is rejected
+        let e = planner
+            .execute_update(
+                "CREATE VIEW bad AS SELECT a FROM nosuchtable".to_string(),
+                &mut tx,
+            )
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "view bad is invalid: table nosuchtable not found"
+        );
+        assert!(matches!(
+            planner.create_query_plan("SELECT * FROM nosuchtable".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        // a view cannot take the name of a table or of another view
+        for sql in [
+            "CREATE VIEW student AS SELECT sid FROM student",
+            "CREATE VIEW cs AS SELECT sid FROM student",
+        ] {
+            let e = planner
+                .execute_update(sql.to_string(), &mut tx)
+                .unwrap_err();
+            assert!(e.to_string().ends_with("already exists"), "{}", e);
+        }
+
+        // a quote inside a constant survives the stored definition
+        planner.execute_update(
+            "INSERT INTO student (sid, sname, majorid) VALUES (4, 'it''s', 30)".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "CREATE VIEW quoted AS SELECT sid FROM student WHERE sname = 'it''s'".to_string(),
+            &mut tx,
+        )?;
+        let viewdef = db
+            .metadata_mgr()?
+            .lock()
+            .unwrap()
+            .get_view_def("quoted".to_string(), &mut tx)?;
+        assert_eq!(
+            viewdef,
+            Some("select sid from student where sname = 'it''s'".to_string())
+        );
+        let p = planner.create_query_plan("SELECT sid FROM quoted".to_string(), &mut tx)?;
+        let mut s = p.open(&mut tx)?;
+        assert!(s.next(&mut tx)?);
+        assert_eq!(s.get_val("sid".to_string(), &mut tx)?, Constant::Int(4));
+        assert!(!s.next(&mut tx)?);
+        s.close(&mut tx);
+
+        // a definition with anything after the query is reported instead of being cut short
+        db.metadata_mgr()?.lock().unwrap().create_view(
+            "trailing".to_string(),
+            &mut tx,
+            "select sid from student where sname = 'it' s'".to_string(),
+        )?;
+        assert!(matches!(
+            planner.create_query_plan("SELECT sid FROM trailing".to_string(), &mut tx),
+            Err(DbError::Corruption(msg)) if msg.starts_with("view trailing has an invalid definition")
+        ));
+
+        // views that are defined in terms of each other are rejected when they are expanded
+        {
+            let mdm = db.metadata_mgr()?;
+            let mut mdm = mdm.lock().unwrap();
+            mdm.create_view("v1".to_string(), &mut tx, "select sid from v2".to_string())?;
+            mdm.create_view("v2".to_string(), &mut tx, "select sid from v1".to_string())?;
+        }
+        assert!(matches!(
+            planner.create_query_plan("SELECT sid FROM v1".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    /// look up the sids of the student records having the value through the index on fldname
+    fn index_lookup(
+        mdm: &Arc<Mutex<MetadataMgr>>,
+        fldname: &str,
+        val: Constant,
+        tx: &mut Transaction,
+    ) -> Vec<i32> {
+        let mut mdm = mdm.lock().unwrap();
+        let ii = mdm
+            .get_index_info("student".to_string(), tx)
+            .unwrap()
+            .remove(fldname)
+            .unwrap();
+        let layout = mdm.get_layout("student".to_string(), tx).unwrap();
+        let mut ts = TableScan::new(tx, "student".to_string(), layout).unwrap();
+        let mut idx = ii.open(tx).unwrap();
+        let mut sids = Vec::new();
+        idx.before_first(val, tx).unwrap();
+        while idx.next(tx).unwrap() {
+            let rid = idx.get_data_rid(tx).unwrap();
+            ts.move_to_rid(tx, rid).unwrap();
+            sids.push(ts.get_int(tx, &"sid".to_string()).unwrap());
+        }
+        idx.close(tx);
+        ts.close(tx);
+        sids.sort();
+        sids
+    }
+
+    #[test]
+    fn test_planner_indexes() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannerindextest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mdm = db.metadata_mgr()?;
+        let mut planner = Planner::new(
+            QueryPlanner::new(mdm.clone()),
+            UpdatePlanner::new(mdm.clone()),
+        );
+
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, sname VARCHAR(10), majorid INT)".to_string(),
+            &mut tx,
+        )?;
+        for (sid, sname, majorid) in [(1, "joe", 10), (2, "amy", 20), (3, "max", 10)] {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, sname, majorid) VALUES ({}, '{}', {})",
+                    sid, sname, majorid
+                ),
+                &mut tx,
+            )?;
+        }
+
+        // the index is built from the existing records
+        planner.execute_update(
+            "CREATE INDEX majoridx ON student (majorid) USING BTREE".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "CREATE INDEX snameidx ON student (sname)".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            index_lookup(&mdm, "majorid", Constant::Int(10), &mut tx),
+            vec![1, 3]
+        );
+        assert_eq!(
+            index_lookup(&mdm, "sname", Constant::String("amy".to_string()), &mut tx),
+            vec![2]
+        );
+
+        // insert
+        planner.execute_update(
+            "INSERT INTO student (sid, sname, majorid) VALUES (4, 'sue', 10)".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            index_lookup(&mdm, "majorid", Constant::Int(10), &mut tx),
+            vec![1, 3, 4]
+        );
+
+        // update moves the index record to the new value
+        planner.execute_update(
+            "UPDATE student SET majorid = 20 WHERE sid = 3".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            index_lookup(&mdm, "majorid", Constant::Int(10), &mut tx),
+            vec![1, 4]
+        );
+        assert_eq!(
+            index_lookup(&mdm, "majorid", Constant::Int(20), &mut tx),
+            vec![2, 3]
+        );
+
+        // delete removes the record from every index
+        planner.execute_update("DELETE FROM student WHERE sid = 1".to_string(), &mut tx)?;
+        assert_eq!(
+            index_lookup(&mdm, "majorid", Constant::Int(10), &mut tx),
+            vec![4]
+        );
+        assert!(
+            index_lookup(&mdm, "sname", Constant::String("joe".to_string()), &mut tx).is_empty()
+        );
+
+        assert!(matches!(
+            planner.execute_update(
+                "CREATE INDEX bad ON student (nosuchfield)".to_string(),
+                &mut tx
+            ),
+            Err(DbError::Catalog(_))
+        ));
+        assert!(matches!(
+            planner.execute_update("CREATE INDEX bad ON nosuchtable (sid)".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+        assert!(matches!(
+            planner.execute_update(
+                "CREATE INDEX again ON student (majorid)".to_string(),
+                &mut tx
+            ),
+            Err(DbError::Catalog(_))
+        ));
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_planner_index_plans() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannerindexplantest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, sname VARCHAR(10), majorid INT)".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "CREATE TABLE dept (did INT, dname VARCHAR(10))".to_string(),
+            &mut tx,
+        )?;
+        // statistics are cached, so fill the small table first
+        for i in 0..10 {
+            planner.execute_update(
+                format!("INSERT INTO dept (did, dname) VALUES ({}, 'd{}')", i, i),
+                &mut tx,
+            )?;
+        }
+
+        for i in 0..200 {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, sname, majorid) VALUES ({}, 's{}', {})",
+                    i,
+                    i,
+                    i % 10
+                ),
+                &mut tx,
+            )?;
+        }
+        let select = "SELECT sname FROM student WHERE sid = 57".to_string();
+        let join = "SELECT sname, dname FROM dept, student WHERE did = majorid".to_string();
+        let range = "SELECT sid FROM student WHERE sid >= 10 AND 15 > sid".to_string();
+        let select_cost = planner
+            .create_query_plan(select.clone(), &mut tx)?
+            .blocks_accessed();
+        let range_cost = planner
+            .create_query_plan(range.clone(), &mut tx)?
+            .blocks_accessed();
+        let join_cost = planner
+            .create_query_plan(join.clone(), &mut tx)?
+            .blocks_accessed();
+
+        planner.execute_update(
+            "CREATE INDEX sididx ON student (sid) USING BTREE".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "CREATE INDEX majoridx ON student (majorid)".to_string(),
+            &mut tx,
+        )?;
+
+        // the index select reads only the matching record
+        let p = planner.create_query_plan(select, &mut tx)?;
+        assert!(p.blocks_accessed() < select_cost);
+        let mut s = p.open(&mut tx)?;
+        assert!(s.next(&mut tx)?);
+        assert_eq!(s.get_string("sname".to_string(), &mut tx)?, "s57");
+        assert!(!s.next(&mut tx)?);
+        s.close(&mut tx);
+
+        // the index join probes student once per dept record
+        let p = planner.create_query_plan(join, &mut tx)?;
+        assert!(p.blocks_accessed() < join_cost);
+        let mut s = p.open(&mut tx)?;
+        let mut n = 0;
+        while s.next(&mut tx)? {
+            let sname = s.get_string("sname".to_string(), &mut tx)?;
+            let sid = sname[1..].parse::<i32>()?;
+            assert_eq!(
+                s.get_string("dname".to_string(), &mut tx)?,
+                format!("d{}", sid % 10)
+            );
+            n += 1;
+        }
+        assert_eq!(n, 200);
+        s.close(&mut tx);
+
+        // the range select reads the records of the range through the B-tree index
+        let p = planner.create_query_plan(range.clone(), &mut tx)?;
+        assert!(p.blocks_accessed() < range_cost);
+        assert_eq!(sids_of(&p, &mut tx)?, (10..15).collect::<Vec<i32>>());
+        // with one bound the range is too wide to beat a full scan
+        let p = planner.create_query_plan(
+            "SELECT sid FROM student WHERE sid >= 190".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(p.blocks_accessed(), select_cost);
+        assert_eq!(sids_of(&p, &mut tx)?, (190..200).collect::<Vec<i32>>());
+        // a hash index cannot scan a range
+        let p = planner.create_query_plan(
+            "SELECT sid FROM student WHERE majorid > 3 AND majorid <= 4".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(p.blocks_accessed(), select_cost);
+        assert_eq!(sids_of(&p, &mut tx)?.len(), 20);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    /// the sorted sids of the records of the plan
+    fn sids_of(p: &PlanType, tx: &mut Transaction) -> Result<Vec<i32>> {
+        let mut s = p.open(tx)?;
+        let mut sids = Vec::new();
+        while s.next(tx)? {
+            sids.push(s.get_int("sid".to_string(), tx)?);
+        }
+        s.close(tx);
+        sids.sort();
+        Ok(sids)
+    }
+
+    /// the sids of the records selected by the query, in scan order
+    fn query_sids(planner: &mut Planner, qry: &str, tx: &mut Transaction) -> Result<Vec<i32>> {
+        let p = planner.create_query_plan(qry.to_string(), tx)?;
+        let mut s = p.open(tx)?;
+        let mut sids = Vec::new();
+        while s.next(tx)? {
+            sids.push(s.get_int("sid".to_string(), tx)?);
+        }
+        s.close(tx);
+        Ok(sids)
+    }
+
+    #[test]
+    fn test_planner_predicates() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannerpredtest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, age INT, dept VARCHAR(5))".to_string(),
+            &mut tx,
+        )?;
+        for (sid, age, dept) in [
+            (1, 17, "x"),
+            (2, 18, "x"),
+            (3, 19, "y"),
+            (4, 20, "z"),
+            (5, 21, "y"),
+        ] {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, age, dept) VALUES ({}, {}, '{}')",
+                    sid, age, dept
+                ),
+                &mut tx,
+            )?;
+        }
+
+        let cases: Vec<(&str, Vec<i32>)> = vec![
+            ("age >= 18 AND (dept = 'x' OR dept = 'y')", vec![2, 3, 5]),
+            ("age < 19", vec![1, 2]),
+            ("age > 19", vec![4, 5]),
+            ("age <= 18", vec![1, 2]),
+            ("dept != 'y'", vec![1, 2, 4]),
+            ("dept <> 'y'", vec![1, 2, 4]),
+            ("NOT dept = 'y'", vec![1, 2, 4]),
+            ("NOT (age > 17 AND dept = 'x')", vec![1, 3, 4, 5]),
+            ("sid = 1 OR sid = 2 OR sid = 5", vec![1, 2, 5]),
+            ("(sid = 1 OR sid = 2) AND (sid = 2 OR sid = 3)", vec![2]),
+            // comparisons with null are unknown, and unknown is not selected
+            ("sid = NULL", vec![]),
+            ("NOT sid = NULL", vec![]),
+            ("sid = 1 OR sid = NULL", vec![1]),
+            ("NOT (sid = 1 OR sid = NULL)", vec![]),
+            ("NOT (sid = 1 AND sid = NULL)", vec![2, 3, 4, 5]),
+        ];
+        for (pred, expected) in cases {
+            let qry = format!("SELECT sid FROM student WHERE {}", pred);
+            assert_eq!(
+                query_sids(&mut planner, &qry, &mut tx)?,
+                expected,
+                "{}",
+                qry
+            );
+        }
+
+        // the predicate survives the view definition
+        planner.execute_update(
+            "CREATE VIEW v AS SELECT sid, age FROM student WHERE NOT age < 18 AND (dept = 'x' OR dept = 'z')".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            query_sids(&mut planner, "SELECT sid FROM v", &mut tx)?,
+            vec![2, 4]
+        );
+
+        let n = planner.execute_update(
+            "DELETE FROM student WHERE age >= 20 OR dept = 'x'".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 4);
+        assert_eq!(
+            query_sids(&mut planner, "SELECT sid FROM student", &mut tx)?,
+            vec![3]
+        );
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_planner_expressions() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannerexprtest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, name VARCHAR(8), age INT)".to_string(),
+            &mut tx,
+        )?;
+        for (sid, name, age) in [(1, "amy", 17), (2, "Bob", 20), (3, "carol", 23)] {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, name, age) VALUES ({}, '{}', {})",
+                    sid, name, age
+                ),
+                &mut tx,
+            )?;
+        }
+
+        // computed fields in the select list
+        let p = planner.create_query_plan(
+            "SELECT sid, (age + 1) * 2 - sid % 2 AS x, UPPER(name) || '_' || LOWER(name) AS names, \
+             LENGTH(name), SUBSTR(name, 2, 2) AS mid, ABS(-age) AS a, COALESCE(NULL, sid) AS c \
+             FROM student WHERE age - 20 >= -3 AND (age + 1) / 3 != 8"
+                .to_string(),
+            &mut tx,
+        )?;
+        let sch = p.schema();
+        assert_eq!(
+            sch.get_fields(),
+            vec!["sid", "x", "names", "length(name)", "mid", "a", "c"]
+        );
+        assert_eq!(sch.get_length(&"names".to_string())?, 17);
+        let mut s = p.open(&mut tx)?;
+        let mut rows = Vec::new();
+        while s.next(&mut tx)? {
+            rows.push((
+                s.get_int("sid".to_string(), &mut tx)?,
+                s.get_int("x".to_string(), &mut tx)?,
+                s.get_string("names".to_string(), &mut tx)?,
+                s.get_int("length(name)".to_string(), &mut tx)?,
+                s.get_string("mid".to_string(), &mut tx)?,
+                s.get_int("a".to_string(), &mut tx)?,
+                s.get_int("c".to_string(), &mut tx)?,
+            ));
+        }
+        s.close(&mut tx);
+        assert_eq!(
+            rows,
+            vec![
+                (1, 35, "AMY_amy".to_string(), 3, "my".to_string(), 17, 1),
+                (2, 42, "BOB_bob".to_string(), 3, "ob".to_string(), 20, 2),
+            ]
+        );
+
+        // an alias of a field and unary minus
+        assert_eq!(
+            query_sids(
+                &mut planner,
+                "SELECT -sid AS x, sid FROM student WHERE -age < -20",
+                &mut tx
+            )?,
+            vec![3]
+        );
+
+        // expressions survive the view definition
+        planner.execute_update(
+            "CREATE VIEW v AS SELECT sid, age * 2 AS dbl FROM student WHERE age - 1 > 17"
+                .to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            query_sids(&mut planner, "SELECT sid FROM v WHERE dbl = 40", &mut tx)?,
+            vec![2]
+        );
+
+        // the new value of an update is computed from the record
+        let n = planner.execute_update(
+            "UPDATE student SET age = age * 10 + sid WHERE LENGTH(name) = 3".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(n, 2);
+        planner.execute_update(
+            "UPDATE student SET name = name || '_x' WHERE sid = 3".to_string(),
+            &mut tx,
+        )?;
+        assert_eq!(
+            query_sids(
+                &mut planner,
+                "SELECT sid FROM student WHERE age = 171 OR age = 202 OR name = 'carol_x'",
+                &mut tx
+            )?,
+            vec![1, 2, 3]
+        );
+
+        // expressions are type checked before execution
+        for qry in [
+            "SELECT name + 1 AS x FROM student",
+            "SELECT sid FROM student WHERE name = 1",
+            "SELECT sid FROM student WHERE UPPER(age) = 'A'",
+            "SELECT SUBSTR(name) AS x FROM student",
+        ] {
+            assert!(
+                matches!(
+                    planner.create_query_plan(qry.to_string(), &mut tx),
+                    Err(DbError::Type(_))
+                ),
+                "{}",
+                qry
+            );
+        }
+        assert!(matches!(
+            planner.create_query_plan("SELECT height + 1 AS h FROM student".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        // every column of the result has its own name
+        for (qry, msg) in [
+            (
+                "SELECT sid AS age, age FROM student",
+                "alias age is already the name of a field",
+            ),
+            (
+                "SELECT age + 1 AS sid FROM student",
+                "alias sid is already the name of a field",
+            ),
+            (
+                "SELECT sid AS x, age AS x FROM student",
+                "column x is selected more than once",
+            ),
+            (
+                "SELECT sid, sid FROM student",
+                "column sid is selected more than once",
+            ),
+        ] {
+            match planner.create_query_plan(qry.to_string(), &mut tx) {
+                Err(DbError::Catalog(m)) => assert_eq!(m, msg),
+                _ => panic!("{}: expected a catalog error", qry),
+            }
+        }
+        // an alias may repeat the name of the field it renames
+        let p = planner.create_query_plan("SELECT sid AS sid FROM student".to_string(), &mut tx)?;
+        assert_eq!(p.schema().get_fields(), vec!["sid"]);
+
+        for cmd in [
+            "UPDATE student SET age = name",
+            "UPDATE student SET age = NULL",
+            "DELETE FROM student WHERE age = 'x'",
+        ] {
+            assert!(
+                matches!(
+                    planner.execute_update(cmd.to_string(), &mut tx),
+                    Err(DbError::Type(_))
+                ),
+                "{}",
+                cmd
+            );
+        }
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_planner_snapshot() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("plannersnapshottest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+        planner.execute_update(
+            "CREATE TABLE student (sid INT, sname VARCHAR(10), majorid INT)".to_string(),
+            &mut tx,
+        )?;
+        planner.execute_update(
+            "CREATE INDEX majoridx ON student (majorid)".to_string(),
+            &mut tx,
+        )?;
+        for (sid, sname, majorid) in [(1, "joe", 10), (2, "amy", 20), (3, "max", 10)] {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO student (sid, sname, majorid) VALUES ({}, '{}', {})",
+                    sid, sname, majorid
+                ),
+                &mut tx,
+            )?;
+        }
+        tx.commit()?;
+
+        let qry = "SELECT sid FROM student WHERE majorid = 10";
+        let mut snapshot = db.new_tx_with_mode(TxMode::Snapshot);
+        let mut tx = db.new_tx();
+        planner.execute_update("DELETE FROM student WHERE sid = 1".to_string(), &mut tx)?;
+        planner.execute_update(
+            "UPDATE student SET majorid = 10 WHERE sid = 2".to_string(),
+            &mut tx,
+        )?;
+        tx.commit()?;
+
+        // the snapshot does not read through the index, which has lost the deleted record
+        let mut sids = query_sids(&mut planner, qry, &mut snapshot)?;
+        sids.sort();
+        assert_eq!(sids, vec![1, 3]);
+        snapshot.commit()?;
+
+        let mut tx = db.new_tx();
+        let mut sids = query_sids(&mut planner, qry, &mut tx)?;
+        sids.sort();
+        assert_eq!(sids, vec![2, 3]);
+
+        // the deleted record, the replaced version and the two index records
+        assert_eq!(planner.vacuum("student".to_string(), &mut tx)?, 4);
+        assert_eq!(planner.vacuum("student".to_string(), &mut tx)?, 0);
+        assert_eq!(
+            planner.execute_update("VACUUM student".to_string(), &mut tx)?,
+            0
+        );
+        let mut sids = query_sids(&mut planner, qry, &mut tx)?;
+        sids.sort();
+        assert_eq!(sids, vec![2, 3]);
+        assert!(matches!(
+            planner.vacuum("nosuchtable".to_string(), &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use crate::error::DbResult;
+use crate::planning::plan::{Plan, PlanType};
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::scans::production_scan::ProductionScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the product relational algebra operator
+#[derive(Clone)]
+pub struct ProductPlan {
+    p1: Box<PlanType>,
+    p2: Box<PlanType>,
+    schema: Schema,
+}
+
+impl ProductPlan {
+    pub fn new(p1: Box<PlanType>, p2: Box<PlanType>) -> Self {
+        let mut schema = Schema::new();
+        schema.add_all(p1.schema());
+        schema.add_all(p2.schema());
+        Self { p1, p2, schema }
+    }
+}
+
+impl Plan for ProductPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let s1 = self.p1.open(tx)?;
+        let s2 = self.p2.open(tx)?;
+        Ok(ScanType::ProductionScan(ProductionScan::new(
+            Box::new(s1),
+            Box::new(s2),
+            tx,
+        )?))
+    }
+
+    /// the right-hand side is scanned once per record of the left-hand side
+    /// B(product(p1, p2)) = B(p1) + R(p1) * B(p2)
+    fn blocks_accessed(&self) -> i32 {
+        self.p1.blocks_accessed() + self.p1.records_output() * self.p2.blocks_accessed()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.p1.records_output() * self.p2.records_output()
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        if self.p1.schema().has_field(fldname) {
+            self.p1.distinct_values(fldname)
+        } else {
+            self.p2.distinct_values(fldname)
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
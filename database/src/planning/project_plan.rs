@@ -0,0 +1,137 @@
+use crate::error::DbResult;
+use crate::planning::plan::{Plan, PlanType};
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::scans::project_scan::ProjectScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the project relational algebra operator
+#[derive(Clone)]
+pub struct ProjectPlan {
+    p: Box<PlanType>,
+    schema: Schema,
+}
+
+impl ProjectPlan {
+    /// fails if a field is not in the schema of p
+    pub fn new(p: Box<PlanType>, field_list: Vec<String>) -> DbResult<Self> {
+        let mut schema = Schema::new();
+        let sch = p.schema();
+        for fldname in field_list {
+            schema.add(fldname, &sch)?;
+        }
+        Ok(Self { p, schema })
+    }
+}
+
+impl Plan for ProjectPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let s = self.p.open(tx)?;
+        Ok(ScanType::ProjectScan(ProjectScan::new(
+            Box::new(s),
+            self.schema.get_fields(),
+        )))
+    }
+
+    fn blocks_accessed(&self) -> i32 {
+        self.p.blocks_accessed()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.p.records_output()
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        self.p.distinct_values(fldname)
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DbError;
+    use crate::scans::common::Scan;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_project_plan() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("projectplantest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update(
+            "CREATE TABLE T (a INT, b VARCHAR(5), c INT)".to_string(),
+            &mut tx,
+        )?;
+        for i in 0..3 {
+            planner.execute_update(
+                format!(
+                    "INSERT INTO T (a, b, c) VALUES ({}, 'b{}', {})",
+                    i,
+                    i,
+                    10 * i
+                ),
+                &mut tx,
+            )?;
+        }
+
+        // the columns come out in the order of the select list
+        let p = planner.create_query_plan("SELECT c, b, a FROM T".to_string(), &mut tx)?;
+        assert_eq!(p.schema().get_fields(), vec!["c", "b", "a"]);
+        let p = planner.create_query_plan("SELECT c, a FROM T".to_string(), &mut tx)?;
+        assert_eq!(p.schema().get_fields(), vec!["c", "a"]);
+        assert_eq!(p.records_output(), 3);
+
+        let mut s = p.open(&mut tx)?;
+        let mut n = 0;
+        while s.next(&mut tx)? {
+            let a = s.get_int("a".to_string(), &mut tx)?;
+            assert_eq!(s.get_int("c".to_string(), &mut tx)?, 10 * a);
+            // a field that is not projected cannot be read
+            assert!(!s.has_field("b".to_string()));
+            assert!(matches!(
+                s.get_string("b".to_string(), &mut tx),
+                Err(DbError::Catalog(_))
+            ));
+            n += 1;
+        }
+        assert_eq!(n, 3);
+        s.close(&mut tx);
+
+        // unknown columns are rejected when the query is planned
+        for q in ["SELECT a, d FROM T", "SELECT d FROM T WHERE a = 1"] {
+            assert!(matches!(
+                planner.create_query_plan(q.to_string(), &mut tx),
+                Err(DbError::Catalog(msg)) if msg == "field d not found"
+            ));
+        }
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{DbError, DbResult};
+use crate::metadata_management::index_mgr::IndexInfo;
+use crate::metadata_management::metadata_mgr::MetadataMgr;
+use crate::parser::parser::{Parser, QueryData, QueryFields};
+use crate::planning::extend_plan::ExtendPlan;
+use crate::planning::index_join_plan::IndexJoinPlan;
+use crate::planning::index_select_plan::IndexSelectPlan;
+use crate::planning::plan::{Plan, PlanType};
+use crate::planning::product_plan::ProductPlan;
+use crate::planning::project_plan::ProjectPlan;
+use crate::planning::select_plan::SelectPlan;
+use crate::planning::table_plan::TablePlan;
+use crate::scans::predicate::Predicate;
+use crate::transaction_manager::transaction::Transaction;
+use crate::transaction_manager::version_manager::TxMode;
+
+/// a stored table mentioned in the query, together with its indexes
+struct IndexedTable {
+    tp: TablePlan,
+    indexes: HashMap<String, IndexInfo>,
+}
+
+/// the query planner
+/// it joins the tables in the order they are mentioned, selects on the predicate and projects the result
+/// a table is read through an index, and joined through an index of the right-hand table,
+/// when the estimated number of block accesses is smaller than with a full scan
+/// an index only holds the entries of the newest record versions,
+/// so a snapshot transaction always scans the whole table
+pub struct QueryPlanner {
+    mdm: Arc<Mutex<MetadataMgr>>,
+}
+
+impl QueryPlanner {
+    pub fn new(mdm: Arc<Mutex<MetadataMgr>>) -> Self {
+        Self { mdm }
+    }
+
+    /// create a query plan from the parsed query
+    /// fails if a table does not exist, an expression mentions a field that none of the tables has,
+    /// or an expression does not type check
+    pub fn create_plan(&mut self, data: QueryData, tx: &mut Transaction) -> DbResult<PlanType> {
+        return self.plan_query(data, tx, &mut Vec::new());
+    }
+
+    /// @param views: the views being expanded, to reject a view that is defined in terms of itself
+    fn plan_query(
+        &mut self,
+        data: QueryData,
+        tx: &mut Transaction,
+        views: &mut Vec<String>,
+    ) -> DbResult<PlanType> {
+        // Step 1: create a plan for each mentioned table or view
+        let mut plans: Vec<(PlanType, Option<IndexedTable>)> = Vec::new();
+        for tblname in data.tables.iter() {
+            let viewdef = {
+                let mut mdm = self.mdm.lock().unwrap();
+                mdm.get_view_def(tblname.clone(), tx)?
+            };
+            match viewdef {
+                Some(viewdef) => {
+                    if views.contains(tblname) {
+                        return Err(DbError::Catalog(format!(
+                            "view {} is defined in terms of itself",
+                            tblname
+                        )));
+                    }
+                    // expand the view; its definition may mention other views
+                    let viewdata = Parser::new(viewdef).view_def().map_err(|e| {
+                        DbError::Corruption(format!(
+                            "view {} has an invalid definition: {}",
+                            tblname, e
+                        ))
+                    })?;
+                    views.push(tblname.clone());
+                    let p = self.plan_query(viewdata, tx, views);
+                    views.pop();
+                    match p {
+                        Ok(p) => plans.push((p, None)),
+                        Err(e) if e.is_abort() => return Err(e),
+                        Err(e) => {
+                            return Err(DbError::Catalog(format!(
+                                "view {} is invalid: {}",
+                                tblname, e
+                            )))
+                        }
+                    }
+                }
+                None => {
+                    let (tp, indexes) = {
+                        let mut mdm = self.mdm.lock().unwrap();
+                        let tp = TablePlan::new(tblname.clone(), &mut mdm, tx)?;
+                        let indexes = match tx.mode() {
+                            TxMode::Locking => mdm.get_index_info(tblname.clone(), tx)?,
+                            TxMode::Snapshot => HashMap::new(),
+                        };
+                        (tp, indexes)
+                    };
+                    let table = IndexedTable { tp, indexes };
+                    let p = Self::make_select_plan(&table, &data.pred, tx);
+                    plans.push((p, Some(table)));
+                }
+            }
+        }
+
+        // Step 2: join the plans in order
+        let mut plans = plans.into_iter();
+        let (mut p, _) = plans.next().expect("query has no table");
+        for (next, table) in plans {
+            p = Self::make_join_plan(p, next, table, &data.pred, tx);
+        }
+
+        // Step 3: add a selection plan for the predicate
+        data.pred.check_types(&p.schema())?;
+        p = PlanType::SelectPlan(SelectPlan::new(Box::new(p), data.pred));
+
+        // Step 4: compute the expressions of the select list and project on their names
+        // the names must be unique, and an alias cannot hide a field of the query
+        match data.fields {
+            QueryFields::AllFields => Ok(p),
+            QueryFields::Fields(fields) => {
+                let mut names = Vec::new();
+                let mut computed = Vec::new();
+                for field in fields {
+                    let name = field.name();
+                    if names.contains(&name) {
+                        return Err(DbError::Catalog(format!(
+                            "column {} is selected more than once",
+                            name
+                        )));
+                    }
+                    if field.expr.as_field_name().as_ref() != Some(&name) {
+                        if p.schema().has_field(&name) {
+                            return Err(DbError::Catalog(format!(
+                                "alias {} is already the name of a field",
+                                name
+                            )));
+                        }
+                        computed.push((name.clone(), field.expr));
+                    } else if !p.schema().has_field(&name) {
+                        return Err(DbError::Catalog(format!("field {} not found", name)));
+                    }
+                    names.push(name);
+                }
+                if !computed.is_empty() {
+                    p = PlanType::ExtendPlan(ExtendPlan::new(Box::new(p), computed)?);
+                }
+                Ok(PlanType::ProjectPlan(ProjectPlan::new(Box::new(p), names)?))
+            }
+        }
+    }
+
+    /// read the table through an index if the predicate equates an indexed field with a constant,
+    /// or compares it with constants and the index supports ranges,
+    /// and the index is cheaper than scanning the whole table
+    fn make_select_plan(table: &IndexedTable, pred: &Predicate, tx: &mut Transaction) -> PlanType {
+        let mut best = PlanType::TablePlan(table.tp.clone());
+        for (fldname, ii) in table.indexes.iter() {
+            let p = if let Some(val) = pred.equates_with_constant(fldname.clone()) {
+                IndexSelectPlan::new(table.tp.clone(), ii.clone(), val, tx)
+            } else if let (true, Some((lo, hi))) =
+                (ii.method().supports_range(), pred.range_of(fldname))
+            {
+                IndexSelectPlan::new_range(table.tp.clone(), ii.clone(), lo, hi, tx)
+            } else {
+                continue;
+            };
+            if p.blocks_accessed() < best.blocks_accessed() {
+                best = PlanType::IndexSelectPlan(p);
+            }
+        }
+        best
+    }
+
+    /// join the current plan with the next one
+    /// an index join is used if the predicate equates an indexed field of the next table
+    /// with a field of the current plan and the index join is cheaper than the product
+    fn make_join_plan(
+        current: PlanType,
+        next: PlanType,
+        table: Option<IndexedTable>,
+        pred: &Predicate,
+        tx: &mut Transaction,
+    ) -> PlanType {
+        let mut best =
+            PlanType::ProductPlan(ProductPlan::new(Box::new(current.clone()), Box::new(next)));
+        if let Some(table) = table {
+            let sch = current.schema();
+            for (fldname, ii) in table.indexes.iter() {
+                if let Some(joinfield) = pred.equates_with_field(fldname.clone()) {
+                    if !sch.has_field(&joinfield) || table.tp.schema().has_field(&joinfield) {
+                        continue;
+                    }
+                    let p = IndexJoinPlan::new(
+                        Box::new(current.clone()),
+                        table.tp.clone(),
+                        ii.clone(),
+                        joinfield,
+                        tx,
+                    );
+                    if p.blocks_accessed() < best.blocks_accessed() {
+                        best = PlanType::IndexJoinPlan(p);
+                    }
+                }
+            }
+        }
+        best
+    }
+}
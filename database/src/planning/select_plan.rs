@@ -0,0 +1,58 @@
+use crate::error::DbResult;
+use crate::planning::plan::{Plan, PlanType};
+use crate::record_management::schema::Schema;
+use crate::scans::common::ScanType;
+use crate::scans::predicate::Predicate;
+use crate::scans::select_scan::SelectScan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for the select relational algebra operator
+#[derive(Clone)]
+pub struct SelectPlan {
+    p: Box<PlanType>,
+    pred: Predicate,
+}
+
+impl SelectPlan {
+    pub fn new(p: Box<PlanType>, pred: Predicate) -> Self {
+        Self { p, pred }
+    }
+}
+
+impl Plan for SelectPlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        let s = self.p.open(tx)?;
+        Ok(ScanType::SelectScan(SelectScan::new(
+            Box::new(s),
+            self.pred.clone(),
+        )))
+    }
+
+    /// a select scan reads every block of its underlying scan
+    fn blocks_accessed(&self) -> i32 {
+        self.p.blocks_accessed()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.p.records_output() / self.pred.reduction_factor(&self.p)
+    }
+
+    /// if the predicate equates the field with a constant, there is only one value left
+    /// if it equates the field with another field, the smaller of the two counts survives
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        if self.pred.equates_with_constant(fldname.clone()).is_some() {
+            1
+        } else if let Some(fldname2) = self.pred.equates_with_field(fldname.clone()) {
+            std::cmp::min(
+                self.p.distinct_values(fldname),
+                self.p.distinct_values(&fldname2),
+            )
+        } else {
+            self.p.distinct_values(fldname)
+        }
+    }
+
+    fn schema(&self) -> Schema {
+        self.p.schema()
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::{DbError, DbResult};
+use crate::metadata_management::metadata_mgr::MetadataMgr;
+use crate::metadata_management::state_mgr::StateInfo;
+use crate::planning::plan::Plan;
+use crate::record_management::layout::Layout;
+use crate::record_management::schema::Schema;
+use crate::record_management::table_scan::TableScan;
+use crate::scans::common::ScanType;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the plan for a stored table
+/// the cost estimates come from the statistics held by StatMgr
+#[derive(Clone)]
+pub struct TablePlan {
+    tblname: String,
+    layout: Layout,
+    si: StateInfo,
+}
+
+impl TablePlan {
+    pub fn new(tblname: String, md: &mut MetadataMgr, tx: &mut Transaction) -> DbResult<Self> {
+        if !md.has_table(tblname.clone(), tx)? {
+            return Err(DbError::Catalog(format!("table {} not found", tblname)));
+        }
+        let layout = md.get_layout(tblname.clone(), tx)?;
+        let si = md.get_stat_info(tblname.clone(), layout.clone(), tx)?;
+        Ok(Self {
+            tblname,
+            layout,
+            si,
+        })
+    }
+
+    /// open the table scan itself, for the scans that move to a record by its rid
+    pub fn table_scan(&self, tx: &mut Transaction) -> DbResult<TableScan> {
+        TableScan::new(tx, self.tblname.clone(), self.layout.clone())
+    }
+}
+
+impl Plan for TablePlan {
+    fn open(&self, tx: &mut Transaction) -> DbResult<ScanType> {
+        Ok(ScanType::TableScan(self.table_scan(tx)?))
+    }
+
+    fn blocks_accessed(&self) -> i32 {
+        self.si.blocks_accessed()
+    }
+
+    fn records_output(&self) -> i32 {
+        self.si.records_output()
+    }
+
+    fn distinct_values(&self, fldname: &String) -> i32 {
+        self.si.distinct_values(fldname)
+    }
+
+    fn schema(&self) -> Schema {
+        self.layout.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scans::common::Scan;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_table_plan() -> Result<()> {
+        setup();
+        let db = SimpleDB::new("./db/logtest", "tableplantest", 400, 8)?;
+        let mut tx = db.new_tx();
+        let mut mdm = MetadataMgr::new(true, &mut tx)?;
+
+        let mut sch = Schema::new();
+        sch.add_int_field("A".to_string());
+        sch.add_string_field("B".to_string(), 9);
+        mdm.create_table("T".to_string(), sch, &mut tx)?;
+
+        let layout = mdm.get_layout("T".to_string(), &mut tx);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout?)?;
+        for i in 0..50 {
+            ts.insert(&mut tx)?;
+            ts.set_int(&mut tx, &"A".to_string(), i)?;
+            ts.set_string(&mut tx, &"B".to_string(), format!("rec{}", i))?;
+        }
+        ts.close(&mut tx);
+
+        assert!(matches!(
+            TablePlan::new("U".to_string(), &mut mdm, &mut tx),
+            Err(DbError::Catalog(_))
+        ));
+
+        let p = TablePlan::new("T".to_string(), &mut mdm, &mut tx)?;
+        assert_eq!(p.records_output(), 50);
+        assert_eq!(p.blocks_accessed(), 5); // 12 records per block
+        assert_eq!(p.distinct_values(&"A".to_string()), 1 + 50 / 3);
+        assert_eq!(p.schema().get_fields(), vec!["A", "B"]);
+
+        let mut s = p.open(&mut tx)?;
+        let mut n = 0;
+        while s.next(&mut tx)? {
+            assert_eq!(s.get_int("A".to_string(), &mut tx)?, n);
+            n += 1;
+        }
+        assert_eq!(n, 50);
+        s.close(&mut tx);
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+}
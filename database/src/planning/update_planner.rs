@@ -0,0 +1,417 @@
+use std::sync::{Arc, Mutex};
+
+use crate::common::Constant;
+use crate::error::{DbError, DbResult};
+use crate::indexing::index::Index;
+use crate::metadata_management::metadata_mgr::MetadataMgr;
+use crate::metadata_management::view_mgr::MAX_VIEWDEF;
+use crate::parser::parser::{
+    CreateIndexData, CreateTableData, CreateViewData, DeleteData, InsertData, ModifyData,
+};
+use crate::planning::plan::{Plan, PlanType};
+use crate::planning::query_planner::QueryPlanner;
+use crate::planning::select_plan::SelectPlan;
+use crate::planning::table_plan::TablePlan;
+use crate::record_management::schema::Type;
+use crate::scans::common::{Scan, UpdateScan};
+use crate::transaction_manager::transaction::Transaction;
+
+/// the planner for SQL insert, delete, update and create statements
+/// each method returns the number of affected records
+/// every index on the modified table is kept in sync with its records
+pub struct UpdatePlanner {
+    mdm: Arc<Mutex<MetadataMgr>>,
+}
+
+impl UpdatePlanner {
+    pub fn new(mdm: Arc<Mutex<MetadataMgr>>) -> Self {
+        Self { mdm }
+    }
+
+    /// every value is checked against the schema before the record is inserted
+    pub fn execute_insert(&mut self, data: InsertData, tx: &mut Transaction) -> DbResult<i32> {
+        let p = {
+            let mut mdm = self.mdm.lock().unwrap();
+            PlanType::TablePlan(TablePlan::new(data.tblname.clone(), &mut mdm, tx)?)
+        };
+        let sch = p.schema();
+        if data.flds.len() != data.vals.len() {
+            return Err(DbError::Type(format!(
+                "{} fields are given {} values",
+                data.flds.len(),
+                data.vals.len()
+            )));
+        }
+        let mut vals = Vec::new();
+        for (i, (fldname, val)) in data.flds.iter().zip(data.vals.iter()).enumerate() {
+            if !sch.has_field(fldname) {
+                return Err(DbError::Catalog(format!("field {} not found", fldname)));
+            }
+            if data.flds[..i].contains(fldname) {
+                return Err(DbError::Catalog(format!(
+                    "field {} is given more than once",
+                    fldname
+                )));
+            }
+            let val = match sch.get_type_(fldname)? {
+                Type::INTEGER => match val.parse::<i32>() {
+                    Ok(v) => Constant::Int(v),
+                    Err(_) => {
+                        return Err(DbError::Type(format!(
+                            "value {} of field {} is not an integer",
+                            val, fldname
+                        )))
+                    }
+                },
+                Type::VARCHAR => {
+                    let length = sch.get_length(fldname)?;
+                    if val.len() > length as usize {
+                        return Err(DbError::Type(format!(
+                            "value {} of field {} is longer than {}",
+                            val, fldname, length
+                        )));
+                    }
+                    Constant::String(val.clone())
+                }
+            };
+            vals.push(val);
+        }
+        let indexes = self.mdm.lock().unwrap().get_index_info(data.tblname, tx)?;
+        let mut us = p.open(tx)?;
+        let result = (|| {
+            us.insert(tx)?;
+            let rid = us.get_rid();
+            for (fldname, val) in data.flds.iter().zip(vals) {
+                us.set_val(fldname.clone(), val.clone(), tx)?;
+
+                if let Some(ii) = indexes.get(fldname) {
+                    let mut idx = ii.open(tx)?;
+                    idx.insert(val, rid.clone(), tx)?;
+                    idx.close(tx);
+                }
+            }
+            Ok(1)
+        })();
+        us.close(tx);
+        result
+    }
+
+    /// delete every record of the table that satisfies the predicate
+    pub fn execute_delete(&mut self, data: DeleteData, tx: &mut Transaction) -> DbResult<i32> {
+        let p = {
+            let mut mdm = self.mdm.lock().unwrap();
+            PlanType::TablePlan(TablePlan::new(data.tblname.clone(), &mut mdm, tx)?)
+        };
+        data.pred.check_types(&p.schema())?;
+        let indexes = self.mdm.lock().unwrap().get_index_info(data.tblname, tx)?;
+        let p = PlanType::SelectPlan(SelectPlan::new(Box::new(p), data.pred));
+        let mut us = p.open(tx)?;
+        let mut count = 0;
+        while us.next(tx)? {
+            // first, delete the record's rid from every index
+            let rid = us.get_rid();
+            for (fldname, ii) in indexes.iter() {
+                let val = us.get_val(fldname.clone(), tx)?;
+                let mut idx = ii.open(tx)?;
+                idx.delete(val, rid.clone(), tx)?;
+                idx.close(tx);
+            }
+            // then delete the record
+            us.delete(tx)?;
+            count += 1;
+        }
+        us.close(tx);
+        Ok(count)
+    }
+
+    /// set the field of every record that satisfies the predicate to the new value
+    /// the new value is evaluated against the record being modified
+    /// and must have the type of the field, since null cannot be stored
+    pub fn execute_modify(&mut self, data: ModifyData, tx: &mut Transaction) -> DbResult<i32> {
+        let p = {
+            let mut mdm = self.mdm.lock().unwrap();
+            PlanType::TablePlan(TablePlan::new(data.tblname.clone(), &mut mdm, tx)?)
+        };
+        let sch = p.schema();
+        if !sch.has_field(&data.fldname) {
+            return Err(DbError::Catalog(format!(
+                "field {} not found",
+                data.fldname
+            )));
+        }
+        let fldtype = sch.get_type_(&data.fldname)?;
+        match data.newval.type_of(&sch)? {
+            Some(t) if t == fldtype => {}
+            t => {
+                return Err(DbError::Type(format!(
+                    "cannot set {} field {} to {}",
+                    fldtype.to_str(),
+                    data.fldname,
+                    t.as_ref().map_or("null", |t| t.to_str())
+                )))
+            }
+        }
+        data.pred.check_types(&sch)?;
+        let index = self
+            .mdm
+            .lock()
+            .unwrap()
+            .get_index_info(data.tblname, tx)?
+            .remove(&data.fldname);
+        let p = PlanType::SelectPlan(SelectPlan::new(Box::new(p), data.pred));
+        let mut us = p.open(tx)?;
+        let mut count = 0;
+        while us.next(tx)? {
+            let newval = data.newval.evaluate(&mut us, tx)?;
+            let oldval = us.get_val(data.fldname.clone(), tx)?;
+            us.set_val(data.fldname.clone(), newval.clone(), tx)?;
+
+            // move the index record from the old value to the new one
+            if let Some(ii) = index.as_ref() {
+                let rid = us.get_rid();
+                let mut idx = ii.open(tx)?;
+                idx.delete(oldval, rid.clone(), tx)?;
+                idx.insert(newval, rid, tx)?;
+                idx.close(tx);
+            }
+            count += 1;
+        }
+        us.close(tx);
+        Ok(count)
+    }
+
+    pub fn execute_create_table(
+        &mut self,
+        data: CreateTableData,
+        tx: &mut Transaction,
+    ) -> DbResult<i32> {
+        let mut mdm = self.mdm.lock().unwrap();
+        if mdm.has_table(data.tblname.clone(), tx)?
+            || mdm.get_view_def(data.tblname.clone(), tx)?.is_some()
+        {
+            return Err(DbError::Catalog(format!(
+                "table or view {} already exists",
+                data.tblname
+            )));
+        }
+        mdm.create_table(data.tblname, data.schema, tx)?;
+        Ok(0)
+    }
+
+    /// register the index in the catalog and fill it with the records already in the table
+    pub fn execute_create_index(
+        &mut self,
+        data: CreateIndexData,
+        tx: &mut Transaction,
+    ) -> DbResult<i32> {
+        let mut mdm = self.mdm.lock().unwrap();
+        let p = TablePlan::new(data.tblname.clone(), &mut mdm, tx)?;
+        if !p.schema().has_field(&data.fldname) {
+            return Err(DbError::Catalog(format!(
+                "field {} not found",
+                data.fldname
+            )));
+        }
+        if mdm
+            .get_index_info(data.tblname.clone(), tx)?
+            .contains_key(&data.fldname)
+        {
+            return Err(DbError::Catalog(format!(
+                "field {} of table {} already has an index",
+                data.fldname, data.tblname
+            )));
+        }
+        mdm.create_index(
+            data.idxname.clone(),
+            data.tblname.clone(),
+            data.fldname.clone(),
+            data.method,
+            tx,
+        )?;
+        let ii = mdm
+            .get_index_info(data.tblname, tx)?
+            .remove(&data.fldname)
+            .expect("index was just created");
+        drop(mdm);
+
+        let mut idx = ii.open(tx)?;
+        let mut s = p.open(tx)?;
+        while s.next(tx)? {
+            let val = s.get_val(data.fldname.clone(), tx)?;
+            idx.insert(val, s.get_rid(), tx)?;
+        }
+        s.close(tx);
+        idx.close(tx);
+        Ok(0)
+    }
+
+    /// reclaim the versions of the table records and of its hash index records
+    /// that no transaction can see any more
+    /// @return: the number of reclaimed versions
+    pub fn execute_vacuum(&mut self, tblname: String, tx: &mut Transaction) -> DbResult<i32> {
+        let (p, indexes) = {
+            let mut mdm = self.mdm.lock().unwrap();
+            let p = TablePlan::new(tblname.clone(), &mut mdm, tx)?;
+            (p, mdm.get_index_info(tblname, tx)?)
+        };
+        let mut ts = p.table_scan(tx)?;
+        let mut count = ts.vacuum(tx)?;
+        ts.close(tx);
+        for ii in indexes.values() {
+            count += ii.vacuum(tx)?;
+        }
+        Ok(count)
+    }
+
+    /// the view definition is planned once so that a view on a missing table or field is rejected
+    /// the name must not be taken by a table or another view
+    pub fn execute_create_view(
+        &mut self,
+        data: CreateViewData,
+        tx: &mut Transaction,
+    ) -> DbResult<i32> {
+        {
+            let mut mdm = self.mdm.lock().unwrap();
+            if mdm.has_table(data.viewname.clone(), tx)?
+                || mdm.get_view_def(data.viewname.clone(), tx)?.is_some()
+            {
+                return Err(DbError::Catalog(format!(
+                    "table or view {} already exists",
+                    data.viewname
+                )));
+            }
+        }
+        let viewdef = data.view_def();
+        if viewdef.len() > MAX_VIEWDEF as usize {
+            return Err(DbError::Catalog(format!(
+                "definition of view {} is too long",
+                data.viewname
+            )));
+        }
+        if let Err(e) = QueryPlanner::new(self.mdm.clone()).create_plan(data.qry, tx) {
+            if e.is_abort() {
+                return Err(e);
+            }
+            return Err(DbError::Catalog(format!(
+                "view {} is invalid: {}",
+                data.viewname, e
+            )));
+        }
+        let mut mdm = self.mdm.lock().unwrap();
+        mdm.create_view(data.viewname, tx, viewdef)?;
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DbError;
+    use crate::planning::plan::Plan;
+    use crate::planning::planner::Planner;
+    use crate::scans::common::Scan;
+    use crate::transaction_manager::transaction::Transaction;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    /// the (a, b) pairs of T in order of a
+    fn rows(planner: &mut Planner, tx: &mut Transaction) -> Result<Vec<(i32, String)>> {
+        let p = planner.create_query_plan("SELECT a, b FROM T".to_string(), tx)?;
+        let mut s = p.open(tx)?;
+        let mut rows = Vec::new();
+        while s.next(tx)? {
+            rows.push((
+                s.get_int("a".to_string(), tx)?,
+                s.get_string("b".to_string(), tx)?,
+            ));
+        }
+        s.close(tx);
+        rows.sort();
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_update_planner() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("updateplannertest")
+            .open()?;
+        let mut tx = db.new_tx();
+        let mut planner = db.new_planner()?;
+
+        planner.execute_update("CREATE TABLE T (a INT, b VARCHAR(5))".to_string(), &mut tx)?;
+        for i in 0..5 {
+            let cmd = format!("INSERT INTO T (a, b) VALUES ({}, 'r{}')", i, i);
+            assert_eq!(planner.execute_update(cmd, &mut tx)?, 1);
+        }
+
+        // each statement reports the number of records it changed
+        for (cmd, count) in [
+            ("DELETE FROM T WHERE a = 1", 1),
+            ("DELETE FROM T WHERE a = 1", 0),
+            ("UPDATE T SET b = 'x' WHERE a >= 3", 2),
+            ("UPDATE T SET a = a + 10 WHERE b = 'x'", 2),
+            ("UPDATE T SET b = 'y' WHERE a = 100", 0),
+        ] {
+            assert_eq!(
+                planner.execute_update(cmd.to_string(), &mut tx)?,
+                count,
+                "{}",
+                cmd
+            );
+        }
+        assert_eq!(
+            rows(&mut planner, &mut tx)?,
+            vec![
+                (0, "r0".to_string()),
+                (2, "r2".to_string()),
+                (13, "x".to_string()),
+                (14, "x".to_string()),
+            ]
+        );
+
+        // a statement without a WHERE clause affects every record
+        assert_eq!(
+            planner.execute_update("UPDATE T SET b = 'z'".to_string(), &mut tx)?,
+            4
+        );
+        assert_eq!(
+            planner.execute_update("DELETE FROM T".to_string(), &mut tx)?,
+            4
+        );
+        assert!(rows(&mut planner, &mut tx)?.is_empty());
+
+        for (cmd, is_type_error) in [
+            ("DELETE FROM U WHERE a = 1", false),
+            ("UPDATE T SET c = 1", false),
+            ("DELETE FROM T WHERE c = 1", false),
+            ("UPDATE T SET a = 'x'", true),
+            ("UPDATE T SET b = 1 WHERE a = 0", true),
+        ] {
+            match planner.execute_update(cmd.to_string(), &mut tx) {
+                Err(DbError::Type(_)) => assert!(is_type_error, "{}", cmd),
+                Err(DbError::Catalog(_)) => assert!(!is_type_error, "{}", cmd),
+                r => panic!("{}: expected an error, but got {:?}", cmd, r),
+            }
+        }
+
+        tx.commit()?;
+        teardown();
+        Ok(())
+    }
+}
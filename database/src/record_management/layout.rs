@@ -4,6 +4,7 @@ use crate::common::integer;
 use crate::file_manager::page::Page;
 use crate::record_management::schema::Schema;
 
+use super::record_page::HEADER_SIZE;
 use super::schema::Type;
 
 /// Create a instance for each database table
@@ -26,16 +27,16 @@ impl Layout {
 
     /// create a new layout from schema
     /// For example -
-    /// Record | empty/inuse flag | field 1 (int) | field 2 (int) | field 3 (int) | ...
-    /// Bytes  | 1 byte           | 4 bytes       | 4 bytes       | 4 bytes       | ...
-    /// Offset | 0                | 4             | 8             | 12            | ...
+    /// Record | empty/inuse flag | version header | field 1 (int) | field 2 (int) | ...
+    /// Bytes  | 4 bytes          | 12 bytes       | 4 bytes       | 4 bytes       | ...
+    /// Offset | 0                | 4              | 16            | 20            | ...
     pub fn new_from_schema(schema: Schema) -> Self {
         let mut offsets = HashMap::new();
-        let mut pos = integer::BYTES; // space for the empty / inuse flag
-        for field_name in schema.get_fields().iter() {
+        let mut pos = HEADER_SIZE; // space for the empty / inuse flag and the version header
+        for (field_name, type_, length) in schema.field_types() {
             // set offset for each field
-            offsets.insert(field_name.to_string(), pos);
-            pos += Self::length_in_bytes(&schema, field_name);
+            offsets.insert(field_name, pos);
+            pos += Self::length_in_bytes(type_, length);
         }
         Self {
             schema,
@@ -59,11 +60,10 @@ impl Layout {
         self.slot_size
     }
 
-    fn length_in_bytes(schema: &Schema, field_name: &String) -> i32 {
-        let field_type: Type = schema.get_type_(field_name).into();
-        match field_type {
+    fn length_in_bytes(type_: Type, length: i32) -> i32 {
+        match type_ {
             Type::INTEGER => integer::BYTES,
-            Type::VARCHAR => Page::max_length(schema.get_length(field_name)),
+            Type::VARCHAR => Page::max_length(length),
         }
     }
 }
@@ -99,14 +99,14 @@ mod tests {
         let layout = Layout::new_from_schema(sch.clone());
 
         // check offset and slot size
-        assert_eq!(layout.offset(&"A".to_string()), integer::BYTES);
+        assert_eq!(layout.offset(&"A".to_string()), HEADER_SIZE);
         assert_eq!(
             layout.offset(&"B".to_string()),
-            integer::BYTES + integer::BYTES
+            HEADER_SIZE + integer::BYTES
         );
         assert_eq!(
             layout.slot_size(),
-            integer::BYTES + integer::BYTES + Page::max_length(9)
+            HEADER_SIZE + integer::BYTES + Page::max_length(9)
         );
 
         teardown(db_directory.clone());
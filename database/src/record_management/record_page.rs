@@ -1,3 +1,5 @@
+use crate::common::integer;
+use crate::error::DbResult;
 use crate::file_manager::block_id::BlockId;
 use crate::record_management::layout::Layout;
 use crate::record_management::schema::Type;
@@ -6,34 +8,62 @@ use crate::transaction_manager::transaction::Transaction;
 const EMPTY: i32 = 0;
 const USED: i32 = 1;
 
+const CREATOR_OFFSET: i32 = integer::BYTES;
+const DELETER_OFFSET: i32 = 2 * integer::BYTES;
+const PREV_VERSION_OFFSET: i32 = 3 * integer::BYTES;
+/// the space taken by the empty/inuse flag and the version header of each slot
+pub const HEADER_SIZE: i32 = 4 * integer::BYTES;
+/// the deleter of a version nobody deleted
+pub const NO_TX: i32 = -1;
+/// the previous version of a record having no older version
+pub const NO_VERSION: i32 = -1;
+
 /// Homogeneous: all records in same table have same file
 /// unspanned: no split record
 /// fixed-length: all records have same length
-/// | empty/inuse | record 1 | empty/inuse | record 2 | empty/inuse | record 3 | ...
+/// | empty/inuse | creator | deleter | prev version | record 1 | empty/inuse | creator | ...
+/// the version header holds the txnum that created the version, the txnum that deleted
+/// or replaced it (NO_TX if none) and where its previous version is kept (NO_VERSION if none)
 pub struct RecordPage {
     blk: BlockId,
     layout: Layout,
 }
 
 impl RecordPage {
-    pub fn new(tx: &mut Transaction, blk: BlockId, layout: Layout) -> Self {
-        tx.pin(blk.clone());
-        Self { blk, layout }
+    pub fn new(tx: &mut Transaction, blk: BlockId, layout: Layout) -> DbResult<Self> {
+        tx.pin(blk.clone())?;
+        Ok(Self { blk, layout })
     }
 
-    pub fn get_int(&mut self, tx: &mut Transaction, slot: i32, field_name: &String) -> i32 {
+    pub fn get_int(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        field_name: &String,
+    ) -> DbResult<i32> {
         let fldpos = self.offset(slot) + self.layout.offset(field_name);
-        return tx.get_int(self.blk.clone(), fldpos);
+        return tx.get_record_int(self.blk.clone(), fldpos);
     }
 
-    pub fn get_string(&mut self, tx: &mut Transaction, slot: i32, field_name: &String) -> String {
+    pub fn get_string(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        field_name: &String,
+    ) -> DbResult<String> {
         let fldpos = self.offset(slot) + self.layout.offset(field_name);
-        return tx.get_string(self.blk.clone(), fldpos).clone();
+        return tx.get_record_string(self.blk.clone(), fldpos);
     }
 
-    pub fn set_int(&mut self, tx: &mut Transaction, slot: i32, field_name: &String, val: i32) {
+    pub fn set_int(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        field_name: &String,
+        val: i32,
+    ) -> DbResult<()> {
         let fldpos = self.offset(slot) + self.layout.offset(field_name);
-        tx.set_int(self.blk.clone(), fldpos, val, true);
+        return tx.set_int(self.blk.clone(), fldpos, val, true);
     }
 
     pub fn set_string(
@@ -42,53 +72,136 @@ impl RecordPage {
         slot: i32,
         field_name: &String,
         val: String,
-    ) {
+    ) -> DbResult<()> {
         let fldpos = self.offset(slot) + self.layout.offset(field_name);
-        tx.set_string(self.blk.clone(), fldpos, val, true);
+        return tx.set_string(self.blk.clone(), fldpos, val, true);
+    }
+
+    /// free the slot, whatever versions are in it
+    pub fn delete(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<()> {
+        return self.set_flag(tx, slot, EMPTY);
+    }
+
+    pub fn creator(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
+        return tx.get_record_int(self.blk.clone(), self.offset(slot) + CREATOR_OFFSET);
+    }
+
+    pub fn deleter(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
+        return tx.get_record_int(self.blk.clone(), self.offset(slot) + DELETER_OFFSET);
+    }
+
+    pub fn prev_version(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
+        return tx.get_record_int(self.blk.clone(), self.offset(slot) + PREV_VERSION_OFFSET);
     }
 
-    pub fn delete(&mut self, tx: &mut Transaction, slot: i32) {
-        self.set_flag(tx, slot, EMPTY);
+    pub fn set_creator(&mut self, tx: &mut Transaction, slot: i32, txnum: i32) -> DbResult<()> {
+        return tx.set_int(
+            self.blk.clone(),
+            self.offset(slot) + CREATOR_OFFSET,
+            txnum,
+            true,
+        );
+    }
+
+    pub fn set_deleter(&mut self, tx: &mut Transaction, slot: i32, txnum: i32) -> DbResult<()> {
+        return tx.set_int(
+            self.blk.clone(),
+            self.offset(slot) + DELETER_OFFSET,
+            txnum,
+            true,
+        );
+    }
+
+    pub fn set_prev_version(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        version: i32,
+    ) -> DbResult<()> {
+        let pos = self.offset(slot) + PREV_VERSION_OFFSET;
+        return tx.set_int(self.blk.clone(), pos, version, true);
+    }
+
+    /// copy the version header and the fields of the slot to the slot of another page
+    pub fn copy_to(
+        &mut self,
+        tx: &mut Transaction,
+        slot: i32,
+        dest: &mut RecordPage,
+        destslot: i32,
+    ) -> DbResult<()> {
+        let creator = self.creator(tx, slot)?;
+        dest.set_creator(tx, destslot, creator)?;
+        let deleter = self.deleter(tx, slot)?;
+        dest.set_deleter(tx, destslot, deleter)?;
+        let prev = self.prev_version(tx, slot)?;
+        dest.set_prev_version(tx, destslot, prev)?;
+        let sch = self.layout.schema();
+        for field_name in sch.get_fields().iter() {
+            match sch.get_type_(field_name)? {
+                Type::INTEGER => {
+                    let val = self.get_int(tx, slot, field_name)?;
+                    dest.set_int(tx, destslot, field_name, val)?;
+                }
+                Type::VARCHAR => {
+                    let val = self.get_string(tx, slot, field_name)?;
+                    dest.set_string(tx, destslot, field_name, val)?;
+                }
+            }
+        }
+        Ok(())
     }
 
     /// return the first record setting empty/inuse flag to USED
     /// @return: the slot number of the record
     /// if no record is found, return -1
     /// @return: the slot number of the record
-    pub fn next_after(&mut self, tx: &mut Transaction, slot: i32) -> i32 {
+    pub fn next_after(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
         self.search_after(tx, slot, USED)
     }
 
     /// search for the first empty record and set empty/inuse flag to USED and return the slot number
+    /// the new record is the only version, created by the transaction
     /// @param slot: the slot number of the record
     /// if no empty record is found, return -1
     /// @return: the slot number of the record
-    pub fn insert_after(&mut self, tx: &mut Transaction, slot: i32) -> i32 {
-        let new_slot = self.search_after(tx, slot, EMPTY);
+    pub fn insert_after(&mut self, tx: &mut Transaction, slot: i32) -> DbResult<i32> {
+        // a snapshot transaction reads the flags without an slock,
+        // so the block is locked before an empty slot is chosen
+        tx.xlock(self.blk.clone())?;
+        let new_slot = self.search_after(tx, slot, EMPTY)?;
         if new_slot >= 0 {
-            self.set_flag(tx, new_slot, USED);
+            // the header is written first, since the readers without a lock look at it once the slot is used
+            let txnum = tx.tx_num();
+            self.set_creator(tx, new_slot, txnum)?;
+            self.set_deleter(tx, new_slot, NO_TX)?;
+            self.set_prev_version(tx, new_slot, NO_VERSION)?;
+            self.set_flag(tx, new_slot, USED)?;
         }
-        return new_slot;
+        return Ok(new_slot);
     }
 
     /// delte the all records at blk
     /// set empty/inuse flag to empty
     /// set INTEGER to 0
     /// set VARCHAR to ""
-    pub fn format(&mut self, tx: &mut Transaction) {
+    pub fn format(&mut self, tx: &mut Transaction) -> DbResult<()> {
         let mut slot = 0;
         while self.is_valid_slot(tx, slot) {
-            tx.set_int(self.blk.clone(), Self::offset(&self, slot), EMPTY, false);
+            tx.set_int(self.blk.clone(), Self::offset(&self, slot), EMPTY, false)?;
             let sch = self.layout.schema();
             for field_name in sch.get_fields().iter() {
                 let fldpot = self.offset(slot) + self.layout.offset(field_name);
-                match sch.get_type_(field_name).into() {
-                    Type::INTEGER => tx.set_int(self.blk.clone(), fldpot, 0, false),
-                    Type::VARCHAR => tx.set_string(self.blk.clone(), fldpot, "".to_string(), false),
+                match sch.get_type_(field_name)? {
+                    Type::INTEGER => tx.set_int(self.blk.clone(), fldpot, 0, false)?,
+                    Type::VARCHAR => {
+                        tx.set_string(self.blk.clone(), fldpot, "".to_string(), false)?
+                    }
                 }
             }
             slot += 1;
         }
+        Ok(())
     }
 
     pub fn block(&self) -> BlockId {
@@ -98,8 +211,8 @@ impl RecordPage {
     /// set empty/inuse flag to indicate if a record is empty or inuse
     /// @param slot: the slot number of the record
     /// @param flag: EMPTY or USED
-    fn set_flag(&mut self, tx: &mut Transaction, slot: i32, flag: i32) {
-        tx.set_int(self.blk.clone(), Self::offset(self, slot), flag, true);
+    fn set_flag(&mut self, tx: &mut Transaction, slot: i32, flag: i32) -> DbResult<()> {
+        return tx.set_int(self.blk.clone(), Self::offset(self, slot), flag, true);
     }
 
     /// return true if the slot is valid
@@ -118,15 +231,15 @@ impl RecordPage {
     /// search for the first record (start from slot + 1) with the given flag
     /// @param slot: the slot number of the record
     /// @param flag: EMPTY or USED
-    fn search_after(&mut self, tx: &mut Transaction, mut slot: i32, flag: i32) -> i32 {
+    fn search_after(&mut self, tx: &mut Transaction, mut slot: i32, flag: i32) -> DbResult<i32> {
         slot += 1;
         while self.is_valid_slot(tx, slot) {
-            if tx.get_int(self.blk.clone(), Self::offset(self, slot)) == flag {
-                return slot;
+            if tx.get_record_int(self.blk.clone(), Self::offset(self, slot))? == flag {
+                return Ok(slot);
             }
             slot += 1;
         }
-        return -1;
+        return Ok(-1);
     }
 
     /// return slot size to calculate the starting location of a record slot
@@ -146,6 +259,8 @@ mod tests {
     use crate::file_manager::file_mgr::FileMgr;
     use crate::log_manager::log_mgr::LogMgr;
     use crate::record_management::schema::Schema;
+    use crate::transaction_manager::concurrency_manager::lock_table::LockTable;
+    use crate::transaction_manager::version_manager::{TxMode, VersionMgr};
 
     fn setup(db_directory: String) {
         // delete db_deirectory if exists
@@ -166,57 +281,64 @@ mod tests {
             LogMgr::new(fm.clone(), log_file.clone()).unwrap(),
         ));
         let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
-        let mut tx: Transaction = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+        let mut tx: Transaction = Transaction::new(
+            fm.clone(),
+            log_mgr.clone(),
+            bm.clone(),
+            Arc::new(LockTable::new()),
+            Arc::new(VersionMgr::new()),
+            TxMode::Locking,
+        );
         let mut sch = Schema::new();
         sch.add_int_field("A".to_string());
         sch.add_string_field("B".to_string(), 9);
         let layout = Layout::new_from_schema(sch.clone());
-        let blk = tx.append(log_file.clone());
-        tx.pin(blk.clone());
-        let mut rp = RecordPage::new(&mut tx, blk.clone(), layout);
-        rp.format(&mut tx);
+        let blk = tx.append(log_file.clone())?;
+        tx.pin(blk.clone())?;
+        let mut rp = RecordPage::new(&mut tx, blk.clone(), layout)?;
+        rp.format(&mut tx)?;
 
-        let mut slot = rp.insert_after(&mut tx, -1);
+        let mut slot = rp.insert_after(&mut tx, -1)?;
         // check insert_after
         assert_eq!(slot, 0);
         // set field
-        rp.set_int(&mut tx, slot, &"A".to_string(), 100);
-        rp.set_string(&mut tx, slot, &"B".to_string(), format!("rec{}", 100));
+        rp.set_int(&mut tx, slot, &"A".to_string(), 100)?;
+        rp.set_string(&mut tx, slot, &"B".to_string(), format!("rec{}", 100))?;
 
-        slot = rp.insert_after(&mut tx, slot);
+        slot = rp.insert_after(&mut tx, slot)?;
         // check insert_after
         assert_eq!(slot, 1);
         // set field
-        rp.set_int(&mut tx, slot, &"A".to_string(), 120);
-        rp.set_string(&mut tx, slot, &"B".to_string(), format!("rec{}", 120));
+        rp.set_int(&mut tx, slot, &"A".to_string(), 120)?;
+        rp.set_string(&mut tx, slot, &"B".to_string(), format!("rec{}", 120))?;
 
         // Check disk content
-        let mut slot = rp.next_after(&mut tx, -1);
-        assert_eq!(rp.get_int(&mut tx, slot, &"A".to_string()), 100);
+        let mut slot = rp.next_after(&mut tx, -1)?;
+        assert_eq!(rp.get_int(&mut tx, slot, &"A".to_string())?, 100);
         assert_eq!(
-            rp.get_string(&mut tx, slot, &"B".to_string()),
+            rp.get_string(&mut tx, slot, &"B".to_string())?,
             format!("rec{}", 100)
         );
 
-        slot = rp.next_after(&mut tx, slot);
-        assert_eq!(rp.get_int(&mut tx, slot, &"A".to_string()), 120);
+        slot = rp.next_after(&mut tx, slot)?;
+        assert_eq!(rp.get_int(&mut tx, slot, &"A".to_string())?, 120);
         assert_eq!(
-            rp.get_string(&mut tx, slot, &"B".to_string()),
+            rp.get_string(&mut tx, slot, &"B".to_string())?,
             format!("rec{}", 120)
         );
 
         // Check search_after
-        assert_eq!(rp.search_after(&mut tx, slot, USED), -1);
+        assert_eq!(rp.search_after(&mut tx, slot, USED)?, -1);
 
         // Check delete
-        slot = rp.next_after(&mut tx, -1);
-        rp.delete(&mut tx, slot);
-        slot = rp.next_after(&mut tx, slot);
-        rp.delete(&mut tx, slot);
-        assert_eq!(rp.search_after(&mut tx, -1, USED), -1);
+        slot = rp.next_after(&mut tx, -1)?;
+        rp.delete(&mut tx, slot)?;
+        slot = rp.next_after(&mut tx, slot)?;
+        rp.delete(&mut tx, slot)?;
+        assert_eq!(rp.search_after(&mut tx, -1, USED)?, -1);
 
         tx.unpin(blk.clone());
-        tx.commit();
+        tx.commit()?;
 
         teardown();
         Ok(())
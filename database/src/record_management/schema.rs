@@ -1,37 +1,53 @@
 use std::collections::HashMap;
 
+use crate::error::{DbError, DbResult};
+
 #[derive(Debug, Hash, Clone)]
 struct FieldInfo {
-    type_: i32,
+    type_: Type,
     length: i32,
 }
 
 impl FieldInfo {
-    fn new(type_: i32, length: i32) -> Self {
+    fn new(type_: Type, length: i32) -> Self {
         Self { type_, length }
     }
 }
 
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
 pub enum Type {
     INTEGER,
     VARCHAR,
 }
 
-impl Into<i32> for Type {
-    fn into(self) -> i32 {
+impl Type {
+    pub fn to_str(&self) -> &str {
         match self {
+            Type::INTEGER => "int",
+            Type::VARCHAR => "varchar",
+        }
+    }
+}
+
+/// the code of the type in the catalog
+impl From<Type> for i32 {
+    fn from(type_: Type) -> i32 {
+        match type_ {
             Type::INTEGER => 4,
             Type::VARCHAR => 12,
         }
     }
 }
 
-impl Into<Type> for i32 {
-    fn into(self) -> Type {
-        match self {
-            4 => Type::INTEGER,
-            12 => Type::VARCHAR,
-            _ => panic!("invalid type"),
+/// a code other than those of the types can only come from a damaged catalog
+impl TryFrom<i32> for Type {
+    type Error = DbError;
+
+    fn try_from(code: i32) -> DbResult<Type> {
+        match code {
+            4 => Ok(Type::INTEGER),
+            12 => Ok(Type::VARCHAR),
+            _ => Err(DbError::Corruption(format!("invalid field type {}", code))),
         }
     }
 }
@@ -50,28 +66,30 @@ impl Schema {
         }
     }
 
-    pub fn add_field(&mut self, field_name: String, type_: i32, length: i32) {
+    pub fn add_field(&mut self, field_name: String, type_: Type, length: i32) {
         self.fields.push(field_name.clone());
         self.info.insert(field_name, FieldInfo::new(type_, length));
     }
 
     pub fn add_int_field(&mut self, field_name: String) {
-        self.add_field(field_name, Type::INTEGER.into(), 0);
+        self.add_field(field_name, Type::INTEGER, 0);
     }
 
     pub fn add_string_field(&mut self, field_name: String, length: i32) {
-        self.add_field(field_name, Type::VARCHAR.into(), length);
+        self.add_field(field_name, Type::VARCHAR, length);
     }
 
-    pub fn add(&mut self, field_name: String, sch: Self) {
-        let type_ = sch.get_type_(&field_name);
-        let length = sch.get_length(&field_name);
+    /// add the field of the other schema, with its type and length
+    pub fn add(&mut self, field_name: String, sch: &Self) -> DbResult<()> {
+        let type_ = sch.get_type_(&field_name)?;
+        let length = sch.get_length(&field_name)?;
         self.add_field(field_name, type_, length);
+        Ok(())
     }
 
     pub fn add_all(&mut self, sch: Self) {
-        for field_name in sch.fields.iter() {
-            self.add(field_name.clone(), sch.clone());
+        for (field_name, type_, length) in sch.field_types() {
+            self.add_field(field_name, type_, length);
         }
     }
 
@@ -83,17 +101,69 @@ impl Schema {
         self.fields.contains(field_name)
     }
 
-    pub fn get_type_(&self, field_name: &String) -> i32 {
+    /// the name, type and length of each field, in the order the fields were added
+    pub fn field_types(&self) -> Vec<(String, Type, i32)> {
+        self.fields
+            .iter()
+            .filter_map(|field_name| {
+                let info = self.info.get(field_name)?;
+                Some((field_name.clone(), info.type_, info.length))
+            })
+            .collect()
+    }
+
+    pub fn get_type_(&self, field_name: &String) -> DbResult<Type> {
         match self.info.get(field_name) {
-            Some(info) => info.type_,
-            None => panic!("field {} not found", field_name),
+            Some(info) => Ok(info.type_),
+            None => Err(DbError::Catalog(format!("field {} not found", field_name))),
         }
     }
 
-    pub fn get_length(&self, field_name: &String) -> i32 {
+    pub fn get_length(&self, field_name: &String) -> DbResult<i32> {
         match self.info.get(field_name) {
-            Some(info) => info.length,
-            None => panic!("field {} not found", field_name),
+            Some(info) => Ok(info.length),
+            None => Err(DbError::Catalog(format!("field {} not found", field_name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        let mut sch = Schema::new();
+        sch.add_int_field("A".to_string());
+        sch.add_string_field("B".to_string(), 9);
+        assert_eq!(sch.get_type_(&"B".to_string()).unwrap(), Type::VARCHAR);
+        assert_eq!(sch.get_length(&"B".to_string()).unwrap(), 9);
+        assert!(matches!(
+            sch.get_type_(&"C".to_string()),
+            Err(DbError::Catalog(_))
+        ));
+        assert!(matches!(
+            sch.get_length(&"C".to_string()),
+            Err(DbError::Catalog(_))
+        ));
+
+        let mut sch2 = Schema::new();
+        sch2.add("B".to_string(), &sch).unwrap();
+        assert!(sch2.add("C".to_string(), &sch).is_err());
+        sch2.add_all(sch);
+        assert_eq!(
+            sch2.field_types(),
+            vec![
+                ("B".to_string(), Type::VARCHAR, 9),
+                ("A".to_string(), Type::INTEGER, 0),
+                ("B".to_string(), Type::VARCHAR, 9),
+            ]
+        );
+
+        // the catalog codes of the types
+        for t in [Type::INTEGER, Type::VARCHAR] {
+            assert_eq!(Type::try_from(i32::from(t)).unwrap(), t);
         }
+        assert!(matches!(Type::try_from(7), Err(DbError::Corruption(_))));
     }
 }
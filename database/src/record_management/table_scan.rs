@@ -1,75 +1,103 @@
 use crate::common::Constant;
+use crate::error::{DbError, DbResult};
 use crate::file_manager::block_id::BlockId;
 use crate::record_management::layout::Layout;
-use crate::record_management::record_page::RecordPage;
+use crate::record_management::record_page::{RecordPage, NO_TX, NO_VERSION};
 use crate::record_management::rid::RID;
 use crate::record_management::schema::Type;
+use crate::transaction_manager::concurrency_manager::LockAbortError;
 use crate::transaction_manager::transaction::Transaction;
 
+/// a scan over the records of a table, as seen by the transaction
+/// the slots of the table file hold the newest version of each record,
+/// and the older versions still needed by snapshots are kept in the version file,
+/// each one pointing at the version before it
 pub struct TableScan {
     pub layout: Layout,
     rp: RecordPage,
     filename: String,
+    verfilename: String,
     current_slot: i32,
+    /// the older version of the current record seen by the transaction, if it is not the newest one
+    version: Option<(RecordPage, i32)>,
+    /// the creator of the newest version when the current record was read from it
+    creator: i32,
 }
 
 impl TableScan {
-    pub fn new(tx: &mut Transaction, tblname: String, layout: Layout) -> Self {
+    pub fn new(tx: &mut Transaction, tblname: String, layout: Layout) -> DbResult<Self> {
         let filename = tblname.clone() + ".tbl";
-        let blk = match tx.size(filename.clone()) {
-            0 => tx.append(filename.clone()),
+        let blk = match tx.size(filename.clone())? {
+            0 => tx.append(filename.clone())?,
             _ => BlockId::new(filename.clone(), 0),
         };
-        Self {
+        Ok(Self {
             layout: layout.clone(),
-            rp: RecordPage::new(tx, blk, layout.clone()),
+            rp: RecordPage::new(tx, blk, layout.clone())?,
             filename,
+            verfilename: tblname + ".ver",
             current_slot: -1,
-        }
+            version: None,
+            creator: NO_TX,
+        })
     }
 
     /// close the scan
-    pub fn close(&self, tx: &mut Transaction) {
+    pub fn close(&mut self, tx: &mut Transaction) {
+        self.close_version(tx);
         tx.unpin(self.rp.block().clone());
     }
 
     /// move to the first record
-    pub fn before_first(&mut self, tx: &mut Transaction) {
-        self.move_to_block(tx, 0);
+    pub fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.move_to_block(tx, 0)
     }
 
-    /// move to the next record
+    /// move to the next record the transaction sees
     /// current_slot will be -1 if there is no next record
     /// @return: false if there is no next record
     /// @return: true if there is next record
-    pub fn next(&mut self, tx: &mut Transaction) -> bool {
-        self.current_slot = self.rp.next_after(tx, self.current_slot);
-        while self.current_slot < 0 {
-            if self.at_last_block(tx) {
-                return false;
-            };
-            self.move_to_block(tx, self.rp.block().number() + 1);
-            self.current_slot = self.rp.next_after(tx, self.current_slot);
+    pub fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        while self.next_used(tx)? {
+            if self.resolve_version(tx)? {
+                return Ok(true);
+            }
         }
-        return true;
+        return Ok(false);
     }
 
     /// get int from current slot and field name
     /// @return: the int value
-    pub fn get_int(&mut self, tx: &mut Transaction, field_name: &String) -> i32 {
-        self.rp.get_int(tx, self.current_slot, field_name)
+    pub fn get_int(&mut self, tx: &mut Transaction, field_name: &String) -> DbResult<i32> {
+        loop {
+            let val = match self.version.as_mut() {
+                Some((vp, vslot)) => return vp.get_int(tx, *vslot, field_name),
+                None => self.rp.get_int(tx, self.current_slot, field_name)?,
+            };
+            if self.still_current(tx)? {
+                return Ok(val);
+            }
+        }
     }
 
     /// get string from current slot and field name
     /// @return: the string value
-    pub fn get_string(&mut self, tx: &mut Transaction, field_name: &String) -> String {
-        self.rp.get_string(tx, self.current_slot, field_name)
+    pub fn get_string(&mut self, tx: &mut Transaction, field_name: &String) -> DbResult<String> {
+        loop {
+            let val = match self.version.as_mut() {
+                Some((vp, vslot)) => return vp.get_string(tx, *vslot, field_name),
+                None => self.rp.get_string(tx, self.current_slot, field_name)?,
+            };
+            if self.still_current(tx)? {
+                return Ok(val);
+            }
+        }
     }
 
-    pub fn get_value(&mut self, tx: &mut Transaction, field_name: &String) -> Constant {
-        match self.layout.schema().get_type_(field_name).into() {
-            Type::INTEGER => Constant::Int(self.get_int(tx, field_name)),
-            Type::VARCHAR => Constant::String(self.get_string(tx, field_name)),
+    pub fn get_value(&mut self, tx: &mut Transaction, field_name: &String) -> DbResult<Constant> {
+        match self.layout.schema().get_type_(field_name)? {
+            Type::INTEGER => Ok(Constant::Int(self.get_int(tx, field_name)?)),
+            Type::VARCHAR => Ok(Constant::String(self.get_string(tx, field_name)?)),
         }
     }
 
@@ -83,48 +111,95 @@ impl TableScan {
     /// set int to current slot and field name
     /// @param val: the int value
     /// @return: the int value
-    pub fn set_int(&mut self, tx: &mut Transaction, field_name: &String, val: i32) {
-        self.rp.set_int(tx, self.current_slot, field_name, val);
+    pub fn set_int(&mut self, tx: &mut Transaction, field_name: &String, val: i32) -> DbResult<()> {
+        self.new_version(tx)?;
+        self.rp.set_int(tx, self.current_slot, field_name, val)
     }
 
     /// set string to current slot and field name
     /// @param val: the string value
     /// @return: the string value
-    pub fn set_string(&mut self, tx: &mut Transaction, field_name: &String, val: String) {
-        self.rp.set_string(tx, self.current_slot, field_name, val);
+    pub fn set_string(
+        &mut self,
+        tx: &mut Transaction,
+        field_name: &String,
+        val: String,
+    ) -> DbResult<()> {
+        self.new_version(tx)?;
+        self.rp.set_string(tx, self.current_slot, field_name, val)
     }
 
-    pub fn set_value(&mut self, tx: &mut Transaction, field_name: &String, val: Constant) {
+    pub fn set_value(
+        &mut self,
+        tx: &mut Transaction,
+        field_name: &String,
+        val: Constant,
+    ) -> DbResult<()> {
         match val {
             Constant::Int(val) => self.set_int(tx, field_name, val),
             Constant::String(val) => self.set_string(tx, field_name, val),
+            Constant::Null => Err(DbError::Type(format!(
+                "null cannot be stored in field {}",
+                field_name
+            ))),
         }
     }
 
     /// allocate new record space
-    pub fn insert(&mut self, tx: &mut Transaction) {
-        self.current_slot = self.rp.insert_after(tx, self.current_slot);
+    /// a full block first reclaims the records no transaction can see any more
+    pub fn insert(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.close_version(tx);
+        self.current_slot = self.rp.insert_after(tx, self.current_slot)?;
         while self.current_slot < 0 {
-            if self.at_last_block(tx) {
-                self.move_to_new_block(tx);
+            if self.reclaim_block(tx)? {
+                self.current_slot = self.rp.insert_after(tx, -1)?;
+            } else if self.at_last_block(tx)? {
+                self.move_to_new_block(tx)?;
             } else {
-                self.move_to_block(tx, self.rp.block().number() + 1);
+                self.move_to_block(tx, self.rp.block().number() + 1)?;
             }
-            self.current_slot = self.rp.insert_after(tx, self.current_slot);
+            self.current_slot = self.rp.insert_after(tx, self.current_slot)?;
         }
+        self.creator = tx.tx_num();
+        Ok(())
     }
 
     /// delete current record
-    pub fn delete(&mut self, tx: &mut Transaction) {
-        self.rp.delete(tx, self.current_slot);
+    /// without snapshots to see it, the record is freed with its versions right away;
+    /// otherwise it stays in its slot until it is vacuumed or its block needs the space
+    pub fn delete(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.lock_for_write(tx)?;
+        if tx.skips_versions() {
+            let prev = self.rp.prev_version(tx, self.current_slot)?;
+            self.free_versions(tx, prev)?;
+            return self.rp.delete(tx, self.current_slot);
+        }
+        let txnum = tx.tx_num();
+        self.rp.set_deleter(tx, self.current_slot, txnum)
+    }
+
+    /// xlock the block of the current record before writing it
+    /// a snapshot transaction cannot write a record whose newest version it does not see,
+    /// since another transaction committed that version first
+    /// on a lock abort the transaction must be rolled back
+    pub fn lock_for_write(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        tx.xlock(self.rp.block())?;
+        let creator = self.rp.creator(tx, self.current_slot)?;
+        let deleter = self.rp.deleter(tx, self.current_slot)?;
+        if !tx.sees(creator) || (deleter != NO_TX && deleter != tx.tx_num()) {
+            return Err(LockAbortError::WriteConflict(self.rp.block()).into());
+        }
+        return Ok(());
     }
 
     /// move to the RID recoerd
-    pub fn move_to_rid(&mut self, tx: &mut Transaction, rid: RID) {
+    /// @return: whether the transaction sees the record
+    pub fn move_to_rid(&mut self, tx: &mut Transaction, rid: RID) -> DbResult<bool> {
         self.close(tx);
         let blk = BlockId::new(self.filename.clone(), rid.block_number());
-        self.rp = RecordPage::new(tx, blk, self.layout.clone());
+        self.rp = RecordPage::new(tx, blk, self.layout.clone())?;
         self.current_slot = rid.slot_number();
+        return self.resolve_version(tx);
     }
 
     /// get the RID of current record
@@ -132,29 +207,239 @@ impl TableScan {
         RID::new(self.rp.block().number(), self.current_slot)
     }
 
+    /// reclaim the record versions no transaction can see any more
+    /// a record deleted by a transaction every snapshot sees is freed with all its versions,
+    /// and the versions older than one created by such a transaction are cut off
+    /// @return: the number of reclaimed versions
+    pub fn vacuum(&mut self, tx: &mut Transaction) -> DbResult<i32> {
+        let mut count = 0;
+        self.before_first(tx)?;
+        while self.next_used(tx)? {
+            let deleter = self.rp.deleter(tx, self.current_slot)?;
+            if deleter != NO_TX && tx.is_settled(deleter) {
+                let prev = self.rp.prev_version(tx, self.current_slot)?;
+                count += 1 + self.free_versions(tx, prev)?;
+                self.rp.delete(tx, self.current_slot)?;
+                continue;
+            }
+
+            // find the newest version every snapshot sees
+            let creator = self.rp.creator(tx, self.current_slot)?;
+            if tx.is_settled(creator) {
+                let prev = self.rp.prev_version(tx, self.current_slot)?;
+                if prev != NO_VERSION {
+                    self.rp
+                        .set_prev_version(tx, self.current_slot, NO_VERSION)?;
+                    count += self.free_versions(tx, prev)?;
+                }
+                continue;
+            }
+            let mut version = self.rp.prev_version(tx, self.current_slot)?;
+            while version != NO_VERSION {
+                let (mut vp, vslot) = self.open_version(tx, version)?;
+                let creator = vp.creator(tx, vslot)?;
+                let prev = vp.prev_version(tx, vslot)?;
+                if tx.is_settled(creator) && prev != NO_VERSION {
+                    vp.set_prev_version(tx, vslot, NO_VERSION)?;
+                    count += self.free_versions(tx, prev)?;
+                }
+                tx.unpin(vp.block());
+                if tx.is_settled(creator) {
+                    break;
+                }
+                version = prev;
+            }
+        }
+        self.close_version(tx);
+        return Ok(count);
+    }
+
+    /// free the slots of the current block holding records deleted by transactions every snapshot sees,
+    /// with all their versions
+    /// the block is already xlocked by the insert looking for an empty slot
+    /// @return: whether a slot was freed
+    fn reclaim_block(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        let mut reclaimed = false;
+        let mut slot = self.rp.next_after(tx, -1)?;
+        while slot >= 0 {
+            let deleter = self.rp.deleter(tx, slot)?;
+            if deleter != NO_TX && tx.is_settled(deleter) {
+                let prev = self.rp.prev_version(tx, slot)?;
+                self.free_versions(tx, prev)?;
+                self.rp.delete(tx, slot)?;
+                reclaimed = true;
+            }
+            slot = self.rp.next_after(tx, slot)?;
+        }
+        return Ok(reclaimed);
+    }
+
+    /// move to the next used slot, whoever sees the record in it
+    fn next_used(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        self.close_version(tx);
+        self.current_slot = self.rp.next_after(tx, self.current_slot)?;
+        while self.current_slot < 0 {
+            if self.at_last_block(tx)? {
+                return Ok(false);
+            };
+            self.move_to_block(tx, self.rp.block().number() + 1)?;
+            self.current_slot = self.rp.next_after(tx, self.current_slot)?;
+        }
+        return Ok(true);
+    }
+
+    /// find the version of the current record the transaction sees
+    /// it is the newest version created by a transaction it sees, unless that version is deleted
+    /// @return: whether the transaction sees the record
+    fn resolve_version(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        self.close_version(tx);
+        self.creator = self.rp.creator(tx, self.current_slot)?;
+        if tx.sees(self.creator) {
+            let deleter = self.rp.deleter(tx, self.current_slot)?;
+            return Ok(deleter == NO_TX || !tx.sees(deleter));
+        }
+        let mut version = self.rp.prev_version(tx, self.current_slot)?;
+        while version != NO_VERSION {
+            let (mut vp, vslot) = self.open_version(tx, version)?;
+            let creator = vp.creator(tx, vslot)?;
+            if tx.sees(creator) {
+                let deleter = vp.deleter(tx, vslot)?;
+                let visible = deleter == NO_TX || !tx.sees(deleter);
+                self.version = Some((vp, vslot));
+                return Ok(visible);
+            }
+            version = vp.prev_version(tx, vslot)?;
+            tx.unpin(vp.block());
+        }
+        return Ok(false);
+    }
+
+    /// whether the newest version read without a lock is still the current one
+    /// a writer keeps the version for the snapshots that see it before changing the fields,
+    /// so when the newest version is replaced the read is repeated on the version seen
+    fn still_current(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        if self.rp.creator(tx, self.current_slot)? == self.creator {
+            return Ok(true);
+        }
+        self.resolve_version(tx)?;
+        return Ok(false);
+    }
+
+    /// make the newest version of the current record one created by the transaction
+    /// a version created by another transaction is first copied to the version file,
+    /// with the transaction as its deleter, unless no snapshot can see it
+    /// the older versions are freed once every snapshot sees the version being replaced
+    fn new_version(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.lock_for_write(tx)?;
+        self.close_version(tx);
+        let txnum = tx.tx_num();
+        let creator = self.rp.creator(tx, self.current_slot)?;
+        if creator == txnum {
+            return Ok(());
+        }
+        let prev = self.rp.prev_version(tx, self.current_slot)?;
+        let skips_versions = tx.skips_versions();
+        if prev != NO_VERSION && (skips_versions || tx.is_settled(creator)) {
+            self.rp
+                .set_prev_version(tx, self.current_slot, NO_VERSION)?;
+            self.free_versions(tx, prev)?;
+        }
+        if skips_versions {
+            self.rp.set_creator(tx, self.current_slot, txnum)?;
+            self.creator = txnum;
+            return Ok(());
+        }
+        let (mut vp, vslot, version) = self.insert_version(tx)?;
+        self.rp.copy_to(tx, self.current_slot, &mut vp, vslot)?;
+        vp.set_deleter(tx, vslot, txnum)?;
+        tx.unpin(vp.block());
+        // the readers without a lock follow the pointer once they see the new creator
+        self.rp.set_prev_version(tx, self.current_slot, version)?;
+        self.rp.set_creator(tx, self.current_slot, txnum)?;
+        self.creator = txnum;
+        Ok(())
+    }
+
+    /// allocate a slot of the version file
+    /// @return: the pinned page, the slot and the version number of the slot
+    fn insert_version(&mut self, tx: &mut Transaction) -> DbResult<(RecordPage, i32, i32)> {
+        let mut blknum = 0;
+        loop {
+            let blk = if blknum < tx.size(self.verfilename.clone())? {
+                BlockId::new(self.verfilename.clone(), blknum)
+            } else {
+                tx.append(self.verfilename.clone())?
+            };
+            let mut vp = RecordPage::new(tx, blk, self.layout.clone())?;
+            let vslot = vp.insert_after(tx, -1)?;
+            if vslot >= 0 {
+                let version = blknum * self.slots_per_block(tx) + vslot;
+                return Ok((vp, vslot, version));
+            }
+            tx.unpin(vp.block());
+            blknum += 1;
+        }
+    }
+
+    /// pin the page of the version file holding the version
+    fn open_version(&mut self, tx: &mut Transaction, version: i32) -> DbResult<(RecordPage, i32)> {
+        let rpb = self.slots_per_block(tx);
+        let blk = BlockId::new(self.verfilename.clone(), version / rpb);
+        return Ok((
+            RecordPage::new(tx, blk, self.layout.clone())?,
+            version % rpb,
+        ));
+    }
+
+    /// free the version and all the versions before it
+    /// @return: the number of freed versions
+    fn free_versions(&mut self, tx: &mut Transaction, mut version: i32) -> DbResult<i32> {
+        let mut count = 0;
+        while version != NO_VERSION {
+            let (mut vp, vslot) = self.open_version(tx, version)?;
+            version = vp.prev_version(tx, vslot)?;
+            vp.delete(tx, vslot)?;
+            tx.unpin(vp.block());
+            count += 1;
+        }
+        return Ok(count);
+    }
+
+    fn close_version(&mut self, tx: &mut Transaction) {
+        if let Some((vp, _)) = self.version.take() {
+            tx.unpin(vp.block());
+        }
+    }
+
+    fn slots_per_block(&self, tx: &mut Transaction) -> i32 {
+        return tx.block_size() / self.layout.slot_size();
+    }
+
     /// move to the block at blknum and update current_slot to -1
     /// @param blknum: the block number
-    fn move_to_block(&mut self, tx: &mut Transaction, blknum: i32) {
+    fn move_to_block(&mut self, tx: &mut Transaction, blknum: i32) -> DbResult<()> {
         self.close(tx);
         let blk = BlockId::new(self.filename.clone(), blknum);
-        self.rp = RecordPage::new(tx, blk, self.layout.clone());
+        self.rp = RecordPage::new(tx, blk, self.layout.clone())?;
         self.current_slot = -1;
+        Ok(())
     }
 
     /// move to the new block (append new block) and update current_slot to -1
-    fn move_to_new_block(&mut self, tx: &mut Transaction) {
+    fn move_to_new_block(&mut self, tx: &mut Transaction) -> DbResult<()> {
         self.close(tx);
-        let blk = tx.append(self.filename.clone());
-        self.rp = RecordPage::new(tx, blk, self.layout.clone());
+        let blk = tx.append(self.filename.clone())?;
+        self.rp = RecordPage::new(tx, blk, self.layout.clone())?;
         self.current_slot = -1;
+        Ok(())
     }
 
     /// whether the current block is the last block
-    fn at_last_block(&self, tx: &mut Transaction) -> bool {
+    fn at_last_block(&self, tx: &mut Transaction) -> DbResult<bool> {
         // tx.size means the number of blocks
         // rp.block().number() means the current block number
         // so if rp.block().number() = 1 then tx.size = 2
-        return self.rp.block().number() == tx.size(self.filename.clone()) - 1;
+        return Ok(self.rp.block().number() == tx.size(self.filename.clone())? - 1);
     }
 }
 
@@ -164,17 +449,20 @@ mod tests {
     use crate::file_manager::file_mgr::FileMgr;
     use crate::log_manager::log_mgr::LogMgr;
     use crate::record_management::schema::Schema;
+    use crate::transaction_manager::concurrency_manager::lock_table::LockTable;
     use crate::transaction_manager::transaction::Transaction;
+    use crate::transaction_manager::version_manager::{TxMode, VersionMgr};
+    use std::time::Duration;
 
     use super::*;
     use anyhow::Result;
     use std::fs;
     use std::sync::{Arc, Mutex};
 
-    fn setup() {
+    fn setup(db_directory: &str) {
         // delete db_deirectory if exists
-        if fs::metadata("./db/tablescantest".to_string()).is_ok() {
-            fs::remove_dir_all("./db/tablescantest".to_string()).unwrap();
+        if fs::metadata(db_directory).is_ok() {
+            fs::remove_dir_all(db_directory).unwrap();
         }
     }
 
@@ -182,42 +470,49 @@ mod tests {
     fn test_table_scan() -> Result<()> {
         let db_directory = "./db/tablescantest".to_string();
         let log_file = "testfile".to_string();
-        setup();
+        setup(&db_directory);
 
         let fm = FileMgr::new(db_directory.clone(), 400);
         let log_mgr = Arc::new(Mutex::new(
             LogMgr::new(fm.clone(), log_file.clone()).unwrap(),
         ));
         let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
-        let mut tx = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+        let mut tx = Transaction::new(
+            fm.clone(),
+            log_mgr.clone(),
+            bm.clone(),
+            Arc::new(LockTable::new()),
+            Arc::new(VersionMgr::new()),
+            TxMode::Locking,
+        );
         let mut sch = Schema::new();
         sch.add_int_field("A".to_string());
         sch.add_string_field("B".to_string(), 9);
         let layout = Layout::new_from_schema(sch.clone());
-        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone());
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
 
-        ts.before_first(&mut tx);
+        ts.before_first(&mut tx)?;
         for i in 0..50 {
             // record must be following
             // if i = 0
-            // | 00 00 00 01 | 00 00 00 00 |  00 00 00 04  | 73 65 63 30 00 00 00 00 00 00 |
-            // | USED flag   |   "A" field | "B" field len |          "B" field            |
+            // | 00 00 00 01 | txnum   | ff ff ff ff | ff ff ff ff | 00 00 00 00 |  00 00 00 04  | 73 65 63 30 00 00 00 00 00 00 |
+            // | USED flag   | creator | deleter     | prev        |   "A" field | "B" field len |          "B" field            |
             // Be careful that the "B" field is 9 bytes
-            ts.insert(&mut tx); // allocate new record space
-            ts.set_int(&mut tx, &"A".to_string(), i);
+            ts.insert(&mut tx)?; // allocate new record space
+            ts.set_int(&mut tx, &"A".to_string(), i)?;
             ts.set_string(
                 &mut tx,
                 &"B".to_string(),
                 "rec".to_string() + &i.to_string(),
-            );
+            )?;
         }
         // Check insert
-        ts.before_first(&mut tx);
+        ts.before_first(&mut tx)?;
         let mut n = 0;
-        while ts.next(&mut tx) {
-            assert_eq!(ts.get_int(&mut tx, &"A".to_string()), n);
+        while ts.next(&mut tx)? {
+            assert_eq!(ts.get_int(&mut tx, &"A".to_string())?, n);
             assert_eq!(
-                ts.get_string(&mut tx, &"B".to_string()),
+                ts.get_string(&mut tx, &"B".to_string())?,
                 "rec".to_string() + &n.to_string()
             );
             n += 1;
@@ -229,18 +524,326 @@ mod tests {
         // assert!(!ts.next(&mut tx));
 
         // delete
-        ts.before_first(&mut tx);
-        while ts.next(&mut tx) {
+        ts.before_first(&mut tx)?;
+        while ts.next(&mut tx)? {
             // record must be following
             // if i = 0
-            // | 00 00 00 00 | 00 00 00 00 |  00 00 00 04  | 73 65 63 30 00 00 00 00 00 00 |
-            // | EMPTY flag  |   "A" field | "B" field len |          "B" field            |
-            ts.delete(&mut tx);
+            // | 00 00 00 01 | txnum   | txnum       | ff ff ff ff | 00 00 00 00 |  00 00 00 04  | 73 65 63 30 00 00 00 00 00 00 |
+            // | USED flag   | creator | deleter     | prev        |   "A" field | "B" field len |          "B" field            |
+            // the slot is freed by vacuum once every transaction sees the delete
+            ts.delete(&mut tx)?;
         }
         assert_eq!(ts.current_slot, -1);
 
         ts.close(&mut tx);
-        tx.commit();
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// the values of field A the transaction sees
+    fn values(tx: &mut Transaction, layout: &Layout) -> Vec<i32> {
+        let mut ts = TableScan::new(tx, "T".to_string(), layout.clone()).unwrap();
+        let mut vals = Vec::new();
+        while ts.next(tx).unwrap() {
+            vals.push(ts.get_int(tx, &"A".to_string()).unwrap());
+        }
+        ts.close(tx);
+        vals.sort();
+        vals
+    }
+
+    /// move the scan to the record having the value of field A
+    fn find(ts: &mut TableScan, tx: &mut Transaction, val: i32) {
+        ts.before_first(tx).unwrap();
+        while ts.next(tx).unwrap() {
+            if ts.get_int(tx, &"A".to_string()).unwrap() == val {
+                return;
+            }
+        }
+        panic!("no record has A = {}", val);
+    }
+
+    #[test]
+    fn test_snapshot_isolation() -> Result<()> {
+        let db_directory = "./db/snapshottest".to_string();
+        setup(&db_directory);
+
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testfile".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        // a writer waiting for a reader would fail quickly
+        let locktbl = Arc::new(LockTable::with_max_time(Duration::from_millis(100)));
+        let versions = Arc::new(VersionMgr::new());
+        let new_tx = |mode: TxMode| {
+            Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                mode,
+            )
+        };
+        let mut sch = Schema::new();
+        sch.add_int_field("A".to_string());
+        let layout = Layout::new_from_schema(sch);
+
+        let mut tx = new_tx(TxMode::Locking);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+        for i in 1..=3 {
+            ts.insert(&mut tx)?;
+            ts.set_int(&mut tx, &"A".to_string(), i)?;
+        }
+        ts.close(&mut tx);
+        tx.commit()?;
+
+        // the reader takes no locks, so the writer does not wait for it
+        let mut reader = new_tx(TxMode::Snapshot);
+        assert_eq!(values(&mut reader, &layout), vec![1, 2, 3]);
+        let mut writer = new_tx(TxMode::Locking);
+        let mut ts = TableScan::new(&mut writer, "T".to_string(), layout.clone())?;
+        find(&mut ts, &mut writer, 2);
+        ts.set_int(&mut writer, &"A".to_string(), 20)?;
+        find(&mut ts, &mut writer, 3);
+        ts.delete(&mut writer)?;
+        ts.insert(&mut writer)?;
+        ts.set_int(&mut writer, &"A".to_string(), 4)?;
+        ts.close(&mut writer);
+        assert_eq!(values(&mut writer, &layout), vec![1, 4, 20]);
+        // the reader sees neither the uncommitted nor the committed changes
+        assert_eq!(values(&mut reader, &layout), vec![1, 2, 3]);
+        writer.commit()?;
+        assert_eq!(values(&mut reader, &layout), vec![1, 2, 3]);
+        let mut later = new_tx(TxMode::Snapshot);
+        assert_eq!(values(&mut later, &layout), vec![1, 4, 20]);
+        later.commit()?;
+
+        // the reader cannot write a record whose newer version was committed first
+        let mut ts = TableScan::new(&mut reader, "T".to_string(), layout.clone())?;
+        find(&mut ts, &mut reader, 2);
+        assert!(matches!(
+            ts.lock_for_write(&mut reader),
+            Err(DbError::LockAbort(LockAbortError::WriteConflict(_)))
+        ));
+        ts.close(&mut reader);
+        reader.roll_back()?;
+
+        // of two concurrent writers of a record, the first to commit wins
+        let mut first = new_tx(TxMode::Snapshot);
+        let mut second = new_tx(TxMode::Snapshot);
+        let mut ts = TableScan::new(&mut first, "T".to_string(), layout.clone())?;
+        find(&mut ts, &mut first, 1);
+        ts.set_int(&mut first, &"A".to_string(), 10)?;
+        ts.close(&mut first);
+        first.commit()?;
+        let mut ts = TableScan::new(&mut second, "T".to_string(), layout.clone())?;
+        find(&mut ts, &mut second, 1);
+        assert!(ts.lock_for_write(&mut second).is_err());
+        // but a record nobody else wrote can be written
+        find(&mut ts, &mut second, 4);
+        ts.set_int(&mut second, &"A".to_string(), 40)?;
+        ts.close(&mut second);
+        assert_eq!(values(&mut second, &layout), vec![1, 20, 40]);
+        second.roll_back()?;
+
+        // vacuum reclaims the deleted record and the replaced versions of 1 and 2
+        let mut tx = new_tx(TxMode::Locking);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+        assert_eq!(ts.vacuum(&mut tx)?, 3);
+        assert_eq!(ts.vacuum(&mut tx)?, 0);
+        ts.close(&mut tx);
+        assert_eq!(values(&mut tx, &layout), vec![4, 10, 20]);
+        tx.commit()?;
+
+        setup(&db_directory);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reclaim() -> Result<()> {
+        let db_directory = "./db/reclaimtest".to_string();
+        setup(&db_directory);
+
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testfile".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        let locktbl = Arc::new(LockTable::with_max_time(Duration::from_millis(100)));
+        let versions = Arc::new(VersionMgr::new());
+        let new_tx = |mode: TxMode| {
+            Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                mode,
+            )
+        };
+        let mut sch = Schema::new();
+        sch.add_int_field("A".to_string());
+        let layout = Layout::new_from_schema(sch);
+        let a = "A".to_string();
+        let rewrite = |tx: &mut Transaction, vals: std::ops::Range<i32>| -> DbResult<()> {
+            let mut ts = TableScan::new(tx, "T".to_string(), layout.clone())?;
+            while ts.next(tx)? {
+                ts.delete(tx)?;
+            }
+            ts.before_first(tx)?;
+            for i in vals {
+                ts.insert(tx)?;
+                ts.set_int(tx, &a, i)?;
+            }
+            ts.close(tx);
+            Ok(())
+        };
+        let sizes = |tx: &mut Transaction| -> DbResult<(i32, i32)> {
+            Ok((tx.size("T.tbl".to_string())?, tx.size("T.ver".to_string())?))
+        };
+
+        let mut tx = new_tx(TxMode::Locking);
+        rewrite(&mut tx, 0..30)?;
+        tx.commit()?;
+        let mut tx = new_tx(TxMode::Locking);
+        let base = sizes(&mut tx)?;
+        assert!(base.0 > 1);
+        tx.commit()?;
+
+        // without snapshots the deleted records and replaced versions are freed right away,
+        // whether the records are inserted again by the same transaction or by a later one
+        for round in 0..5 {
+            let mut tx = new_tx(TxMode::Locking);
+            rewrite(&mut tx, 0..30)?;
+            if round % 2 == 0 {
+                tx.commit()?;
+                tx = new_tx(TxMode::Locking);
+            }
+            let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+            while ts.next(&mut tx)? {
+                let val = ts.get_int(&mut tx, &a)?;
+                ts.set_int(&mut tx, &a, val + 100)?;
+            }
+            ts.close(&mut tx);
+            assert_eq!(sizes(&mut tx)?, base);
+            tx.commit()?;
+        }
+        let mut tx = new_tx(TxMode::Locking);
+        assert_eq!(values(&mut tx, &layout), (100..130).collect::<Vec<i32>>());
+        tx.commit()?;
+
+        // a running snapshot keeps the deleted records,
+        // whose slots are reused once the snapshot is done
+        let mut reader = new_tx(TxMode::Snapshot);
+        assert_eq!(values(&mut reader, &layout).len(), 30);
+        let mut tx = new_tx(TxMode::Locking);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+        while ts.next(&mut tx)? {
+            ts.delete(&mut tx)?;
+        }
+        ts.close(&mut tx);
+        tx.commit()?;
+        assert_eq!(
+            values(&mut reader, &layout),
+            (100..130).collect::<Vec<i32>>()
+        );
+        reader.commit()?;
+        let mut tx = new_tx(TxMode::Locking);
+        rewrite(&mut tx, 0..30)?;
+        assert_eq!(sizes(&mut tx)?.0, base.0);
+        tx.commit()?;
+
+        // a snapshot begun while a writer keeps no versions is taken once the writer is done
+        let mut writer = new_tx(TxMode::Locking);
+        rewrite(&mut writer, 0..3)?;
+        let mut reader = new_tx(TxMode::Snapshot);
+        assert!(matches!(
+            TableScan::new(&mut reader, "T".to_string(), layout.clone()),
+            Err(DbError::LockAbort(LockAbortError::Timeout(_)))
+        ));
+        reader.roll_back()?;
+        let mut reader = new_tx(TxMode::Snapshot);
+        writer.commit()?;
+        assert_eq!(values(&mut reader, &layout), vec![0, 1, 2]);
+        reader.commit()?;
+
+        setup(&db_directory);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_reads_during_writes() -> Result<()> {
+        let db_directory = "./db/snapshotreadtest".to_string();
+        setup(&db_directory);
+
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testfile".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        let locktbl = Arc::new(LockTable::new());
+        let versions = Arc::new(VersionMgr::new());
+        let new_tx = move |mode: TxMode| {
+            Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                mode,
+            )
+        };
+        let mut sch = Schema::new();
+        sch.add_int_field("A".to_string());
+        sch.add_int_field("B".to_string());
+        let layout = Layout::new_from_schema(sch);
+
+        let mut tx = new_tx(TxMode::Locking);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"A".to_string(), 0)?;
+        ts.set_int(&mut tx, &"B".to_string(), 0)?;
+        ts.close(&mut tx);
+        tx.commit()?;
+
+        // the writer keeps A and B equal, replacing the record while the reader reads it
+        let mut reader = new_tx(TxMode::Snapshot);
+        let writer_tx = new_tx.clone();
+        let writer_layout = layout.clone();
+        let writer = std::thread::spawn(move || {
+            for i in 1..=50 {
+                let mut tx = writer_tx(TxMode::Locking);
+                let mut ts =
+                    TableScan::new(&mut tx, "T".to_string(), writer_layout.clone()).unwrap();
+                assert!(ts.next(&mut tx).unwrap());
+                ts.set_int(&mut tx, &"A".to_string(), i).unwrap();
+                ts.set_int(&mut tx, &"B".to_string(), i).unwrap();
+                ts.close(&mut tx);
+                tx.commit().unwrap();
+            }
+        });
+        let mut ts = TableScan::new(&mut reader, "T".to_string(), layout.clone())?;
+        while !writer.is_finished() {
+            ts.before_first(&mut reader)?;
+            assert!(ts.next(&mut reader)?);
+            assert_eq!(ts.get_int(&mut reader, &"A".to_string())?, 0);
+            assert_eq!(ts.get_int(&mut reader, &"B".to_string())?, 0);
+            assert!(!ts.next(&mut reader)?);
+        }
+        writer.join().unwrap();
+        ts.close(&mut reader);
+        reader.commit()?;
+
+        let mut tx = new_tx(TxMode::Snapshot);
+        let mut ts = TableScan::new(&mut tx, "T".to_string(), layout.clone())?;
+        assert!(ts.next(&mut tx)?);
+        assert_eq!(ts.get_int(&mut tx, &"A".to_string())?, 50);
+        ts.close(&mut tx);
+        tx.commit()?;
+
+        setup(&db_directory);
         Ok(())
     }
 }
@@ -1,5 +1,8 @@
 pub mod common;
 pub mod expression;
+pub mod extend_scan;
+pub mod index_join_scan;
+pub mod index_select_scan;
 pub mod predicate;
 pub mod production_scan;
 pub mod project_scan;
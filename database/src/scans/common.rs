@@ -1,7 +1,11 @@
 use crate::common::Constant;
+use crate::error::DbResult;
 use crate::record_management::rid::RID;
 
 use crate::record_management::table_scan::TableScan;
+use crate::scans::extend_scan::ExtendScan;
+use crate::scans::index_join_scan::IndexJoinScan;
+use crate::scans::index_select_scan::IndexSelectScan;
 use crate::scans::project_scan::ProjectScan;
 use crate::scans::select_scan::SelectScan;
 use crate::transaction_manager::transaction::Transaction;
@@ -9,23 +13,24 @@ use crate::transaction_manager::transaction::Transaction;
 use super::production_scan::ProductionScan;
 
 pub trait Scan {
-    fn before_first(&mut self, tx: &mut Transaction);
-    fn next(&mut self, tx: &mut Transaction) -> bool;
-    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> i32;
-    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> String;
-    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> Constant;
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()>;
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool>;
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32>;
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String>;
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant>;
     fn has_field(&self, fldname: String) -> bool;
     fn close(&mut self, tx: &mut Transaction);
 }
 
 pub trait UpdateScan: Scan {
-    fn set_int(&mut self, fldname: String, newval: i32, tx: &mut Transaction);
-    fn set_string(&mut self, fldname: String, newval: String, tx: &mut Transaction);
-    fn set_val(&mut self, fldname: String, newval: Constant, tx: &mut Transaction);
-    fn delete(&mut self, tx: &mut Transaction);
-    fn insert(&mut self, tx: &mut Transaction);
+    fn set_int(&mut self, fldname: String, newval: i32, tx: &mut Transaction) -> DbResult<()>;
+    fn set_string(&mut self, fldname: String, newval: String, tx: &mut Transaction)
+        -> DbResult<()>;
+    fn set_val(&mut self, fldname: String, newval: Constant, tx: &mut Transaction) -> DbResult<()>;
+    fn delete(&mut self, tx: &mut Transaction) -> DbResult<()>;
+    fn insert(&mut self, tx: &mut Transaction) -> DbResult<()>;
     fn get_rid(&self) -> RID;
-    fn move_to_rid(&mut self, rid: RID, tx: &mut Transaction);
+    fn move_to_rid(&mut self, rid: RID, tx: &mut Transaction) -> DbResult<()>;
 }
 
 pub enum ScanType {
@@ -33,51 +38,69 @@ pub enum ScanType {
     ProjectScan(ProjectScan),
     ProductionScan(ProductionScan),
     TableScan(TableScan),
+    IndexSelectScan(IndexSelectScan),
+    IndexJoinScan(IndexJoinScan),
+    ExtendScan(ExtendScan),
 }
 
 impl Scan for ScanType {
-    fn before_first(&mut self, tx: &mut Transaction) {
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.before_first(tx),
             ScanType::ProjectScan(s) => s.before_first(tx),
             ScanType::ProductionScan(s) => s.before_first(tx),
             ScanType::TableScan(s) => s.before_first(tx),
+            ScanType::IndexSelectScan(s) => s.before_first(tx),
+            ScanType::IndexJoinScan(s) => s.before_first(tx),
+            ScanType::ExtendScan(s) => s.before_first(tx),
         }
     }
 
-    fn next(&mut self, tx: &mut Transaction) -> bool {
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
         match self {
             ScanType::SelectScan(s) => s.next(tx),
             ScanType::ProjectScan(s) => s.next(tx),
             ScanType::ProductionScan(s) => s.next(tx),
             ScanType::TableScan(s) => s.next(tx),
+            ScanType::IndexSelectScan(s) => s.next(tx),
+            ScanType::IndexJoinScan(s) => s.next(tx),
+            ScanType::ExtendScan(s) => s.next(tx),
         }
     }
 
-    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> i32 {
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32> {
         match self {
             ScanType::SelectScan(s) => s.get_int(fldname, tx),
             ScanType::ProjectScan(s) => s.get_int(fldname, tx),
             ScanType::ProductionScan(s) => s.get_int(fldname, tx),
             ScanType::TableScan(s) => s.get_int(tx, &fldname),
+            ScanType::IndexSelectScan(s) => s.get_int(fldname, tx),
+            ScanType::IndexJoinScan(s) => s.get_int(fldname, tx),
+            ScanType::ExtendScan(s) => s.get_int(fldname, tx),
         }
     }
 
-    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> String {
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String> {
         match self {
             ScanType::SelectScan(s) => s.get_string(fldname, tx),
             ScanType::ProjectScan(s) => s.get_string(fldname, tx),
             ScanType::ProductionScan(s) => s.get_string(fldname, tx),
             ScanType::TableScan(s) => s.get_string(tx, &fldname),
+            ScanType::IndexSelectScan(s) => s.get_string(fldname, tx),
+            ScanType::IndexJoinScan(s) => s.get_string(fldname, tx),
+            ScanType::ExtendScan(s) => s.get_string(fldname, tx),
         }
     }
 
-    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> Constant {
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant> {
         match self {
             ScanType::SelectScan(s) => s.get_val(fldname, tx),
             ScanType::ProjectScan(s) => s.get_val(fldname, tx),
             ScanType::ProductionScan(s) => s.get_val(fldname, tx),
             ScanType::TableScan(s) => s.get_value(tx, &fldname),
+            ScanType::IndexSelectScan(s) => s.get_val(fldname, tx),
+            ScanType::IndexJoinScan(s) => s.get_val(fldname, tx),
+            ScanType::ExtendScan(s) => s.get_val(fldname, tx),
         }
     }
 
@@ -87,6 +110,9 @@ impl Scan for ScanType {
             ScanType::ProjectScan(s) => s.has_field(fldname),
             ScanType::ProductionScan(s) => s.has_field(fldname),
             ScanType::TableScan(s) => s.has_field(&fldname),
+            ScanType::IndexSelectScan(s) => s.has_field(fldname),
+            ScanType::IndexJoinScan(s) => s.has_field(fldname),
+            ScanType::ExtendScan(s) => s.has_field(fldname),
         }
     }
 
@@ -96,12 +122,15 @@ impl Scan for ScanType {
             ScanType::ProjectScan(s) => s.close(tx),
             ScanType::ProductionScan(s) => s.close(tx),
             ScanType::TableScan(s) => s.close(tx),
+            ScanType::IndexSelectScan(s) => s.close(tx),
+            ScanType::IndexJoinScan(s) => s.close(tx),
+            ScanType::ExtendScan(s) => s.close(tx),
         }
     }
 }
 
 impl UpdateScan for ScanType {
-    fn set_int(&mut self, fldname: String, newval: i32, tx: &mut Transaction) {
+    fn set_int(&mut self, fldname: String, newval: i32, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.set_int(fldname, newval, tx),
             ScanType::TableScan(s) => s.set_int(tx, &fldname, newval),
@@ -109,7 +138,12 @@ impl UpdateScan for ScanType {
         }
     }
 
-    fn set_string(&mut self, fldname: String, newval: String, tx: &mut Transaction) {
+    fn set_string(
+        &mut self,
+        fldname: String,
+        newval: String,
+        tx: &mut Transaction,
+    ) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.set_string(fldname, newval, tx),
             ScanType::TableScan(s) => s.set_string(tx, &fldname, newval),
@@ -117,7 +151,7 @@ impl UpdateScan for ScanType {
         }
     }
 
-    fn set_val(&mut self, fldname: String, newval: Constant, tx: &mut Transaction) {
+    fn set_val(&mut self, fldname: String, newval: Constant, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.set_val(fldname, newval, tx),
             ScanType::TableScan(s) => s.set_value(tx, &fldname, newval),
@@ -125,7 +159,7 @@ impl UpdateScan for ScanType {
         }
     }
 
-    fn delete(&mut self, tx: &mut Transaction) {
+    fn delete(&mut self, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.delete(tx),
             ScanType::TableScan(s) => s.delete(tx),
@@ -133,7 +167,7 @@ impl UpdateScan for ScanType {
         }
     }
 
-    fn insert(&mut self, tx: &mut Transaction) {
+    fn insert(&mut self, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.insert(tx),
             ScanType::TableScan(s) => s.insert(tx),
@@ -149,10 +183,13 @@ impl UpdateScan for ScanType {
         }
     }
 
-    fn move_to_rid(&mut self, rid: RID, tx: &mut Transaction) {
+    fn move_to_rid(&mut self, rid: RID, tx: &mut Transaction) -> DbResult<()> {
         match self {
             ScanType::SelectScan(s) => s.move_to_rid(rid, tx),
-            ScanType::TableScan(s) => s.move_to_rid(tx, rid),
+            ScanType::TableScan(s) => {
+                s.move_to_rid(tx, rid)?;
+                Ok(())
+            }
             _ => panic!("not implemented UpdateScan::move_to_rid"),
         }
     }
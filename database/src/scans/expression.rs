@@ -1,86 +1,547 @@
 use std::fmt::Display;
 
 use crate::common::Constant;
-use crate::record_management::schema::Schema;
+use crate::error::{DbError, DbResult};
+use crate::record_management::schema::{Schema, Type};
 use crate::scans::common::ScanType;
 use crate::transaction_manager::transaction::Transaction;
 
 use super::common::Scan;
 
-#[derive(Clone)]
-pub struct Expression {
-    val: Option<Constant>,
-    fldname: Option<String>,
+/// a binary operator of an expression
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Concat,
+}
+
+impl BinaryOp {
+    pub fn to_str(&self) -> &str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Concat => "||",
+        }
+    }
+
+    /// operators of higher precedence bind tighter
+    fn precedence(&self) -> i32 {
+        match self {
+            BinaryOp::Concat => 1,
+            BinaryOp::Add | BinaryOp::Sub => 2,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 3,
+        }
+    }
+}
+
+/// a built-in function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Length,
+    Upper,
+    Lower,
+    Substr,
+    Abs,
+    Coalesce,
+}
+
+impl Function {
+    /// the function of the given name, ignoring case
+    pub fn from_name(name: &str) -> Option<Function> {
+        match name.to_lowercase().as_str() {
+            "length" => Some(Function::Length),
+            "upper" => Some(Function::Upper),
+            "lower" => Some(Function::Lower),
+            "substr" => Some(Function::Substr),
+            "abs" => Some(Function::Abs),
+            "coalesce" => Some(Function::Coalesce),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Function::Length => "length",
+            Function::Upper => "upper",
+            Function::Lower => "lower",
+            Function::Substr => "substr",
+            Function::Abs => "abs",
+            Function::Coalesce => "coalesce",
+        }
+    }
+}
+
+/// an expression tree
+/// an operator or function applied to null gives null, except for coalesce
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Constant(Constant),
+    Field(String),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    Negate(Box<Expression>),
+    Call(Function, Vec<Expression>),
 }
 
 impl Expression {
     pub fn new_from_val(val: Constant) -> Self {
-        return Self {
-            val: Some(val),
-            fldname: None,
-        };
+        return Expression::Constant(val);
     }
 
     pub fn new_from_fldname(fldname: String) -> Self {
-        return Self {
-            val: None,
-            fldname: Some(fldname),
-        };
+        return Expression::Field(fldname);
+    }
+
+    pub fn new_binary(op: BinaryOp, lhs: Expression, rhs: Expression) -> Self {
+        return Expression::Binary(op, Box::new(lhs), Box::new(rhs));
+    }
+
+    /// the expression -e
+    /// the negation of an integer constant is folded into the constant
+    pub fn new_negate(e: Expression) -> Self {
+        match e {
+            Expression::Constant(Constant::Int(i)) if i != i32::MIN => {
+                return Expression::Constant(Constant::Int(-i));
+            }
+            e => return Expression::Negate(Box::new(e)),
+        }
+    }
+
+    pub fn new_call(func: Function, args: Vec<Expression>) -> Self {
+        return Expression::Call(func, args);
     }
 
     pub fn is_field_name(&self) -> bool {
-        return self.fldname.is_some();
+        return matches!(self, Expression::Field(_));
     }
 
     pub fn as_constant(&self) -> Option<Constant> {
-        return self.val.clone();
+        match self {
+            Expression::Constant(c) => return Some(c.clone()),
+            _ => return None,
+        }
     }
 
     pub fn as_field_name(&self) -> Option<String> {
-        return self.fldname.clone();
+        match self {
+            Expression::Field(f) => return Some(f.clone()),
+            _ => return None,
+        }
     }
 
-    pub fn evaluate(&self, s: &mut ScanType, tx: &mut Transaction) -> Constant {
-        match (&self.val.clone(), &self.fldname) {
-            (Some(v), None) => {
-                return v.clone();
-            }
-            (None, Some(f)) => {
-                return s.get_val(f.clone(), tx);
+    pub fn evaluate(&self, s: &mut ScanType, tx: &mut Transaction) -> DbResult<Constant> {
+        match self {
+            Expression::Constant(v) => return Ok(v.clone()),
+            Expression::Field(f) => return s.get_val(f.clone(), tx),
+            Expression::Binary(op, lhs, rhs) => {
+                let l = lhs.evaluate(s, tx)?;
+                let r = rhs.evaluate(s, tx)?;
+                return Self::apply_binary(*op, l, r);
             }
-            _ => {
-                panic!("Expression is malformed.");
+            Expression::Negate(e) => match e.evaluate(s, tx)? {
+                Constant::Int(i) => match i.checked_neg() {
+                    Some(v) => return Ok(Constant::Int(v)),
+                    None => return Err(DbError::Type("integer overflow".to_string())),
+                },
+                Constant::Null => return Ok(Constant::Null),
+                v => return Err(DbError::Type(format!("cannot negate {}", v))),
+            },
+            Expression::Call(func, args) => {
+                let mut vals = Vec::new();
+                for arg in args.iter() {
+                    vals.push(arg.evaluate(s, tx)?);
+                }
+                return Self::apply_function(*func, vals);
             }
         }
     }
 
+    /// whether every field mentioned in the expression is in the schema
     pub fn applies_to(&self, sch: &Schema) -> bool {
-        match (&self.val.clone(), &self.fldname) {
-            (Some(_), None) => {
-                return true;
+        match self {
+            Expression::Constant(_) => return true,
+            Expression::Field(f) => return sch.has_field(f),
+            Expression::Binary(_, lhs, rhs) => return lhs.applies_to(sch) && rhs.applies_to(sch),
+            Expression::Negate(e) => return e.applies_to(sch),
+            Expression::Call(_, args) => return args.iter().all(|arg| arg.applies_to(sch)),
+        }
+    }
+
+    /// check the expression against the schema and return the type of its value
+    /// @return: None if the value is always null
+    pub fn type_of(&self, sch: &Schema) -> DbResult<Option<Type>> {
+        match self {
+            Expression::Constant(Constant::Int(_)) => return Ok(Some(Type::INTEGER)),
+            Expression::Constant(Constant::String(_)) => return Ok(Some(Type::VARCHAR)),
+            Expression::Constant(Constant::Null) => return Ok(None),
+            Expression::Field(f) => {
+                if !sch.has_field(f) {
+                    return Err(DbError::Catalog(format!("field {} not found", f)));
+                }
+                return Ok(Some(sch.get_type_(f)?));
+            }
+            Expression::Binary(op, lhs, rhs) => {
+                let expected = match op {
+                    BinaryOp::Concat => Type::VARCHAR,
+                    _ => Type::INTEGER,
+                };
+                lhs.expect_type(sch, expected, self)?;
+                rhs.expect_type(sch, expected, self)?;
+                return Ok(Some(expected));
+            }
+            Expression::Negate(e) => {
+                e.expect_type(sch, Type::INTEGER, self)?;
+                return Ok(Some(Type::INTEGER));
             }
-            (None, Some(f)) => {
-                return sch.has_field(&f);
+            Expression::Call(func, args) => return self.call_type(*func, args, sch),
+        }
+    }
+
+    /// the largest length of a string value of the expression
+    /// the expression must have been type checked against the schema
+    pub fn length(&self, sch: &Schema) -> DbResult<i32> {
+        match self {
+            Expression::Constant(Constant::String(s)) => return Ok(s.chars().count() as i32),
+            Expression::Constant(_) => return Ok(0),
+            Expression::Field(f) => return sch.get_length(f),
+            Expression::Binary(BinaryOp::Concat, lhs, rhs) => {
+                return Ok(lhs.length(sch)? + rhs.length(sch)?)
             }
+            Expression::Binary(_, _, _) | Expression::Negate(_) => return Ok(0),
+            Expression::Call(Function::Coalesce, args) => {
+                let mut length = 0;
+                for arg in args {
+                    length = length.max(arg.length(sch)?);
+                }
+                return Ok(length);
+            }
+            Expression::Call(Function::Length | Function::Abs, _) => return Ok(0),
+            Expression::Call(_, args) => return args[0].length(sch),
+        }
+    }
+
+    fn expect_type(&self, sch: &Schema, expected: Type, parent: &Expression) -> DbResult<()> {
+        match self.type_of(sch)? {
+            Some(t) if t != expected => Err(DbError::Type(format!(
+                "{} expects {}, but {} is {}",
+                parent,
+                expected.to_str(),
+                self,
+                t.to_str()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn call_type(
+        &self,
+        func: Function,
+        args: &[Expression],
+        sch: &Schema,
+    ) -> DbResult<Option<Type>> {
+        let arity_ok = match func {
+            Function::Substr => args.len() == 2 || args.len() == 3,
+            Function::Coalesce => !args.is_empty(),
+            _ => args.len() == 1,
+        };
+        if !arity_ok {
+            return Err(DbError::Type(format!(
+                "wrong number of arguments in {}",
+                self
+            )));
+        }
+        match func {
+            Function::Length => {
+                args[0].expect_type(sch, Type::VARCHAR, self)?;
+                return Ok(Some(Type::INTEGER));
+            }
+            Function::Upper | Function::Lower => {
+                args[0].expect_type(sch, Type::VARCHAR, self)?;
+                return Ok(Some(Type::VARCHAR));
+            }
+            Function::Substr => {
+                args[0].expect_type(sch, Type::VARCHAR, self)?;
+                for arg in args[1..].iter() {
+                    arg.expect_type(sch, Type::INTEGER, self)?;
+                }
+                return Ok(Some(Type::VARCHAR));
+            }
+            Function::Abs => {
+                args[0].expect_type(sch, Type::INTEGER, self)?;
+                return Ok(Some(Type::INTEGER));
+            }
+            Function::Coalesce => {
+                // every argument must have the type of the first non-null one
+                let mut result = None;
+                for arg in args.iter() {
+                    match (result, arg.type_of(sch)?) {
+                        (None, t) => result = t,
+                        (Some(expected), Some(_)) => arg.expect_type(sch, expected, self)?,
+                        (Some(_), None) => {}
+                    }
+                }
+                return Ok(result);
+            }
+        }
+    }
+
+    fn apply_binary(op: BinaryOp, l: Constant, r: Constant) -> DbResult<Constant> {
+        let (l, r) = match (op, l, r) {
+            (_, Constant::Null, _) | (_, _, Constant::Null) => return Ok(Constant::Null),
+            (BinaryOp::Concat, Constant::String(l), Constant::String(r)) => {
+                return Ok(Constant::String(l + &r))
+            }
+            (BinaryOp::Concat, l, r) => {
+                return Err(DbError::Type(format!("cannot concatenate {} and {}", l, r)))
+            }
+            (_, Constant::Int(l), Constant::Int(r)) => (l, r),
+            (op, l, r) => {
+                return Err(DbError::Type(format!(
+                    "cannot apply {} to {} and {}",
+                    op.to_str(),
+                    l,
+                    r
+                )))
+            }
+        };
+        let v = match op {
+            BinaryOp::Add => l.checked_add(r),
+            BinaryOp::Sub => l.checked_sub(r),
+            BinaryOp::Mul => l.checked_mul(r),
+            BinaryOp::Div | BinaryOp::Mod if r == 0 => {
+                return Err(DbError::Type("division by zero".to_string()))
+            }
+            BinaryOp::Div => l.checked_div(r),
+            BinaryOp::Mod => l.checked_rem(r),
+            BinaryOp::Concat => unreachable!(),
+        };
+        match v {
+            Some(v) => return Ok(Constant::Int(v)),
+            None => return Err(DbError::Type("integer overflow".to_string())),
+        }
+    }
+
+    fn apply_function(func: Function, vals: Vec<Constant>) -> DbResult<Constant> {
+        if func == Function::Coalesce {
+            for v in vals {
+                if v != Constant::Null {
+                    return Ok(v);
+                }
+            }
+            return Ok(Constant::Null);
+        }
+        if vals.contains(&Constant::Null) {
+            return Ok(Constant::Null);
+        }
+        match (func, vals.as_slice()) {
+            (Function::Length, [Constant::String(s)]) => {
+                return Ok(Constant::Int(s.chars().count() as i32))
+            }
+            (Function::Upper, [Constant::String(s)]) => {
+                return Ok(Constant::String(s.to_uppercase()))
+            }
+            (Function::Lower, [Constant::String(s)]) => {
+                return Ok(Constant::String(s.to_lowercase()))
+            }
+            (Function::Substr, [Constant::String(s), Constant::Int(start), rest @ ..]) => {
+                // the first character is at position 1
+                let len = match rest {
+                    [Constant::Int(len)] => (*len).max(0) as usize,
+                    _ => usize::MAX,
+                };
+                let skip = (*start - 1).max(0) as usize;
+                return Ok(Constant::String(s.chars().skip(skip).take(len).collect()));
+            }
+            (Function::Abs, [Constant::Int(i)]) => match i.checked_abs() {
+                Some(v) => return Ok(Constant::Int(v)),
+                None => return Err(DbError::Type("integer overflow".to_string())),
+            },
             _ => {
-                panic!("Expression is malformed.");
+                return Err(DbError::Type(format!(
+                    "invalid arguments for {}",
+                    func.to_str()
+                )))
             }
         }
     }
+
+    /// the precedence of the expression when it is an operand, higher binds tighter
+    fn precedence(&self) -> i32 {
+        match self {
+            Expression::Binary(op, _, _) => op.precedence(),
+            _ => i32::MAX,
+        }
+    }
+
+    /// whether the text of the expression starts with a minus sign
+    fn is_negative(&self) -> bool {
+        match self {
+            Expression::Negate(_) => true,
+            Expression::Constant(Constant::Int(i)) => *i < 0,
+            _ => false,
+        }
+    }
 }
 
+/// the text can be parsed again into the same expression
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match (&self.val.clone(), &self.fldname) {
-            (Some(v), None) => {
-                return write!(f, "{}", v);
+        match self {
+            // a quote inside the constant is doubled, so that the text can be parsed again
+            Expression::Constant(Constant::String(s)) => {
+                return write!(f, "'{}'", s.replace('\'', "''"))
             }
-            (None, Some(fname)) => {
-                return write!(f, "{}", fname);
+            Expression::Constant(v) => return write!(f, "{}", v),
+            Expression::Field(fname) => return write!(f, "{}", fname),
+            Expression::Binary(op, lhs, rhs) => {
+                // operators are left associative
+                if lhs.precedence() < op.precedence() {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    write!(f, "{}", lhs)?;
+                }
+                write!(f, " {} ", op.to_str())?;
+                if rhs.precedence() <= op.precedence() || rhs.is_negative() {
+                    return write!(f, "({})", rhs);
+                }
+                return write!(f, "{}", rhs);
             }
-            _ => {
-                panic!("Expression is malformed.");
+            Expression::Negate(e) => {
+                if e.precedence() < i32::MAX || e.is_negative() {
+                    return write!(f, "-({})", e);
+                }
+                return write!(f, "-{}", e);
+            }
+            Expression::Call(func, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                return write!(f, "{}({})", func.to_str(), args.join(", "));
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(i: i32) -> Expression {
+        Expression::new_from_val(Constant::Int(i))
+    }
+
+    fn string(s: &str) -> Expression {
+        Expression::new_from_val(Constant::String(s.to_string()))
+    }
+
+    fn field(f: &str) -> Expression {
+        Expression::new_from_fldname(f.to_string())
+    }
+
+    fn schema() -> Schema {
+        let mut sch = Schema::new();
+        sch.add_int_field("age".to_string());
+        sch.add_string_field("name".to_string(), 10);
+        sch
+    }
+
+    #[test]
+    fn test_expression() {
+        // (age + 1) * -2
+        let e = Expression::new_binary(
+            BinaryOp::Mul,
+            Expression::new_binary(BinaryOp::Add, field("age"), int(1)),
+            Expression::new_negate(int(2)),
+        );
+        assert_eq!(e.to_string(), "(age + 1) * (-2)");
+        assert_eq!(e.type_of(&schema()).unwrap(), Some(Type::INTEGER));
+        assert!(e.applies_to(&schema()));
+        assert_eq!(Expression::new_negate(field("age")).to_string(), "-age");
+
+        let e = Expression::new_binary(
+            BinaryOp::Sub,
+            int(1),
+            Expression::new_binary(BinaryOp::Sub, int(2), int(3)),
+        );
+        assert_eq!(e.to_string(), "1 - (2 - 3)");
+
+        // a concatenation is as long as its operands together
+        let e = Expression::new_binary(BinaryOp::Concat, field("name"), string("x"));
+        assert_eq!(e.to_string(), "name || 'x'");
+        assert_eq!(e.type_of(&schema()).unwrap(), Some(Type::VARCHAR));
+        assert_eq!(e.length(&schema()).unwrap(), 11);
+        assert_eq!(string("it's").to_string(), "'it''s'");
+
+        // type errors
+        let e = Expression::new_binary(BinaryOp::Add, field("name"), int(1));
+        assert!(matches!(e.type_of(&schema()), Err(DbError::Type(_))));
+        let e = Expression::new_call(Function::Upper, vec![field("age")]);
+        assert!(matches!(e.type_of(&schema()), Err(DbError::Type(_))));
+        let e = Expression::new_call(Function::Substr, vec![field("name")]);
+        assert!(matches!(e.type_of(&schema()), Err(DbError::Type(_))));
+        let e = Expression::new_call(
+            Function::Coalesce,
+            vec![
+                Expression::new_from_val(Constant::Null),
+                field("age"),
+                string("a"),
+            ],
+        );
+        assert!(matches!(e.type_of(&schema()), Err(DbError::Type(_))));
+        let e = Expression::new_binary(BinaryOp::Add, field("height"), int(1));
+        assert!(matches!(e.type_of(&schema()), Err(DbError::Catalog(_))));
+
+        // functions
+        let s = |v: &str| Constant::String(v.to_string());
+        assert_eq!(
+            Expression::apply_function(Function::Length, vec![s("héllo")]).unwrap(),
+            Constant::Int(5)
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Upper, vec![s("abc")]).unwrap(),
+            s("ABC")
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Lower, vec![s("AbC")]).unwrap(),
+            s("abc")
+        );
+        assert_eq!(
+            Expression::apply_function(
+                Function::Substr,
+                vec![s("abcdef"), Constant::Int(2), Constant::Int(3)]
+            )
+            .unwrap(),
+            s("bcd")
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Substr, vec![s("abcdef"), Constant::Int(4)])
+                .unwrap(),
+            s("def")
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Abs, vec![Constant::Int(-3)]).unwrap(),
+            Constant::Int(3)
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Abs, vec![Constant::Null]).unwrap(),
+            Constant::Null
+        );
+        assert_eq!(
+            Expression::apply_function(Function::Coalesce, vec![Constant::Null, Constant::Int(7)])
+                .unwrap(),
+            Constant::Int(7)
+        );
+        assert_eq!(
+            Expression::apply_binary(BinaryOp::Mod, Constant::Int(7), Constant::Int(3)).unwrap(),
+            Constant::Int(1)
+        );
+        assert_eq!(
+            Expression::apply_binary(BinaryOp::Div, Constant::Int(7), Constant::Null).unwrap(),
+            Constant::Null
+        );
+    }
+}
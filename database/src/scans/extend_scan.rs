@@ -0,0 +1,71 @@
+use crate::common::Constant;
+use crate::error::{DbError, DbResult};
+use crate::scans::common::Scan;
+use crate::scans::common::ScanType;
+use crate::scans::expression::Expression;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the scan for the extend relational algebra operator
+/// each record of the underlying scan gets the computed fields
+pub struct ExtendScan {
+    s: Box<ScanType>,
+    fields: Vec<(String, Expression)>,
+}
+
+impl ExtendScan {
+    pub fn new(s: Box<ScanType>, fields: Vec<(String, Expression)>) -> Self {
+        return Self { s, fields };
+    }
+
+    fn expression(&self, fldname: &String) -> Option<Expression> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == fldname)
+            .map(|(_, expr)| expr.clone())
+    }
+}
+
+impl Scan for ExtendScan {
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.s.before_first(tx)
+    }
+
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        self.s.next(tx)
+    }
+
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32> {
+        match self.get_val(fldname.clone(), tx)? {
+            Constant::Int(i) => Ok(i),
+            v => Err(DbError::Type(format!(
+                "field {} is not an integer: {}",
+                fldname, v
+            ))),
+        }
+    }
+
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String> {
+        match self.get_val(fldname.clone(), tx)? {
+            Constant::String(s) => Ok(s),
+            v => Err(DbError::Type(format!(
+                "field {} is not a string: {}",
+                fldname, v
+            ))),
+        }
+    }
+
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant> {
+        match self.expression(&fldname) {
+            Some(expr) => expr.evaluate(&mut self.s, tx),
+            None => self.s.get_val(fldname, tx),
+        }
+    }
+
+    fn has_field(&self, fldname: String) -> bool {
+        self.fields.iter().any(|(name, _)| *name == fldname) || self.s.has_field(fldname)
+    }
+
+    fn close(&mut self, tx: &mut Transaction) {
+        self.s.close(tx);
+    }
+}
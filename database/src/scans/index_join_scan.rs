@@ -0,0 +1,104 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::indexing::index::{Index, IndexType};
+use crate::record_management::table_scan::TableScan;
+use crate::scans::common::{Scan, ScanType};
+use crate::transaction_manager::transaction::Transaction;
+
+/// the scan for the join relational algebra operator using an index of the right-hand table
+/// for each record of lhs, the index is probed with the value of the join field
+pub struct IndexJoinScan {
+    lhs: Box<ScanType>,
+    idx: IndexType,
+    joinfield: String,
+    rhs: TableScan,
+    has_lhs: bool,
+}
+
+impl IndexJoinScan {
+    pub fn new(
+        lhs: Box<ScanType>,
+        idx: IndexType,
+        joinfield: String,
+        rhs: TableScan,
+        tx: &mut Transaction,
+    ) -> DbResult<Self> {
+        let mut s = Self {
+            lhs,
+            idx,
+            joinfield,
+            rhs,
+            has_lhs: false,
+        };
+        s.before_first(tx)?;
+        return Ok(s);
+    }
+
+    /// probe the index with the join field of the current lhs record
+    fn reset_index(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        let search_key = self.lhs.get_val(self.joinfield.clone(), tx)?;
+        self.idx.before_first(search_key, tx)
+    }
+}
+
+impl Scan for IndexJoinScan {
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.lhs.before_first(tx)?;
+        self.has_lhs = self.lhs.next(tx)?;
+        if self.has_lhs {
+            self.reset_index(tx)?;
+        }
+        Ok(())
+    }
+
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        while self.has_lhs {
+            while self.idx.next(tx)? {
+                // skip the records the transaction does not see
+                let rid = self.idx.get_data_rid(tx)?;
+                if self.rhs.move_to_rid(tx, rid)? {
+                    return Ok(true);
+                }
+            }
+            self.has_lhs = self.lhs.next(tx)?;
+            if self.has_lhs {
+                self.reset_index(tx)?;
+            }
+        }
+        return Ok(false);
+    }
+
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32> {
+        if self.rhs.has_field(&fldname) {
+            self.rhs.get_int(tx, &fldname)
+        } else {
+            self.lhs.get_int(fldname, tx)
+        }
+    }
+
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String> {
+        if self.rhs.has_field(&fldname) {
+            self.rhs.get_string(tx, &fldname)
+        } else {
+            self.lhs.get_string(fldname, tx)
+        }
+    }
+
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant> {
+        if self.rhs.has_field(&fldname) {
+            self.rhs.get_value(tx, &fldname)
+        } else {
+            self.lhs.get_val(fldname, tx)
+        }
+    }
+
+    fn has_field(&self, fldname: String) -> bool {
+        self.rhs.has_field(&fldname) || self.lhs.has_field(fldname)
+    }
+
+    fn close(&mut self, tx: &mut Transaction) {
+        self.lhs.close(tx);
+        self.idx.close(tx);
+        self.rhs.close(tx);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::indexing::index::{Index, IndexType};
+use crate::record_management::table_scan::TableScan;
+use crate::scans::common::Scan;
+use crate::transaction_manager::transaction::Transaction;
+
+/// the scan for the select relational algebra operator on an indexed field
+/// it reads only the records whose indexed field lies between lo and hi (inclusive),
+/// which is a single value when they are equal
+pub struct IndexSelectScan {
+    ts: TableScan,
+    idx: IndexType,
+    lo: Option<Constant>,
+    hi: Option<Constant>,
+}
+
+impl IndexSelectScan {
+    pub fn new(
+        ts: TableScan,
+        idx: IndexType,
+        lo: Option<Constant>,
+        hi: Option<Constant>,
+        tx: &mut Transaction,
+    ) -> DbResult<Self> {
+        let mut s = Self { ts, idx, lo, hi };
+        s.before_first(tx)?;
+        return Ok(s);
+    }
+}
+
+impl Scan for IndexSelectScan {
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        match (&self.lo, &self.hi) {
+            (Some(lo), Some(hi)) if lo == hi => self.idx.before_first(lo.clone(), tx),
+            _ => self.idx.before_range(self.lo.clone(), self.hi.clone(), tx),
+        }
+    }
+
+    /// move to the next index record, then to the data record it points to
+    /// the records the transaction does not see are skipped
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
+        while self.idx.next(tx)? {
+            let rid = self.idx.get_data_rid(tx)?;
+            if self.ts.move_to_rid(tx, rid)? {
+                return Ok(true);
+            }
+        }
+        return Ok(false);
+    }
+
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32> {
+        self.ts.get_int(tx, &fldname)
+    }
+
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String> {
+        self.ts.get_string(tx, &fldname)
+    }
+
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant> {
+        self.ts.get_value(tx, &fldname)
+    }
+
+    fn has_field(&self, fldname: String) -> bool {
+        self.ts.has_field(&fldname)
+    }
+
+    fn close(&mut self, tx: &mut Transaction) {
+        self.idx.close(tx);
+        self.ts.close(tx);
+    }
+}
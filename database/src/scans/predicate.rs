@@ -1,23 +1,54 @@
 use std::fmt::Display;
 
+use crate::common::Constant;
+use crate::error::DbResult;
+use crate::planning::plan::PlanType;
+use crate::record_management::schema::Schema;
 use crate::scans::common::ScanType;
-use crate::scans::term::Term;
+use crate::scans::term::{Term, Truth};
 use crate::transaction_manager::transaction::Transaction;
 
-#[derive(Clone)]
+/// a factor of the conjunction that makes up a predicate
+#[derive(Clone, Debug)]
+enum Factor {
+    Term(Term),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+}
+
+impl Display for Factor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Factor::Term(t) => write!(f, "{}", t),
+            Factor::Or(p1, p2) => write!(f, "({} or {})", p1, p2),
+            Factor::Not(p) => match p.factors.as_slice() {
+                [Factor::Term(t)] => write!(f, "not {}", t),
+                _ => write!(f, "not ({})", p),
+            },
+        }
+    }
+}
+
+/// a boolean combination of terms
+/// the predicate is the conjunction of its factors; an empty predicate is always true
+#[derive(Clone, Debug)]
 pub struct Predicate {
-    terms: Vec<Term>,
+    factors: Vec<Factor>,
 }
 
 impl Display for Predicate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // a single disjunction needs no parentheses
+        if let [Factor::Or(p1, p2)] = self.factors.as_slice() {
+            return write!(f, "{} or {}", p1, p2);
+        }
         let mut s = String::new();
-        // append all terms with and
-        for (i, t) in self.terms.iter().enumerate() {
-            if i != 0 && i != self.terms.len() - 1 {
+        // append all factors with and
+        for (i, factor) in self.factors.iter().enumerate() {
+            if i != 0 {
                 s.push_str(" and ");
             }
-            s.push_str(&format!("{}", t));
+            s.push_str(&format!("{}", factor));
         }
         write!(f, "{}", s)
     }
@@ -25,23 +56,146 @@ impl Display for Predicate {
 
 impl Predicate {
     pub fn new() -> Self {
-        return Self { terms: Vec::new() };
+        return Self {
+            factors: Vec::new(),
+        };
     }
 
     pub fn add_term(&mut self, t: Term) {
-        self.terms.push(t);
+        self.factors.push(Factor::Term(t));
     }
 
     pub fn conjoin_with(&mut self, p: Predicate) {
-        self.terms.extend(p.terms);
+        self.factors.extend(p.factors);
     }
 
-    pub fn is_satisfied(&self, s: &mut ScanType, tx: &mut Transaction) -> bool {
-        for t in self.terms.iter() {
-            if !t.is_satisfied(s, tx) {
-                return false;
+    /// the predicate p1 OR p2
+    pub fn disjoin(p1: Predicate, p2: Predicate) -> Predicate {
+        return Self {
+            factors: vec![Factor::Or(Box::new(p1), Box::new(p2))],
+        };
+    }
+
+    /// the predicate NOT p
+    pub fn negate(p: Predicate) -> Predicate {
+        return Self {
+            factors: vec![Factor::Not(Box::new(p))],
+        };
+    }
+
+    /// a record satisfies the predicate only if the predicate is true, not unknown
+    pub fn is_satisfied(&self, s: &mut ScanType, tx: &mut Transaction) -> DbResult<bool> {
+        return Ok(self.truth(s, tx)? == Truth::True);
+    }
+
+    pub fn truth(&self, s: &mut ScanType, tx: &mut Transaction) -> DbResult<Truth> {
+        let mut result = Truth::True;
+        for factor in self.factors.iter() {
+            let t = match factor {
+                Factor::Term(t) => t.truth(s, tx)?,
+                Factor::Or(p1, p2) => p1.truth(s, tx)?.or(p2.truth(s, tx)?),
+                Factor::Not(p) => !p.truth(s, tx)?,
+            };
+            result = result.and(t);
+            if result == Truth::False {
+                break;
+            }
+        }
+        return Ok(result);
+    }
+
+    /// check the types of every term against the schema
+    pub fn check_types(&self, sch: &Schema) -> DbResult<()> {
+        for factor in self.factors.iter() {
+            match factor {
+                Factor::Term(t) => t.check_types(sch)?,
+                Factor::Or(p1, p2) => {
+                    p1.check_types(sch)?;
+                    p2.check_types(sch)?;
+                }
+                Factor::Not(p) => p.check_types(sch)?,
             }
         }
-        return true;
+        Ok(())
+    }
+
+    /// product of the reduction factors of all factors
+    pub fn reduction_factor(&self, p: &PlanType) -> i32 {
+        let mut factor: i32 = 1;
+        for f in self.factors.iter() {
+            let rf = match f {
+                Factor::Term(t) => t.reduction_factor(p),
+                Factor::Or(p1, p2) => {
+                    // the fraction of records kept by either side
+                    let s1 = 1.0 / p1.reduction_factor(p) as f64;
+                    let s2 = 1.0 / p2.reduction_factor(p) as f64;
+                    Self::factor_of(s1 + s2 - s1 * s2)
+                }
+                Factor::Not(p1) => Self::factor_of(1.0 - 1.0 / p1.reduction_factor(p) as f64),
+            };
+            factor = factor.saturating_mul(rf);
+        }
+        factor
+    }
+
+    /// if some term is of the form "fldname = c", return c
+    /// only the terms of the conjunction itself are considered
+    pub fn equates_with_constant(&self, fldname: String) -> Option<Constant> {
+        for t in self.terms() {
+            if let Some(c) = t.equates_with_constant(fldname.clone()) {
+                return Some(c);
+            }
+        }
+        None
+    }
+
+    /// the range of values of fldname that the comparisons with constants let through,
+    /// as an inclusive lowest and highest value; none if no term compares fldname with a constant
+    /// only the terms of the conjunction itself are considered
+    pub fn range_of(&self, fldname: &str) -> Option<(Option<Constant>, Option<Constant>)> {
+        let mut range = None;
+        for t in self.terms() {
+            if let Some((lo, hi)) = t.range_of(fldname) {
+                let (cur_lo, cur_hi) = range.unwrap_or((None, None));
+                // the narrower bound on each side wins
+                range = Some((cur_lo.max(lo), Self::min_bound(cur_hi, hi)));
+            }
+        }
+        range
+    }
+
+    /// if some term is of the form "fldname = f2", return f2
+    /// only the terms of the conjunction itself are considered
+    pub fn equates_with_field(&self, fldname: String) -> Option<String> {
+        for t in self.terms() {
+            if let Some(s) = t.equates_with_field(fldname.clone()) {
+                return Some(s);
+            }
+        }
+        None
+    }
+
+    fn terms(&self) -> impl Iterator<Item = &Term> {
+        self.factors.iter().filter_map(|f| match f {
+            Factor::Term(t) => Some(t),
+            _ => None,
+        })
+    }
+
+    /// the smaller of two upper bounds, where none is unbounded
+    fn min_bound(b1: Option<Constant>, b2: Option<Constant>) -> Option<Constant> {
+        match (b1, b2) {
+            (Some(b1), Some(b2)) => Some(b1.min(b2)),
+            (b1, None) => b1,
+            (None, b2) => b2,
+        }
+    }
+
+    /// convert the fraction of kept records to a reduction factor
+    fn factor_of(selectivity: f64) -> i32 {
+        if selectivity <= 1.0 / i32::MAX as f64 {
+            return i32::MAX;
+        }
+        return (1.0 / selectivity).round().max(1.0) as i32;
     }
 }
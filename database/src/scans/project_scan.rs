@@ -1,51 +1,51 @@
-use std::collections::HashSet;
-
 use crate::common::Constant;
+use crate::error::{DbError, DbResult};
 use crate::scans::common::Scan;
 use crate::scans::common::ScanType;
 use crate::transaction_manager::transaction::Transaction;
 
+/// field_list keeps the order in which the fields were requested
 pub struct ProjectScan {
     s: Box<ScanType>,
-    field_list: HashSet<String>,
+    field_list: Vec<String>,
 }
 
 impl ProjectScan {
-    pub fn new(s: Box<ScanType>, field_list: HashSet<String>) -> Self {
+    pub fn new(s: Box<ScanType>, field_list: Vec<String>) -> Self {
         return Self { s, field_list };
     }
 }
 
 impl Scan for ProjectScan {
-    fn before_first(&mut self, tx: &mut Transaction) {
-        self.s.before_first(tx);
+    fn before_first(&mut self, tx: &mut Transaction) -> DbResult<()> {
+        self.s.before_first(tx)
     }
 
-    fn next(&mut self, tx: &mut Transaction) -> bool {
+    fn next(&mut self, tx: &mut Transaction) -> DbResult<bool> {
         self.s.next(tx)
     }
 
-    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> i32 {
+    fn get_int(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<i32> {
         if self.has_field(fldname.clone()) {
             self.s.get_int(fldname, tx)
         } else {
-            panic!("field {} not found.", fldname);
+            Err(DbError::Catalog(format!("field {} not found", fldname)))
         }
     }
 
-    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> String {
+    fn get_string(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<String> {
         if self.has_field(fldname.clone()) {
             self.s.get_string(fldname, tx)
         } else {
-            panic!("field {} not found.", fldname);
+            Err(DbError::Catalog(format!("field {} not found", fldname)))
         }
     }
 
-    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> Constant {
+    fn get_val(&mut self, fldname: String, tx: &mut Transaction) -> DbResult<Constant> {
         if self.has_field(fldname.clone()) {
             self.s.get_val(fldname, tx)
         } else {
-            panic!("field {} not found.", fldname);
+            Err(DbError::Catalog(format!("field {} not found", fldname)))
         }
     }
 
@@ -61,6 +61,7 @@ impl Scan for ProjectScan {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata_management::metadata_mgr::MetadataMgr;
     use crate::record_management::schema::Schema;
     use crate::record_management::table_scan::TableScan;
     use crate::scans::predicate::Predicate;
@@ -87,39 +88,39 @@ mod tests {
     #[test]
     fn test_project_scan() -> Result<()> {
         setup();
-        let db = SimpleDB::new("projectscantest".to_string(), 400, 10);
+        let db = SimpleDB::new("./db/logtest", "projectscantest", 400, 10)?;
         let mut tx = db.new_tx();
-        let mut mdm = db.new_metadata_mgr(&mut tx);
+        let mut mdm = MetadataMgr::new(true, &mut tx)?;
 
         // create table
         let mut sch = Schema::new();
         sch.add_int_field("sid".to_string());
         sch.add_string_field("sname".to_string(), 10);
         sch.add_int_field("majorId".to_string());
-        mdm.create_table("student".to_string(), sch, &mut tx);
+        mdm.create_table("student".to_string(), sch, &mut tx)?;
 
         // insert data
-        let layout = mdm.get_layout("student".to_string(), &mut tx);
-        let mut ts = TableScan::new(&mut tx, "student".to_string(), layout.clone());
-        ts.insert(&mut tx);
-        ts.set_int(&mut tx, &"sid".to_string(), 1);
-        ts.set_string(&mut tx, &"sname".to_string(), "joe".to_string());
-        ts.set_int(&mut tx, &"majorId".to_string(), 10);
-        ts.insert(&mut tx);
-        ts.set_int(&mut tx, &"sid".to_string(), 2);
-        ts.set_string(&mut tx, &"sname".to_string(), "Aoi".to_string());
-        ts.set_int(&mut tx, &"majorId".to_string(), 20);
-        ts.insert(&mut tx);
-        ts.set_int(&mut tx, &"sid".to_string(), 3);
-        ts.set_string(&mut tx, &"sname".to_string(), "Bob".to_string());
-        ts.set_int(&mut tx, &"majorId".to_string(), 10);
+        let layout = mdm.get_layout("student".to_string(), &mut tx)?;
+        let mut ts = TableScan::new(&mut tx, "student".to_string(), layout.clone())?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"sid".to_string(), 1)?;
+        ts.set_string(&mut tx, &"sname".to_string(), "joe".to_string())?;
+        ts.set_int(&mut tx, &"majorId".to_string(), 10)?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"sid".to_string(), 2)?;
+        ts.set_string(&mut tx, &"sname".to_string(), "Aoi".to_string())?;
+        ts.set_int(&mut tx, &"majorId".to_string(), 20)?;
+        ts.insert(&mut tx)?;
+        ts.set_int(&mut tx, &"sid".to_string(), 3)?;
+        ts.set_string(&mut tx, &"sname".to_string(), "Bob".to_string())?;
+        ts.set_int(&mut tx, &"majorId".to_string(), 10)?;
         ts.close(&mut tx);
 
         // Q = project (select (STUDENT, MajorId = 10), {sname})
 
         // STUDENT node
-        let layout = mdm.get_layout("student".to_string(), &mut tx);
-        let ts = TableScan::new(&mut tx, "student".to_string(), layout.clone());
+        let layout = mdm.get_layout("student".to_string(), &mut tx)?;
+        let ts = TableScan::new(&mut tx, "student".to_string(), layout.clone())?;
 
         // SELECT node
         let mut pred = Predicate::new();
@@ -131,23 +132,22 @@ mod tests {
         let ss = SelectScan::new(Box::new(ScanType::TableScan(ts)), pred);
 
         // PROJECT node
-        let mut field_list = HashSet::new();
-        field_list.insert("sname".to_string());
+        let field_list = vec!["sname".to_string()];
         let mut ps = ProjectScan::new(Box::new(ScanType::SelectScan(ss)), field_list);
 
-        ps.next(&mut tx);
+        ps.next(&mut tx)?;
         assert_eq!(
-            ps.get_string("sname".to_string(), &mut tx),
+            ps.get_string("sname".to_string(), &mut tx)?,
             "joe".to_string()
         );
-        ps.next(&mut tx);
+        ps.next(&mut tx)?;
         assert_eq!(
-            ps.get_string("sname".to_string(), &mut tx),
+            ps.get_string("sname".to_string(), &mut tx)?,
             "Bob".to_string()
         );
 
         ps.close(&mut tx);
-        tx.commit();
+        tx.commit()?;
 
         teardown();
         Ok(())
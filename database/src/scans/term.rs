@@ -1,44 +1,196 @@
 use std::fmt::Display;
 
 use crate::{
-    common::Constant, planning::plan::Plan, record_management::schema::Schema,
-    scans::expression::Expression, transaction_manager::transaction::Transaction,
+    common::Constant,
+    error::{DbError, DbResult},
+    planning::plan::{Plan, PlanType},
+    record_management::schema::Schema,
+    scans::expression::Expression,
+    transaction_manager::transaction::Transaction,
 };
 
 use super::common::ScanType;
 
-#[derive(Clone)]
+/// the result of a condition under SQL three-valued logic
+/// a comparison with null is unknown, and a record is selected only if its condition is true
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+impl Truth {
+    pub fn and(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::False, _) | (_, Truth::False) => Truth::False,
+            (Truth::True, Truth::True) => Truth::True,
+            _ => Truth::Unknown,
+        }
+    }
+
+    pub fn or(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::True, _) | (_, Truth::True) => Truth::True,
+            (Truth::False, Truth::False) => Truth::False,
+            _ => Truth::Unknown,
+        }
+    }
+}
+
+impl std::ops::Not for Truth {
+    type Output = Truth;
+
+    fn not(self) -> Truth {
+        match self {
+            Truth::True => Truth::False,
+            Truth::False => Truth::True,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+}
+
+impl From<bool> for Truth {
+    fn from(b: bool) -> Self {
+        if b {
+            Truth::True
+        } else {
+            Truth::False
+        }
+    }
+}
+
+/// the comparison operator of a term
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompOp {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+}
+
+impl CompOp {
+    pub fn to_str(&self) -> &str {
+        match self {
+            CompOp::Equal => "=",
+            CompOp::NotEqual => "!=",
+            CompOp::Less => "<",
+            CompOp::Greater => ">",
+            CompOp::LessEqual => "<=",
+            CompOp::GreaterEqual => ">=",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Term {
     lhs: Expression,
     rhs: Expression,
+    op: CompOp,
 }
 
 impl Display for Term {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} = {}", self.lhs, self.rhs)
+        write!(f, "{} {} {}", self.lhs, self.op.to_str(), self.rhs)
     }
 }
 
 impl Term {
+    /// the term lhs = rhs
     pub fn new(lhs: Expression, rhs: Expression) -> Self {
-        return Self { lhs, rhs };
+        return Self::new_with_op(lhs, rhs, CompOp::Equal);
     }
 
-    pub fn is_satisfied(&self, s: &mut ScanType, tx: &mut Transaction) -> bool {
-        let lhs_val = self.lhs.evaluate(s, tx);
-        let rhs_val = self.rhs.evaluate(s, tx);
-        return lhs_val == rhs_val;
+    pub fn new_with_op(lhs: Expression, rhs: Expression, op: CompOp) -> Self {
+        return Self { lhs, rhs, op };
+    }
+
+    pub fn is_satisfied(&self, s: &mut ScanType, tx: &mut Transaction) -> DbResult<bool> {
+        return Ok(self.truth(s, tx)? == Truth::True);
+    }
+
+    /// compare the two sides
+    /// the result is unknown if a side is null or the sides are not of the same type
+    pub fn truth(&self, s: &mut ScanType, tx: &mut Transaction) -> DbResult<Truth> {
+        let lhs_val = self.lhs.evaluate(s, tx)?;
+        let rhs_val = self.rhs.evaluate(s, tx)?;
+        let ord = match (&lhs_val, &rhs_val) {
+            (Constant::Int(l), Constant::Int(r)) => l.cmp(r),
+            (Constant::String(l), Constant::String(r)) => l.cmp(r),
+            _ => return Ok(Truth::Unknown),
+        };
+        let b = match self.op {
+            CompOp::Equal => ord.is_eq(),
+            CompOp::NotEqual => ord.is_ne(),
+            CompOp::Less => ord.is_lt(),
+            CompOp::Greater => ord.is_gt(),
+            CompOp::LessEqual => ord.is_le(),
+            CompOp::GreaterEqual => ord.is_ge(),
+        };
+        return Ok(Truth::from(b));
+    }
+
+    pub fn op(&self) -> CompOp {
+        self.op
     }
 
     pub fn applies_to(&self, sch: &Schema) -> bool {
         return self.lhs.applies_to(sch) && self.rhs.applies_to(sch);
     }
 
-    pub fn reduction_factor(&self, _p: Plan) {
-        todo!("Term.reduction_factor()")
+    /// both sides must have the same type, unless one of them is null
+    pub fn check_types(&self, sch: &Schema) -> DbResult<()> {
+        match (self.lhs.type_of(sch)?, self.rhs.type_of(sch)?) {
+            (Some(l), Some(r)) if l != r => Err(DbError::Type(format!(
+                "cannot compare {} with {} in {}",
+                l.to_str(),
+                r.to_str(),
+                self
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// estimate how much the term reduces the number of records output by the plan
+    /// field = field: the larger of the two distinct value counts
+    /// field = constant: the distinct value count of the field
+    /// constant = constant: 1 if they are equal, otherwise no record survives
+    /// a computed expression is treated like a range comparison
+    /// a range comparison keeps about a third of the records, and != keeps almost all of them
+    pub fn reduction_factor(&self, p: &PlanType) -> i32 {
+        match self.op {
+            CompOp::Equal => {}
+            CompOp::NotEqual => return 1,
+            _ => return 3,
+        }
+        if self.lhs.is_field_name() && self.rhs.is_field_name() {
+            let lhs_name = self.lhs.as_field_name().unwrap();
+            let rhs_name = self.rhs.as_field_name().unwrap();
+            return std::cmp::max(p.distinct_values(&lhs_name), p.distinct_values(&rhs_name));
+        }
+        if self.lhs.is_field_name() {
+            return p.distinct_values(&self.lhs.as_field_name().unwrap());
+        }
+        if self.rhs.is_field_name() {
+            return p.distinct_values(&self.rhs.as_field_name().unwrap());
+        }
+        match (self.lhs.as_constant(), self.rhs.as_constant()) {
+            (Some(l), Some(r)) if l == r => 1,
+            (Some(_), Some(_)) => i32::MAX,
+            _ => 3,
+        }
     }
 
+    /// if the term is of the form "fldname = c" with a non-null c, return c
     pub fn equates_with_constant(&self, fldname: String) -> Option<Constant> {
+        if self.op != CompOp::Equal
+            || self.lhs.as_constant() == Some(Constant::Null)
+            || self.rhs.as_constant() == Some(Constant::Null)
+        {
+            return None;
+        }
         if self.lhs.is_field_name()
             && self.lhs.as_field_name().unwrap() == fldname
             && !self.rhs.is_field_name()
@@ -54,15 +206,50 @@ impl Term {
         }
     }
 
+    /// if the term compares fldname with a non-null constant by =, <, <=, > or >=,
+    /// return the lowest and the highest value it lets through, inclusive
+    /// a strict comparison gives the constant itself as the bound,
+    /// so the term must still be checked on the records in the range
+    pub fn range_of(&self, fldname: &str) -> Option<(Option<Constant>, Option<Constant>)> {
+        let (val, op) = if self.lhs.as_field_name().as_deref() == Some(fldname) {
+            (self.rhs.as_constant()?, self.op)
+        } else if self.rhs.as_field_name().as_deref() == Some(fldname) {
+            // c < fldname is fldname > c
+            let op = match self.op {
+                CompOp::Less => CompOp::Greater,
+                CompOp::Greater => CompOp::Less,
+                CompOp::LessEqual => CompOp::GreaterEqual,
+                CompOp::GreaterEqual => CompOp::LessEqual,
+                op => op,
+            };
+            (self.lhs.as_constant()?, op)
+        } else {
+            return None;
+        };
+        if val == Constant::Null {
+            return None;
+        }
+        match op {
+            CompOp::Equal => Some((Some(val.clone()), Some(val))),
+            CompOp::Less | CompOp::LessEqual => Some((None, Some(val))),
+            CompOp::Greater | CompOp::GreaterEqual => Some((Some(val), None)),
+            CompOp::NotEqual => None,
+        }
+    }
+
+    /// if the term is of the form "fldname = f2", return f2
     pub fn equates_with_field(&self, fldname: String) -> Option<String> {
+        if self.op != CompOp::Equal {
+            return None;
+        }
         if self.lhs.is_field_name()
             && self.lhs.as_field_name().unwrap() == fldname
-            && !self.rhs.is_field_name()
+            && self.rhs.is_field_name()
         {
             return self.rhs.as_field_name();
         } else if self.rhs.is_field_name()
             && self.rhs.as_field_name().unwrap() == fldname
-            && !self.lhs.is_field_name()
+            && self.lhs.is_field_name()
         {
             return self.lhs.as_field_name();
         } else {
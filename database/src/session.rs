@@ -0,0 +1,503 @@
+pub mod result_set;
+
+use crate::error::{DbError, DbResult};
+use crate::parser::parser::{Object, Parser, QueryData};
+use crate::planning::plan::Plan;
+use crate::planning::planner::Planner;
+use crate::scans::common::Scan;
+use crate::transaction_manager::concurrency_manager::IsolationLevel;
+use crate::transaction_manager::transaction::Transaction;
+use crate::SimpleDB;
+
+use result_set::{QueryResult, ResultSet};
+
+/// the savepoint each statement of a transaction block runs after
+/// the name of a savepoint set in SQL cannot contain a space, so it cannot be taken
+const STATEMENT_SAVEPOINT: &str = "statement savepoint";
+
+/// runs the SQL statements of one client
+/// BEGIN opens a transaction that the following statements share until COMMIT or ROLLBACK,
+/// otherwise each statement runs in its own transaction (autocommit)
+pub struct Session<'a> {
+    db: &'a SimpleDB,
+    planner: Planner,
+    /// the transaction opened by BEGIN
+    tx: Option<Transaction>,
+    /// the isolation level set outside a transaction, for the next one
+    isolation: Option<IsolationLevel>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(db: &'a SimpleDB) -> DbResult<Self> {
+        return Ok(Self {
+            db,
+            planner: db.new_planner()?,
+            tx: None,
+            isolation: None,
+        });
+    }
+
+    /// whether a transaction opened by BEGIN is running
+    pub fn in_transaction(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// run the SQL statement
+    /// @return: the records of a select statement, or else the number of affected records
+    pub fn execute(&mut self, sql: String) -> DbResult<QueryResult> {
+        let obj = Parser::new(sql).sql()?;
+        return self.execute_statement(obj);
+    }
+
+    /// run the semicolon separated statements of the script in order,
+    /// stopping at the first one that fails
+    /// nothing runs unless the whole script can be parsed
+    /// @return: the result of each statement
+    pub fn execute_script(&mut self, script: String) -> DbResult<Vec<QueryResult>> {
+        let objects = Parser::new(script).statements()?;
+        let mut results = Vec::new();
+        for obj in objects {
+            results.push(self.execute_statement(obj)?);
+        }
+        return Ok(results);
+    }
+
+    /// run a statement the caller has parsed, e.g. to time each statement of a script
+    pub fn execute_statement(&mut self, obj: Object) -> DbResult<QueryResult> {
+        match obj {
+            Object::Query(data) => return Ok(QueryResult::Rows(self.query(data)?)),
+            obj => return Ok(QueryResult::Affected(self.update(obj)?)),
+        }
+    }
+
+    /// run the select statement and read all its records
+    pub fn execute_query(&mut self, qry: String) -> DbResult<ResultSet> {
+        let data = Parser::new(qry).query()?;
+        return self.query(data);
+    }
+
+    /// run a statement other than a select
+    /// @return: the number of affected records
+    pub fn execute_update(&mut self, cmd: String) -> DbResult<i32> {
+        let obj = Parser::new(cmd).sql()?;
+        return self.update(obj);
+    }
+
+    /// the number of blocks and the number of records the planner assumes for the table
+    pub fn table_stats(&mut self, tblname: &str) -> DbResult<(i32, i32)> {
+        let qry = format!("select * from {}", tblname);
+        return self.run(|planner, tx| {
+            let p = planner.create_query_plan(qry, tx)?;
+            Ok((p.blocks_accessed(), p.records_output()))
+        });
+    }
+
+    /// the scan is closed before returning, so that an autocommit transaction can commit
+    fn query(&mut self, data: QueryData) -> DbResult<ResultSet> {
+        return self.run(|planner, tx| {
+            let p = planner.create_plan(data, tx)?;
+            let schema = p.schema();
+            let fields = schema.get_fields();
+            let mut rows = Vec::new();
+            let mut s = p.open(tx)?;
+            while s.next(tx)? {
+                let row = fields
+                    .iter()
+                    .map(|fldname| s.get_val(fldname.clone(), tx))
+                    .collect::<DbResult<Vec<_>>>()?;
+                rows.push(row);
+            }
+            s.close(tx);
+            Ok(ResultSet::new(&schema, rows))
+        });
+    }
+
+    /// a failed statement rolls back its own transaction, or its changes to the one opened by BEGIN
+    fn update(&mut self, obj: Object) -> DbResult<i32> {
+        match obj {
+            Object::Begin => {
+                if self.tx.is_some() {
+                    return Err(DbError::Transaction(
+                        "a transaction is already in progress".to_string(),
+                    ));
+                }
+                self.tx = Some(self.new_tx());
+                return Ok(0);
+            }
+            // without a transaction to end, COMMIT and ROLLBACK do nothing
+            Object::Commit => {
+                if let Some(mut tx) = self.tx.take() {
+                    tx.commit()?;
+                }
+                return Ok(0);
+            }
+            Object::Rollback => {
+                if let Some(mut tx) = self.tx.take() {
+                    tx.roll_back()?;
+                }
+                return Ok(0);
+            }
+            // the savepoints of the transaction must not be mixed with the one of the statement,
+            // and a transaction of its own would end with the statement
+            obj @ (Object::Savepoint(_)
+            | Object::RollbackToSavepoint(_)
+            | Object::ReleaseSavepoint(_)
+            | Object::SetIsolation(_)) => match self.tx.as_mut() {
+                Some(tx) => return self.planner.execute(obj, tx),
+                None => match obj {
+                    Object::SetIsolation(level) => {
+                        self.isolation = Some(level);
+                        return Ok(0);
+                    }
+                    _ => {
+                        return Err(DbError::Transaction(
+                            "savepoints need a transaction started by BEGIN".to_string(),
+                        ))
+                    }
+                },
+            },
+            obj => return self.run(|planner, tx| planner.execute(obj, tx)),
+        }
+    }
+
+    /// start a transaction at the isolation level set before it, if any
+    fn new_tx(&mut self) -> Transaction {
+        let mut tx = self.db.new_tx();
+        if let Some(level) = self.isolation.take() {
+            tx.set_isolation_level(level);
+        }
+        return tx;
+    }
+
+    /// call f in the open transaction, or else in a new one that is committed if f succeeds
+    /// in the open transaction, f runs after a savepoint, so that the changes of a failed f are undone
+    /// an open transaction that is aborted by a deadlock or a lack of buffers is rolled back
+    fn run<T>(
+        &mut self,
+        f: impl FnOnce(&mut Planner, &mut Transaction) -> DbResult<T>,
+    ) -> DbResult<T> {
+        if let Some(tx) = self.tx.as_mut() {
+            tx.savepoint(STATEMENT_SAVEPOINT.to_string());
+            let result = f(&mut self.planner, tx);
+            match &result {
+                Err(e) if e.is_abort() => {
+                    if let Some(mut tx) = self.tx.take() {
+                        // the abort is reported rather than a failure to roll back
+                        let _ = tx.roll_back();
+                    }
+                }
+                Err(_) => {
+                    if let Err(e) = tx.rollback_to_savepoint(STATEMENT_SAVEPOINT) {
+                        if let Some(mut tx) = self.tx.take() {
+                            let _ = tx.roll_back();
+                        }
+                        return Err(e);
+                    }
+                    tx.release_savepoint(STATEMENT_SAVEPOINT);
+                }
+                Ok(_) => {
+                    tx.release_savepoint(STATEMENT_SAVEPOINT);
+                }
+            }
+            return result;
+        }
+        let mut tx = self.new_tx();
+        let result = f(&mut self.planner, &mut tx);
+        match result {
+            Ok(_) => tx.commit()?,
+            // the error of f is reported rather than a failure to roll back
+            Err(_) => {
+                let _ = tx.roll_back();
+            }
+        }
+        return result;
+    }
+}
+
+/// a transaction left open when the session ends is rolled back
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        if let Some(mut tx) = self.tx.take() {
+            let _ = tx.roll_back();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Constant;
+    use crate::record_management::schema::Type;
+    use crate::transaction_manager::concurrency_manager::lock_table::DeadlockPolicy;
+    use anyhow::Result;
+    use std::fs;
+    use std::time::Duration;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_session() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("sessiontest")
+            .open()?;
+        let mut session = Session::new(&db)?;
+
+        // autocommit
+        session.execute_update("CREATE TABLE T (a INT, b VARCHAR(10))".to_string())?;
+        session.execute_update("INSERT INTO T (a, b) VALUES (1, 'one')".to_string())?;
+        let rs = session.execute_query("SELECT a, b FROM T".to_string())?;
+        assert_eq!(rs.column_names(), vec!["a", "b"]);
+        assert_eq!(rs.columns()[0].type_(), Type::INTEGER);
+        assert_eq!(rs.columns()[1].type_(), Type::VARCHAR);
+        assert_eq!(rs.columns()[1].length(), 10);
+        assert_eq!(rs.column_index("b"), Some(1));
+        let rows: Vec<Vec<Constant>> = rs.into_iter().collect();
+        assert_eq!(
+            rows,
+            vec![vec![Constant::Int(1), Constant::String("one".to_string())]]
+        );
+        match session.execute("select a from T where a = 1".to_string())? {
+            QueryResult::Rows(rs) => assert_eq!(rs.len(), 1),
+            QueryResult::Affected(_) => panic!("expected rows"),
+        }
+        match session.execute("update T set a = 1 where a = 1".to_string())? {
+            QueryResult::Affected(n) => assert_eq!(n, 1),
+            QueryResult::Rows(_) => panic!("expected an affected count"),
+        }
+
+        // a rolled back transaction
+        session.execute_update("BEGIN".to_string())?;
+        assert!(session.in_transaction());
+        assert!(matches!(
+            session.execute_update("BEGIN TRANSACTION".to_string()),
+            Err(DbError::Transaction(_))
+        ));
+        session.execute_update("INSERT INTO T (a, b) VALUES (2, 'two')".to_string())?;
+        assert_eq!(
+            session.execute_query("SELECT a FROM T".to_string())?.len(),
+            2
+        );
+        session.execute_update("ROLLBACK".to_string())?;
+        assert!(!session.in_transaction());
+        assert_eq!(
+            session.execute_query("SELECT a FROM T".to_string())?.len(),
+            1
+        );
+
+        // a committed transaction, which keeps running after a failed statement
+        session.execute_update("begin work".to_string())?;
+        session.execute_update("INSERT INTO T (a, b) VALUES (3, 'three')".to_string())?;
+        assert!(session
+            .execute_query("SELECT c FROM T".to_string())
+            .is_err());
+        session.execute_update("UPDATE T SET a = 4 WHERE a = 3".to_string())?;
+        session.execute_update("COMMIT".to_string())?;
+        let rs = session.execute_query("SELECT a FROM T WHERE b = 'three'".to_string())?;
+        assert_eq!(rs.rows().next(), Some(&vec![Constant::Int(4)]));
+
+        // a statement that fails half way is undone on its own
+        session.execute_update("BEGIN".to_string())?;
+        session.execute_update("INSERT INTO T (a, b) VALUES (5, 'five')".to_string())?;
+        assert!(matches!(
+            session.execute_update("UPDATE T SET a = 10 / (a - 4)".to_string()),
+            Err(DbError::Type(_))
+        ));
+        assert!(session.in_transaction());
+        let rs = session.execute_query("SELECT a FROM T".to_string())?;
+        let mut vals: Vec<Constant> = rs.into_iter().map(|row| row[0].clone()).collect();
+        vals.sort();
+        assert_eq!(
+            vals,
+            vec![Constant::Int(1), Constant::Int(4), Constant::Int(5)]
+        );
+        session.execute_update("ROLLBACK".to_string())?;
+
+        // the autocommit transactions release their locks, so another session can write
+        let mut other = Session::new(&db)?;
+        other.execute_update("DELETE FROM T WHERE a = 1".to_string())?;
+        assert_eq!(
+            session.execute_query("SELECT a FROM T".to_string())?.len(),
+            1
+        );
+
+        // the transaction left open is rolled back
+        session.execute_update("BEGIN".to_string())?;
+        session.execute_update("DELETE FROM T WHERE a = 4".to_string())?;
+        drop(session);
+        assert_eq!(other.execute_query("SELECT a FROM T".to_string())?.len(), 1);
+
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_errors() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("sessionerrortest")
+            .open()?;
+        db.set_lock_policy(DeadlockPolicy::Timeout, Duration::from_millis(100));
+        let mut session = Session::new(&db)?;
+        session.execute_update("CREATE TABLE T (a INT, b VARCHAR(10))".to_string())?;
+        session.execute_update("INSERT INTO T (a, b) VALUES (1, 'one')".to_string())?;
+        session.execute_update("CREATE TABLE U (c INT)".to_string())?;
+
+        assert!(matches!(
+            session.execute("SELECT a FROM T WHERE".to_string()),
+            Err(DbError::Parse(_))
+        ));
+        assert!(matches!(
+            session.execute("CREATE TABLE U (a FLOAT)".to_string()),
+            Err(DbError::Parse(_))
+        ));
+        assert!(matches!(
+            session.execute("INSERT INTO T (a, b) VALUES ('x', 'y')".to_string()),
+            Err(DbError::Type(_))
+        ));
+        assert!(matches!(
+            session.execute("SELECT a FROM nosuchtable".to_string()),
+            Err(DbError::Catalog(_))
+        ));
+
+        // outside a transaction, savepoints are rejected and the isolation level waits for BEGIN
+        for cmd in ["SAVEPOINT a", "ROLLBACK TO SAVEPOINT a", "RELEASE a"] {
+            assert!(matches!(
+                session.execute_update(cmd.to_string()),
+                Err(DbError::Transaction(_))
+            ));
+        }
+        session.execute_update("SET TRANSACTION ISOLATION LEVEL READ COMMITTED".to_string())?;
+        session.execute_update("BEGIN".to_string())?;
+        assert_eq!(
+            session.tx.as_ref().map(|tx| tx.isolation_level()),
+            Some(IsolationLevel::ReadCommitted)
+        );
+        session.execute_update("COMMIT".to_string())?;
+        session.execute_update("BEGIN".to_string())?;
+        assert_ne!(
+            session.tx.as_ref().map(|tx| tx.isolation_level()),
+            Some(IsolationLevel::ReadCommitted)
+        );
+        session.execute_update("COMMIT".to_string())?;
+
+        // a transaction that times out waiting for a lock is rolled back
+        let mut other = Session::new(&db)?;
+        session.execute_update("BEGIN".to_string())?;
+        session.execute_update("UPDATE T SET a = 2 WHERE a = 1".to_string())?;
+        other.execute_update("BEGIN".to_string())?;
+        other.execute_update("INSERT INTO U (c) VALUES (3)".to_string())?;
+        match other.execute_update("UPDATE T SET a = 4 WHERE a = 2".to_string()) {
+            Err(e) => assert!(e.is_abort()),
+            Ok(_) => panic!("expected the update to be aborted"),
+        }
+        assert!(!other.in_transaction());
+        session.execute_update("COMMIT".to_string())?;
+        let rs = other.execute_query("SELECT a FROM T".to_string())?;
+        let rows: Vec<Vec<Constant>> = rs.into_iter().collect();
+        assert_eq!(rows, vec![vec![Constant::Int(2)]]);
+        assert_eq!(other.execute_query("SELECT c FROM U".to_string())?.len(), 0);
+
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_script() -> Result<()> {
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("scripttest")
+            .open()?;
+        let results = db.execute_script(
+            "-- schema
+            CREATE TABLE T (a INT, b VARCHAR(10));
+            /* two records,
+               one statement each */
+            INSERT INTO T (a, b) VALUES (1, 'one');
+            INSERT INTO T (a, b)
+                VALUES (2, 'two');
+            SELECT a FROM T WHERE a > 1;",
+        )?;
+        assert_eq!(results.len(), 4);
+        match &results[3] {
+            QueryResult::Rows(rs) => assert_eq!(rs.len(), 1),
+            QueryResult::Affected(_) => panic!("expected rows"),
+        }
+
+        // a script that does not parse runs nothing
+        assert!(matches!(
+            db.execute_script("INSERT INTO T (a, b) VALUES (3, 'three'); SELEC a FROM T"),
+            Err(DbError::Parse(_))
+        ));
+        // a failed statement stops the script, the transaction it is in is rolled back
+        assert!(db
+            .execute_script(
+                "BEGIN; INSERT INTO T (a, b) VALUES (3, 'three'); SELECT c FROM T; COMMIT;"
+            )
+            .is_err());
+        let mut session = Session::new(&db)?;
+        assert_eq!(
+            session.execute_query("SELECT a FROM T".to_string())?.len(),
+            2
+        );
+
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen() -> Result<()> {
+        setup();
+        {
+            let db = SimpleDB::builder()
+                .dir("./db/reopentest")
+                .log_file("reopentest")
+                .open()?;
+            db.execute_script(
+                "CREATE TABLE T (a INT, b VARCHAR(9));
+                INSERT INTO T (a, b) VALUES (1, 'one');",
+            )?;
+
+            // an insert that is on disk but never commits, as if the process died
+            let mut planner = db.new_planner()?;
+            let mut tx = db.new_tx();
+            planner.execute_update(
+                "INSERT INTO T (a, b) VALUES (2, 'two')".to_string(),
+                &mut tx,
+            )?;
+            db.checkpoint()?;
+        }
+
+        // the catalog is read back, not recreated, and recovery undoes the insert
+        let db = SimpleDB::builder()
+            .dir("./db/reopentest")
+            .log_file("reopentest")
+            .open()?;
+        let mut session = Session::new(&db)?;
+        let rs = session.execute_query("SELECT a, b FROM T".to_string())?;
+        assert_eq!(rs.column_names(), vec!["a", "b"]);
+        let rows: Vec<&Vec<Constant>> = rs.rows().collect();
+        assert_eq!(
+            rows,
+            vec![&vec![Constant::Int(1), Constant::String("one".to_string())]]
+        );
+
+        teardown();
+        Ok(())
+    }
+}
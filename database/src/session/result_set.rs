@@ -0,0 +1,259 @@
+use std::fmt::Display;
+
+use crate::common::Constant;
+use crate::record_management::schema::{Schema, Type};
+
+/// the name and the type of a column of a result set
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    name: String,
+    type_: Type,
+    length: i32,
+}
+
+impl Column {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn type_(&self) -> Type {
+        self.type_
+    }
+
+    /// the maximum number of characters of a varchar column, 0 for an int column
+    pub fn length(&self) -> i32 {
+        self.length
+    }
+}
+
+/// the records read by a select statement, in the order of the scan
+/// each row holds one value per column
+#[derive(Debug)]
+pub struct ResultSet {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Constant>>,
+}
+
+impl ResultSet {
+    /// @param schema: the schema of the plan the rows were read from
+    pub fn new(schema: &Schema, rows: Vec<Vec<Constant>>) -> Self {
+        let columns = schema
+            .field_types()
+            .into_iter()
+            .map(|(name, type_, length)| Column {
+                name,
+                type_,
+                length,
+            })
+            .collect();
+        Self { columns, rows }
+    }
+
+    pub fn columns(&self) -> &Vec<Column> {
+        &self.columns
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// the position of the column in each row
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn rows(&self) -> std::slice::Iter<'_, Vec<Constant>> {
+        self.rows.iter()
+    }
+}
+
+impl IntoIterator for ResultSet {
+    type Item = Vec<Constant>;
+    type IntoIter = std::vec::IntoIter<Vec<Constant>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+/// an aligned table with a header, followed by the number of rows
+/// int columns are aligned to the right, varchar columns to the left
+impl Display for ResultSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|val| val.to_string()).collect())
+            .collect();
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([c.name.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!(" {:<w$} ", c.name, w = w))
+            .collect();
+        writeln!(f, "{}", header.join("|").trim_end())?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(w + 2)).collect();
+        writeln!(f, "{}", rule.join("+"))?;
+        for row in cells.iter() {
+            let line: Vec<String> = row
+                .iter()
+                .zip(self.columns.iter().zip(&widths))
+                .map(|(val, (c, w))| match c.type_ {
+                    Type::INTEGER => format!(" {:>w$} ", val, w = w),
+                    Type::VARCHAR => format!(" {:<w$} ", val, w = w),
+                })
+                .collect();
+            writeln!(f, "{}", line.join("|").trim_end())?;
+        }
+        match self.rows.len() {
+            1 => write!(f, "(1 row)"),
+            n => write!(f, "({} rows)", n),
+        }
+    }
+}
+
+/// the outcome of a SQL statement
+#[derive(Debug)]
+pub enum QueryResult {
+    /// the records read by a select statement
+    Rows(ResultSet),
+    /// the number of records changed by any other statement
+    Affected(i32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+    use crate::SimpleDB;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    fn teardown() {
+        let db_directory = "./db".to_string();
+        if fs::metadata(db_directory.clone()).is_ok() {
+            fs::remove_dir_all(db_directory.clone()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_result_set() -> Result<()> {
+        let mut schema = Schema::new();
+        schema.add_string_field("name".to_string(), 8);
+        schema.add_int_field("id".to_string());
+        let rs = ResultSet::new(
+            &schema,
+            vec![
+                vec![Constant::String("ann".to_string()), Constant::Int(2)],
+                vec![Constant::String("bob".to_string()), Constant::Int(1)],
+            ],
+        );
+        assert_eq!(rs.column_names(), vec!["name", "id"]);
+        assert_eq!(rs.columns()[0].name(), "name");
+        assert_eq!(rs.columns()[0].type_(), Type::VARCHAR);
+        assert_eq!(rs.columns()[0].length(), 8);
+        assert_eq!(rs.columns()[1].type_(), Type::INTEGER);
+        assert_eq!(rs.columns()[1].length(), 0);
+        assert_eq!(rs.column_index("id"), Some(1));
+        assert_eq!(rs.column_index("age"), None);
+        assert_eq!((rs.len(), rs.is_empty()), (2, false));
+
+        // the rows keep the order in which they were read
+        let ids: Vec<Constant> = rs.rows().map(|row| row[1].clone()).collect();
+        assert_eq!(ids, vec![Constant::Int(2), Constant::Int(1)]);
+        let rows: Vec<Vec<Constant>> = rs.into_iter().collect();
+        assert_eq!(rows[1][0], Constant::String("bob".to_string()));
+
+        // the statements of a session return their results instead of printing them
+        setup();
+        let db = SimpleDB::builder()
+            .dir("./db/logtest")
+            .log_file("resultsettest")
+            .open()?;
+        let mut session = Session::new(&db)?;
+        let results = session.execute_script(
+            "create table T (a int, b varchar(4));
+            insert into T (a, b) values (1, 'x');
+            insert into T (a, b) values (2, 'y');
+            select b, a * 10 as c from T where a = 2;
+            select * from T where a = 3;
+            delete from T"
+                .to_string(),
+        )?;
+        assert_eq!(results.len(), 6);
+        assert!(matches!(results[0], QueryResult::Affected(0)));
+        assert!(matches!(results[1], QueryResult::Affected(1)));
+        match &results[3] {
+            QueryResult::Rows(rs) => {
+                assert_eq!(rs.column_names(), vec!["b", "c"]);
+                assert_eq!(rs.columns()[1].type_(), Type::INTEGER);
+                assert_eq!(
+                    rs.rows().collect::<Vec<_>>(),
+                    vec![&vec![Constant::String("y".to_string()), Constant::Int(20)]]
+                );
+            }
+            QueryResult::Affected(_) => panic!("expected rows"),
+        }
+        // an empty result still describes its columns
+        match &results[4] {
+            QueryResult::Rows(rs) => {
+                assert!(rs.is_empty());
+                assert_eq!(rs.column_names(), vec!["a", "b"]);
+                assert_eq!(rs.columns()[1].length(), 4);
+            }
+            QueryResult::Affected(_) => panic!("expected rows"),
+        }
+        assert!(matches!(results[5], QueryResult::Affected(2)));
+
+        teardown();
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        let mut schema = Schema::new();
+        schema.add_int_field("id".to_string());
+        schema.add_string_field("name".to_string(), 10);
+        let rs = ResultSet::new(
+            &schema,
+            vec![
+                vec![Constant::Int(7), Constant::String("ann".to_string())],
+                vec![Constant::Int(1234), Constant::Null],
+            ],
+        );
+        assert_eq!(
+            rs.to_string(),
+            " id   | name\n------+------\n    7 | ann\n 1234 | null\n(2 rows)"
+        );
+        let rs = ResultSet::new(&schema, Vec::new());
+        assert_eq!(rs.to_string(), " id | name\n----+------\n(0 rows)");
+    }
+}
@@ -1,3 +1,5 @@
+pub mod checkpointer;
 pub mod concurrency_manager;
 pub mod recovery_manager;
 pub mod transaction;
+pub mod version_manager;
@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::buffer_manager::buffer_mgr::BufferMgr;
+use crate::log_manager::log_mgr::LogMgr;
+use crate::transaction_manager::recovery_manager::RecoveryMgr;
+use crate::transaction_manager::version_manager::VersionMgr;
+
+/// a background thread writing non-quiescent checkpoints,
+/// so that recovery reads a bounded part of the log however long the database runs
+/// the thread wakes up every period and checkpoints once the log has grown by log_blocks blocks
+/// a checkpoint that fails is written again a period later
+/// it stops when the checkpointer is dropped
+pub struct Checkpointer {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Checkpointer {
+    pub fn start(
+        lm: Arc<Mutex<LogMgr>>,
+        bm: Arc<Mutex<BufferMgr>>,
+        versions: Arc<VersionMgr>,
+        log_blocks: i32,
+        period: Duration,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let mut checkpointed_at = lm.lock().unwrap().blocks_written();
+        let handle = std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                std::thread::park_timeout(period);
+                let size = lm.lock().unwrap().blocks_written();
+                if size - checkpointed_at >= log_blocks
+                    && RecoveryMgr::checkpoint(&lm, &bm, &versions).is_ok()
+                {
+                    checkpointed_at = size;
+                }
+            }
+        });
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// wait for the thread to finish the checkpoint it may be writing
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stop.store(true, Ordering::SeqCst);
+            handle.thread().unpark();
+            handle.join().unwrap();
+        }
+    }
+}
+
+impl Drop for Checkpointer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_manager::block_id::BlockId;
+    use crate::file_manager::file_mgr::FileMgr;
+    use crate::file_manager::page::Page;
+    use crate::transaction_manager::concurrency_manager::lock_table::LockTable;
+    use crate::transaction_manager::recovery_manager::log_record::{LogRecord, LogRecordType};
+    use crate::transaction_manager::transaction::Transaction;
+    use crate::transaction_manager::version_manager::TxMode;
+    use anyhow::Result;
+    use std::fs;
+
+    fn setup(db_directory: &str) {
+        if fs::metadata(db_directory).is_ok() {
+            fs::remove_dir_all(db_directory).unwrap();
+        }
+    }
+
+    fn teardown(db_directory: &str) {
+        if fs::metadata(db_directory).is_ok() {
+            fs::remove_dir_all(db_directory).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_checkpointer() -> Result<()> {
+        let db_directory = "./db/checkpointertest";
+        setup(db_directory);
+        let fm = FileMgr::new(db_directory.to_string(), 400);
+        let lm = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testlog".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), lm.clone(), 8)));
+        let versions = Arc::new(VersionMgr::new());
+        let mut checkpointer = Checkpointer::start(
+            lm.clone(),
+            bm.clone(),
+            versions.clone(),
+            2,
+            Duration::from_millis(5),
+        );
+
+        // a running transaction keeps writing log records
+        let mut tx = Transaction::new(
+            fm.clone(),
+            lm.clone(),
+            bm.clone(),
+            Arc::new(LockTable::new()),
+            versions.clone(),
+            TxMode::Locking,
+        );
+        let blk = tx.append("testfile".to_string())?;
+        tx.pin(blk.clone())?;
+        for i in 1..=100 {
+            tx.set_int(blk.clone(), 0, i, true)?;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        checkpointer.stop();
+
+        // the checkpoint lists the running transaction and flushed its page,
+        // though maybe before its last changes
+        let mut found = false;
+        let mut iter = lm.lock().unwrap().iterator()?;
+        while let Some(bytes) = iter.next() {
+            if let LogRecordType::NQCKPT(c) = LogRecord::create_log_record(bytes?)? {
+                assert_eq!(c.tx_numbers(), &vec![tx.tx_num()]);
+                found = true;
+                break;
+            }
+        }
+        assert!(found);
+        let mut p = Page::new(fm.block_size());
+        let mut fm = fm;
+        fm.read(&BlockId::new("testfile".to_string(), 0), &mut p)
+            .unwrap();
+        assert!(p.get_int(0).unwrap() > 0);
+        tx.commit()?;
+
+        teardown(db_directory);
+        Ok(())
+    }
+}
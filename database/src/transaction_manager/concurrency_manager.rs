@@ -1,2 +1,79 @@
 pub mod concurrency_mgr;
 pub mod lock_table;
+
+use std::fmt::Display;
+
+use crate::file_manager::block_id::BlockId;
+
+/// the transaction could not get a lock and has to be rolled back
+#[derive(Debug)]
+pub enum LockAbortError {
+    /// the transaction waited too long for a lock on the block
+    Timeout(BlockId),
+    /// the transaction was aborted to break or prevent a deadlock
+    /// while it requested a lock on the block
+    Deadlock(BlockId),
+    /// the snapshot transaction tried to write a record of the block
+    /// whose newer version another transaction committed first
+    WriteConflict(BlockId),
+}
+
+impl Display for LockAbortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockAbortError::Timeout(blk) => write!(f, "lock wait on block {} timed out", blk),
+            LockAbortError::Deadlock(blk) => {
+                write!(f, "deadlock victim while locking block {}", blk)
+            }
+            LockAbortError::WriteConflict(blk) => write!(
+                f,
+                "write conflict on block {}: a concurrent transaction committed first",
+                blk
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LockAbortError {}
+
+/// how much a locking transaction is isolated from the changes of the other transactions
+/// a higher level holds more slocks for longer, so it makes writers wait more
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IsolationLevel {
+    /// reads take no slocks, so they may see uncommitted changes
+    ReadUncommitted,
+    /// each read takes an slock and releases it right away, so it sees only committed changes,
+    /// but reading a block again may see a different value
+    ReadCommitted,
+    /// slocks are held until the end of the transaction,
+    /// but records appended to a file it has read may appear later (phantoms)
+    RepeatableRead,
+    /// the end-of-file marker of each file whose size is read is locked as well,
+    /// so no record can be appended to it until the end of the transaction
+    Serializable,
+}
+
+impl IsolationLevel {
+    /// parse the name used in SET TRANSACTION ISOLATION LEVEL
+    pub fn from_name(name: &str) -> Option<Self> {
+        let words: Vec<String> = name.split_whitespace().map(|w| w.to_lowercase()).collect();
+        match words.join(" ").as_str() {
+            "read uncommitted" => Some(IsolationLevel::ReadUncommitted),
+            "read committed" => Some(IsolationLevel::ReadCommitted),
+            "repeatable read" => Some(IsolationLevel::RepeatableRead),
+            "serializable" => Some(IsolationLevel::Serializable),
+            _ => None,
+        }
+    }
+}
+
+impl Display for IsolationLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IsolationLevel::ReadUncommitted => write!(f, "read uncommitted"),
+            IsolationLevel::ReadCommitted => write!(f, "read committed"),
+            IsolationLevel::RepeatableRead => write!(f, "repeatable read"),
+            IsolationLevel::Serializable => write!(f, "serializable"),
+        }
+    }
+}
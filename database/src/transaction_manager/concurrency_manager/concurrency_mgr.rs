@@ -1,42 +1,82 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::file_manager::block_id::BlockId;
 use crate::transaction_manager::concurrency_manager::lock_table::LockTable;
+use crate::transaction_manager::concurrency_manager::{IsolationLevel, LockAbortError};
 
+/// the locks held by one transaction
+/// the lock table is shared with the other transactions of the database
+/// the isolation level decides which slocks are taken and how long they are held,
+/// xlocks are always held until the end of the transaction
 pub struct ConcurrencyMgr {
-    locktbl: LockTable,
+    locktbl: Arc<LockTable>,
+    txnum: i32,
     locks: HashMap<BlockId, String>,
+    level: IsolationLevel,
 }
 
 impl ConcurrencyMgr {
-    pub fn new() -> Self {
+    pub fn new(locktbl: Arc<LockTable>, txnum: i32) -> Self {
         Self {
-            locktbl: LockTable::new(),
+            locktbl,
+            txnum,
             locks: HashMap::new(),
+            level: IsolationLevel::Serializable,
         }
     }
 
-    pub fn slock(&mut self, blk: BlockId) {
+    pub fn isolation_level(&self) -> IsolationLevel {
+        self.level
+    }
+
+    /// the locks already held are kept
+    pub fn set_isolation_level(&mut self, level: IsolationLevel) {
+        self.level = level;
+    }
+
+    /// a read uncommitted transaction takes no slock
+    pub fn slock(&mut self, blk: BlockId) -> Result<(), LockAbortError> {
+        if self.level == IsolationLevel::ReadUncommitted {
+            return Ok(());
+        }
         if let None = self.locks.get(&blk) {
-            self.locktbl.slock(blk.clone());
+            self.locktbl.slock(blk.clone(), self.txnum)?;
             self.locks.insert(blk.clone(), "S".to_string());
         }
+        Ok(())
     }
 
-    pub fn xlock(&mut self, blk: BlockId) {
+    /// an xlock is obtained by upgrading an slock
+    pub fn xlock(&mut self, blk: BlockId) -> Result<(), LockAbortError> {
         if !self.has_xlock(&blk) {
-            self.slock(blk.clone());
-            self.locktbl.xlock(blk.clone());
+            self.slock(blk.clone())?;
+            self.locktbl.xlock(blk.clone(), self.txnum)?;
             self.locks.insert(blk.clone(), "X".to_string());
         }
+        Ok(())
     }
 
-    pub fn release(&mut self) {
-        for (blk, _) in self.locks.iter() {
-            self.locktbl.unlock(blk.clone());
+    /// a read committed transaction gives up the slock once it has read the block
+    /// an xlock is kept
+    pub fn end_read(&mut self, blk: BlockId) {
+        if self.level == IsolationLevel::ReadCommitted
+            && self.locks.get(&blk).is_some_and(|l| l == "S")
+        {
+            self.locks.remove(&blk);
+            self.locktbl.release(self.txnum, vec![blk]);
         }
+    }
+
+    /// how long the transaction waits for a lock before it is aborted
+    pub fn max_time(&self) -> Duration {
+        self.locktbl.max_time()
+    }
 
-        self.locks.clear();
+    pub fn release(&mut self) {
+        let blks = self.locks.drain().map(|(blk, _)| blk).collect();
+        self.locktbl.release(self.txnum, blks);
     }
 
     fn has_xlock(&self, blk: &BlockId) -> bool {
@@ -50,10 +90,34 @@ impl ConcurrencyMgr {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use anyhow::Result;
+    use std::time::Duration;
 
     #[test]
     fn test_concurrency_mgr() -> Result<()> {
+        let locktbl = Arc::new(LockTable::with_max_time(Duration::from_millis(100)));
+        let mut cm1 = ConcurrencyMgr::new(locktbl.clone(), 1);
+        let mut cm2 = ConcurrencyMgr::new(locktbl.clone(), 2);
+        let blk1 = BlockId::new("testfile".to_string(), 1);
+        let blk2 = BlockId::new("testfile".to_string(), 2);
+
+        // both transactions can read the same block
+        cm1.slock(blk1.clone())?;
+        cm2.slock(blk1.clone())?;
+        // but neither can write it while the other reads it
+        assert!(cm1.xlock(blk1.clone()).is_err());
+
+        cm2.xlock(blk2.clone())?;
+        // locks already held are not requested again
+        cm2.slock(blk2.clone())?;
+        cm2.xlock(blk2.clone())?;
+        assert!(cm1.slock(blk2.clone()).is_err());
+
+        cm2.release();
+        cm1.xlock(blk1.clone())?;
+        cm1.slock(blk2.clone())?;
+        cm1.release();
         Ok(())
     }
 }
@@ -1,124 +1,452 @@
-use std::collections::HashMap;
-use std::thread;
-use std::time;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use crate::file_manager::block_id::BlockId;
+use crate::transaction_manager::concurrency_manager::LockAbortError;
 
 use self::constants::MAX_TIME;
 
 mod constants {
-    pub const MAX_TIME: i64 = 10000;
+    pub const MAX_TIME: u64 = 10000;
 }
 
-pub struct LockTable {
-    locks: HashMap<BlockId, i32>,
+/// how the lock table deals with transactions that wait for each other
+/// a smaller txnum means an older transaction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeadlockPolicy {
+    /// wait until the maximum time has passed
+    Timeout,
+    /// look for a cycle in the waits-for graph whenever a transaction has to wait,
+    /// and abort the youngest transaction of the cycle
+    Detect,
+    /// an older transaction waits for a younger one, a younger one is aborted instead of waiting
+    WaitDie,
+    /// an older transaction aborts the younger ones it waits for, a younger one waits
+    /// a wounded transaction finds out at its next lock request
+    WoundWait,
 }
 
-impl LockTable {
-    pub fn new() -> Self {
-        Self {
-            locks: HashMap::new(),
-        }
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+/// the transactions holding locks on a block
+/// the transaction holding the xlock also holds an slock
+#[derive(Default)]
+struct Holders {
+    slocks: HashSet<i32>,
+    xlock: Option<i32>,
+}
+
+struct LockState {
+    locks: HashMap<BlockId, Holders>,
+    /// the lock each waiting transaction is waiting for
+    waiting: HashMap<i32, (BlockId, LockMode)>,
+    /// transactions chosen to be aborted, which have not found out yet
+    aborted: HashSet<i32>,
+    policy: DeadlockPolicy,
+    max_time: Duration,
+}
+
+impl LockState {
+    /// the other transactions whose locks prevent the request
+    fn blockers(&self, blk: &BlockId, txnum: i32, mode: LockMode) -> Vec<i32> {
+        let holders = match self.locks.get(blk) {
+            Some(holders) => holders,
+            None => return Vec::new(),
+        };
+        let mut blockers: Vec<i32> = match mode {
+            LockMode::Shared => Vec::new(),
+            LockMode::Exclusive => holders.slocks.iter().copied().collect(),
+        };
+        blockers.extend(holders.xlock);
+        blockers.retain(|t| *t != txnum);
+        blockers.sort();
+        blockers.dedup();
+        return blockers;
     }
 
-    // get slock
-    pub fn slock(&mut self, blk: BlockId) {
-        let timestamp = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-        while self.has_xlock(&blk) && !Self::wait_too_long(timestamp) {
-            thread::sleep(time::Duration::from_millis(10));
+    fn grant(&mut self, blk: BlockId, txnum: i32, mode: LockMode) {
+        let holders = self.locks.entry(blk).or_default();
+        holders.slocks.insert(txnum);
+        if mode == LockMode::Exclusive {
+            holders.xlock = Some(txnum);
         }
-        if self.has_xlock(&blk) {
-            panic!("block {} is locked", blk);
+    }
+
+    /// the transactions the waiting transaction waits for, leaving out aborted ones
+    /// since they are about to release their locks
+    fn waits_for(&self, txnum: i32) -> Vec<i32> {
+        match self.waiting.get(&txnum) {
+            Some((blk, mode)) => self
+                .blockers(blk, txnum, *mode)
+                .into_iter()
+                .filter(|t| !self.aborted.contains(t))
+                .collect(),
+            None => Vec::new(),
         }
-        let val = self.get_lock_val(&blk);
-        self.locks.insert(blk, val + 1);
     }
 
-    /// get xlock
-    pub fn xlock(&mut self, blk: BlockId) {
-        let timestamp = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-        while self.has_other_slocks(&blk) && !Self::wait_too_long(timestamp) {
-            thread::sleep(time::Duration::from_millis(10));
+    /// if the waits-for graph has a cycle through the transaction, return its youngest member
+    /// the graph had no cycle before the transaction started to wait,
+    /// so any new cycle goes through it
+    fn deadlock_victim(&self, txnum: i32) -> Option<i32> {
+        let mut path = vec![txnum];
+        let mut visited = HashSet::new();
+        if self.find_cycle(txnum, &mut path, &mut visited) {
+            return path.into_iter().max();
         }
-        if self.has_other_slocks(&blk) {
-            panic!("block {} is locked", blk);
+        return None;
+    }
+
+    /// depth-first search for a path from the last transaction of the path back to its first one
+    fn find_cycle(&self, current: i32, path: &mut Vec<i32>, visited: &mut HashSet<i32>) -> bool {
+        for next in self.waits_for(current) {
+            if next == path[0] {
+                return true;
+            }
+            if visited.insert(next) {
+                path.push(next);
+                if self.find_cycle(next, path, visited) {
+                    return true;
+                }
+                path.pop();
+            }
         }
-        self.locks.insert(blk, -1);
+        return false;
     }
+}
 
-    pub fn unlock(&mut self, blk: BlockId) {
-        let val = self.get_lock_val(&blk);
-        if val > 1 {
-            self.locks.insert(blk, val - 1);
-        } else {
-            self.locks.remove(&blk);
+/// the lock table shared by all transactions of a database
+/// a transaction that cannot get a lock waits until a lock is released;
+/// depending on the deadlock policy it may be aborted with an error instead,
+/// and it always gives up once it has waited longer than the maximum time
+pub struct LockTable {
+    state: Mutex<LockState>,
+    released: Condvar,
+}
+
+impl LockTable {
+    /// a lock table that detects deadlocks
+    pub fn new() -> Self {
+        Self::with_policy(DeadlockPolicy::Detect, Duration::from_millis(MAX_TIME))
+    }
+
+    /// @param max_time: how long a transaction waits for a lock before it is aborted
+    pub fn with_max_time(max_time: Duration) -> Self {
+        Self::with_policy(DeadlockPolicy::Detect, max_time)
+    }
+
+    pub fn with_policy(policy: DeadlockPolicy, max_time: Duration) -> Self {
+        Self {
+            state: Mutex::new(LockState {
+                locks: HashMap::new(),
+                waiting: HashMap::new(),
+                aborted: HashSet::new(),
+                policy,
+                max_time,
+            }),
+            released: Condvar::new(),
         }
     }
 
-    /// if the block is slocked by other transactions, return true
-    fn has_other_slocks(&self, blk: &BlockId) -> bool {
-        self.get_lock_val(blk) > 1
+    /// change the deadlock policy and the maximum waiting time for the following requests
+    pub fn set_policy(&self, policy: DeadlockPolicy, max_time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.policy = policy;
+        state.max_time = max_time;
     }
 
-    pub(crate) fn has_xlock(&self, blk: &BlockId) -> bool {
-        return self.get_lock_val(blk) < 0;
+    /// how long a transaction waits for a lock before it is aborted
+    pub fn max_time(&self) -> Duration {
+        self.state.lock().unwrap().max_time
     }
 
-    fn wait_too_long(starttime: i64) -> bool {
-        let now = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-        now - starttime > MAX_TIME
+    /// get slock
+    /// wait while another transaction has an xlock on the block
+    pub fn slock(&self, blk: BlockId, txnum: i32) -> Result<(), LockAbortError> {
+        self.acquire(blk, txnum, LockMode::Shared)
+    }
+
+    /// get xlock
+    /// the transaction must already have an slock on the block,
+    /// so wait while any other transaction has a lock on it
+    pub fn xlock(&self, blk: BlockId, txnum: i32) -> Result<(), LockAbortError> {
+        self.acquire(blk, txnum, LockMode::Exclusive)
+    }
+
+    /// release the locks of the transaction on the blocks and wake up the waiting transactions
+    pub fn release(&self, txnum: i32, blks: Vec<BlockId>) {
+        let mut state = self.state.lock().unwrap();
+        for blk in blks {
+            if let Some(holders) = state.locks.get_mut(&blk) {
+                holders.slocks.remove(&txnum);
+                if holders.xlock == Some(txnum) {
+                    holders.xlock = None;
+                }
+                if holders.slocks.is_empty() && holders.xlock.is_none() {
+                    state.locks.remove(&blk);
+                }
+            }
+        }
+        state.aborted.remove(&txnum);
+        state.waiting.remove(&txnum);
+        self.released.notify_all();
     }
 
-    fn get_lock_val(&self, blk: &BlockId) -> i32 {
-        if let Some(i_val) = self.locks.get(&blk) {
-            *i_val
-        } else {
-            0
+    fn acquire(&self, blk: BlockId, txnum: i32, mode: LockMode) -> Result<(), LockAbortError> {
+        let mut state = self.state.lock().unwrap();
+        let deadline = Instant::now() + state.max_time;
+        loop {
+            if state.aborted.remove(&txnum) {
+                state.waiting.remove(&txnum);
+                return Err(LockAbortError::Deadlock(blk));
+            }
+            let blockers = state.blockers(&blk, txnum, mode);
+            if blockers.is_empty() {
+                state.waiting.remove(&txnum);
+                state.grant(blk, txnum, mode);
+                return Ok(());
+            }
+
+            state.waiting.insert(txnum, (blk.clone(), mode));
+            match state.policy {
+                DeadlockPolicy::Timeout => {}
+                DeadlockPolicy::Detect => {
+                    if let Some(victim) = state.deadlock_victim(txnum) {
+                        if victim == txnum {
+                            state.waiting.remove(&txnum);
+                            return Err(LockAbortError::Deadlock(blk));
+                        }
+                        // the victim is waiting too, so wake it up
+                        state.aborted.insert(victim);
+                        self.released.notify_all();
+                    }
+                }
+                DeadlockPolicy::WaitDie => {
+                    if blockers.iter().any(|t| *t < txnum) {
+                        state.waiting.remove(&txnum);
+                        return Err(LockAbortError::Deadlock(blk));
+                    }
+                }
+                DeadlockPolicy::WoundWait => {
+                    for t in blockers.into_iter().filter(|t| *t > txnum) {
+                        state.aborted.insert(t);
+                    }
+                    self.released.notify_all();
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                state.waiting.remove(&txnum);
+                return Err(LockAbortError::Timeout(blk));
+            }
+            state = self.wait(state, deadline - now);
         }
     }
+
+    fn wait<'a>(
+        &self,
+        state: MutexGuard<'a, LockState>,
+        timeout: Duration,
+    ) -> MutexGuard<'a, LockState> {
+        self.released.wait_timeout(state, timeout).unwrap().0
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::Result;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn holders(lock_table: &LockTable, blk: &BlockId) -> (Vec<i32>, Option<i32>) {
+        let state = lock_table.state.lock().unwrap();
+        match state.locks.get(blk) {
+            Some(h) => {
+                let mut slocks: Vec<i32> = h.slocks.iter().copied().collect();
+                slocks.sort();
+                (slocks, h.xlock)
+            }
+            None => (Vec::new(), None),
+        }
+    }
+
+    fn is_waiting(lock_table: &LockTable, txnum: i32) -> bool {
+        lock_table
+            .state
+            .lock()
+            .unwrap()
+            .waiting
+            .contains_key(&txnum)
+    }
+
+    /// run the lock request in another thread and return once it is waiting
+    fn request_in_thread(
+        lock_table: &Arc<LockTable>,
+        blk: &BlockId,
+        txnum: i32,
+    ) -> thread::JoinHandle<Result<(), LockAbortError>> {
+        let handle = {
+            let lock_table = lock_table.clone();
+            let blk = blk.clone();
+            thread::spawn(move || lock_table.slock(blk, txnum))
+        };
+        while !is_waiting(lock_table, txnum) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        handle
+    }
 
     #[test]
     fn test_lock_table() -> Result<()> {
-        let mut lock_table = LockTable::new();
+        let lock_table =
+            LockTable::with_policy(DeadlockPolicy::Timeout, Duration::from_millis(100));
         let blk = BlockId::new("testfile".to_string(), 1);
 
         // Test slock
         {
-            lock_table.slock(blk.clone());
-            assert_eq!(lock_table.get_lock_val(&blk), 1);
-            lock_table.slock(blk.clone());
-            assert_eq!(lock_table.get_lock_val(&blk), 2);
+            lock_table.slock(blk.clone(), 1)?;
+            lock_table.slock(blk.clone(), 2)?;
+            assert_eq!(holders(&lock_table, &blk), (vec![1, 2], None));
+            // another slock prevents the xlock
+            assert!(matches!(
+                lock_table.xlock(blk.clone(), 1),
+                Err(LockAbortError::Timeout(_))
+            ));
         }
 
-        // Test unlock
+        // Test release
         {
-            lock_table.unlock(blk.clone());
-            assert_eq!(lock_table.get_lock_val(&blk), 1);
-            lock_table.unlock(blk.clone());
-            assert_eq!(lock_table.get_lock_val(&blk), 0);
+            lock_table.release(2, vec![blk.clone()]);
+            assert_eq!(holders(&lock_table, &blk), (vec![1], None));
         }
 
         // Test xlock
         {
-            lock_table.xlock(blk.clone());
-            assert_eq!(lock_table.get_lock_val(&blk), -1);
+            lock_table.xlock(blk.clone(), 1)?;
+            assert_eq!(holders(&lock_table, &blk), (vec![1], Some(1)));
+            assert!(matches!(
+                lock_table.slock(blk.clone(), 2),
+                Err(LockAbortError::Timeout(_))
+            ));
+            lock_table.release(1, vec![blk.clone()]);
+            assert_eq!(holders(&lock_table, &blk), (vec![], None));
         }
 
         Ok(())
     }
+
+    #[test]
+    fn test_lock_table_wakes_up_waiter() -> Result<()> {
+        let lock_table = Arc::new(LockTable::new());
+        let blk = BlockId::new("testfile".to_string(), 1);
+        lock_table.slock(blk.clone(), 1)?;
+        lock_table.xlock(blk.clone(), 1)?;
+
+        let start = Instant::now();
+        let waiter = request_in_thread(&lock_table, &blk, 2);
+        lock_table.release(1, vec![blk.clone()]);
+
+        // the waiter gets the lock as soon as it is released, long before the timeout
+        waiter.join().unwrap()?;
+        assert!(start.elapsed() < Duration::from_millis(MAX_TIME / 2));
+        assert_eq!(holders(&lock_table, &blk), (vec![2], None));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadlock_detection() -> Result<()> {
+        let lock_table = Arc::new(LockTable::new());
+        let blk1 = BlockId::new("testfile".to_string(), 1);
+        let blk2 = BlockId::new("testfile".to_string(), 2);
+        let start = Instant::now();
+
+        // the youngest transaction closes the cycle and is the victim itself
+        lock_table.slock(blk1.clone(), 1)?;
+        lock_table.xlock(blk1.clone(), 1)?;
+        lock_table.slock(blk2.clone(), 2)?;
+        lock_table.xlock(blk2.clone(), 2)?;
+        let waiter = request_in_thread(&lock_table, &blk2, 1);
+        assert!(matches!(
+            lock_table.slock(blk1.clone(), 2),
+            Err(LockAbortError::Deadlock(_))
+        ));
+        lock_table.release(2, vec![blk2.clone()]);
+        waiter.join().unwrap()?;
+        lock_table.release(1, vec![blk1.clone(), blk2.clone()]);
+
+        // the oldest transaction closes the cycle, so the waiting younger one is the victim
+        lock_table.slock(blk1.clone(), 3)?;
+        lock_table.xlock(blk1.clone(), 3)?;
+        lock_table.slock(blk2.clone(), 4)?;
+        lock_table.xlock(blk2.clone(), 4)?;
+        let victim = request_in_thread(&lock_table, &blk1, 4);
+        let survivor = request_in_thread(&lock_table, &blk2, 3);
+        assert!(matches!(
+            victim.join().unwrap(),
+            Err(LockAbortError::Deadlock(_))
+        ));
+        lock_table.release(4, vec![blk2.clone()]);
+        survivor.join().unwrap()?;
+        assert_eq!(holders(&lock_table, &blk2), (vec![3], None));
+        lock_table.release(3, vec![blk1.clone(), blk2.clone()]);
+
+        // no deadlock waited for the timeout
+        assert!(start.elapsed() < Duration::from_millis(MAX_TIME / 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_die_and_wound_wait() -> Result<()> {
+        let blk = BlockId::new("testfile".to_string(), 1);
+        let max_time = Duration::from_millis(50);
+
+        // wait-die: the younger transaction dies at once, the older one waits
+        let lock_table = LockTable::with_policy(DeadlockPolicy::WaitDie, max_time);
+        lock_table.slock(blk.clone(), 1)?;
+        lock_table.xlock(blk.clone(), 1)?;
+        assert!(matches!(
+            lock_table.slock(blk.clone(), 2),
+            Err(LockAbortError::Deadlock(_))
+        ));
+        lock_table.release(1, vec![blk.clone()]);
+        lock_table.slock(blk.clone(), 2)?;
+        lock_table.xlock(blk.clone(), 2)?;
+        assert!(matches!(
+            lock_table.slock(blk.clone(), 1),
+            Err(LockAbortError::Timeout(_))
+        ));
+        lock_table.release(2, vec![blk.clone()]);
+
+        // wound-wait: the older transaction wounds the younger one, the younger one waits
+        let lock_table = LockTable::with_policy(DeadlockPolicy::WoundWait, max_time);
+        lock_table.slock(blk.clone(), 1)?;
+        lock_table.xlock(blk.clone(), 1)?;
+        assert!(matches!(
+            lock_table.slock(blk.clone(), 2),
+            Err(LockAbortError::Timeout(_))
+        ));
+        lock_table.release(1, vec![blk.clone()]);
+        lock_table.slock(blk.clone(), 2)?;
+        lock_table.xlock(blk.clone(), 2)?;
+        assert!(matches!(
+            lock_table.slock(blk.clone(), 1),
+            Err(LockAbortError::Timeout(_))
+        ));
+        // the wounded transaction is aborted at its next request
+        let blk2 = BlockId::new("testfile".to_string(), 2);
+        assert!(matches!(
+            lock_table.slock(blk2, 2),
+            Err(LockAbortError::Deadlock(_))
+        ));
+        lock_table.release(2, vec![blk.clone()]);
+        lock_table.slock(blk.clone(), 1)?;
+        Ok(())
+    }
 }
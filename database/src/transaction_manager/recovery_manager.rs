@@ -2,7 +2,9 @@ pub mod check_point_record;
 pub mod commit_record;
 pub mod log_record;
 pub mod log_record_item;
+pub mod nq_check_point_record;
 pub mod roll_back_record;
+pub mod savepoint_record;
 pub mod set_int_record;
 pub mod set_string_record;
 pub mod start_record;
@@ -13,10 +15,17 @@ use std::sync::Mutex;
 
 use crate::buffer_manager::buffer::Buffer;
 use crate::buffer_manager::buffer_mgr::BufferMgr;
-use crate::log_manager::log_mgr::LogMgr;
+use crate::error::{DbError, DbResult};
+use crate::file_manager::block_id::BlockId;
+use crate::log_manager::log_mgr::{LogMgr, LogRetention};
+use crate::transaction_manager::transaction::Transaction;
+use crate::transaction_manager::version_manager::VersionMgr;
 
+use check_point_record::CheckPointRecord;
 use commit_record::CommitRecord;
+use nq_check_point_record::NqCheckPointRecord;
 use roll_back_record::RollBackRecord;
+use savepoint_record::SavepointRecord;
 use set_int_record::SetIntRecord;
 use set_string_record::SetStringRecord;
 use start_record::StartRecord;
@@ -43,24 +52,20 @@ impl RecoveryMgr {
         Self { lm, bm, txnum }
     }
 
+    /// write COMMIT record to log and flush it to disk
+    /// the modified pages stay in the buffers (no-force), since recovery redoes
+    /// the changes of a committed transaction that did not reach the disk
     pub fn commit(&self) {
-        // flush all buffer to disk (write-Ahead logging)
-        {
-            // lock the buffer manager
-            let bm_ = self.bm.lock().unwrap();
-            bm_.flush_all(self.txnum); // ここでもlogはflushされる？
-        }
-        // write COMMIT record to log and flush it to disk
-        {
-            // lock the log manager
-            let mut lm_ = self.lm.lock().unwrap();
-            let lsn = CommitRecord::write_to_log(&mut lm_, self.txnum);
-            lm_.flush(lsn);
-        }
+        // lock the log manager
+        let mut lm_ = self.lm.lock().unwrap();
+        let lsn = CommitRecord::write_to_log(&mut lm_, self.txnum);
+        lm_.flush(lsn);
     }
 
-    pub fn rollback(&mut self, tx_for_undo: &mut TransactionForUndo) {
-        self.do_roll_back(tx_for_undo);
+    /// the undone pages are still flushed before the ROLLBACK record is written,
+    /// since recovery redoes only committed transactions
+    pub fn rollback(&mut self, tx_for_undo: &mut TransactionForUndo) -> DbResult<()> {
+        self.do_roll_back(tx_for_undo, None)?;
         // flush all buffer
         {
             // lock the buffer manager
@@ -74,76 +79,253 @@ impl RecoveryMgr {
             let lsn = RollBackRecord::write_to_log(&mut lm_, self.txnum);
             lm_.flush(lsn);
         }
+        Ok(())
     }
 
-    pub fn recover(&mut self, tx_for_undo: &mut TransactionForUndo) {
-        self.do_recover(tx_for_undo);
+    /// recovery runs before any other transaction starts,
+    /// so the database is quiescent and a checkpoint can be written when it is done
+    pub fn recover(&mut self, tx_for_undo: &mut TransactionForUndo) -> DbResult<()> {
+        self.do_recover(tx_for_undo)?;
         // flush all buffer
         {
             // lock the buffer manager
             let bm_ = self.bm.lock().unwrap();
             bm_.flush_all(self.txnum);
         }
+        // write CHECKPOINT record to log and flush it to disk
+        {
+            // lock the log manager
+            let mut lm_ = self.lm.lock().unwrap();
+            let lsn = CheckPointRecord::write_to_log(&mut lm_, Transaction::last_tx_number());
+            lm_.flush(lsn);
+            Self::release_log(&mut lm_)
+        }
     }
 
-    pub fn set_int(&self, buff: &mut Buffer, offset: i32, _new_val: i32) -> i32 {
-        let old_val = buff.contents().get_int(offset).unwrap();
-        let blk = buff.block().unwrap();
+    /// write a SETINT record holding the old and the new value, and return its lsn
+    pub fn set_int(&self, buff: &mut Buffer, offset: i32, new_val: i32) -> DbResult<i32> {
+        let old_val = buff.contents().get_int(offset)?;
+        let blk = Self::block_of(buff)?;
         {
             // lock the log manager
             let mut lm_: std::sync::MutexGuard<'_, LogMgr> = self.lm.lock().unwrap();
-            return SetIntRecord::write_to_log(&mut lm_, self.txnum, blk, offset, old_val);
+            return Ok(SetIntRecord::write_to_log(
+                &mut lm_, self.txnum, blk, offset, old_val, new_val,
+            ));
         }
     }
 
-    pub fn set_string(&self, buff: &mut Buffer, offset: i32, _new_val: String) -> i32 {
-        let old_val = buff.contents().get_string(offset).unwrap();
-        let blk = buff.block().unwrap();
+    /// write a SETSTRING record holding the old and the new value, and return its lsn
+    pub fn set_string(&self, buff: &mut Buffer, offset: i32, new_val: String) -> DbResult<i32> {
+        let old_val = buff.contents().get_string(offset)?;
+        let blk = Self::block_of(buff)?;
         {
             // lock the log manager
             let mut lm_ = self.lm.lock().unwrap();
-            return SetStringRecord::write_to_log(&mut lm_, self.txnum, blk, offset, old_val);
+            return Ok(SetStringRecord::write_to_log(
+                &mut lm_, self.txnum, blk, offset, old_val, new_val,
+            ));
         }
     }
 
-    pub fn do_roll_back(&mut self, tx_for_undo: &mut TransactionForUndo) {
+    /// the block a pinned buffer holds
+    fn block_of(buff: &Buffer) -> DbResult<BlockId> {
+        buff.block()
+            .ok_or_else(|| DbError::Corruption("a modified buffer holds no block".to_string()))
+    }
+
+    /// write a SAVEPOINT record, and return its lsn, which identifies the savepoint
+    pub fn savepoint(&self, name: &str) -> i32 {
+        // lock the log manager
+        let mut lm_ = self.lm.lock().unwrap();
+        return SavepointRecord::write_to_log(&mut lm_, self.txnum, name);
+    }
+
+    /// undo the changes made since the savepoint, the transaction keeps running
+    /// @param savepoint: the lsn of the SAVEPOINT record
+    pub fn rollback_to_savepoint(
+        &mut self,
+        tx_for_undo: &mut TransactionForUndo,
+        savepoint: i32,
+    ) -> DbResult<()> {
+        self.do_roll_back(tx_for_undo, Some(savepoint))
+    }
+
+    /// undo the changes of the transaction, going backward from the last log record
+    /// a rollback to a savepoint logs a compensation for each undone change,
+    /// since the transaction may still commit and have its changes redone by recovery
+    /// @param savepoint: the lsn of the SAVEPOINT record to stop at, or None to stop at the START record
+    pub fn do_roll_back(
+        &mut self,
+        tx_for_undo: &mut TransactionForUndo,
+        savepoint: Option<i32>,
+    ) -> DbResult<()> {
+        let mut recs = Vec::new();
         {
             // lock the log maanger
             let mut lm_ = self.lm.lock().unwrap();
-            let mut iter = lm_.iterator();
+            let mut iter = lm_.iterator()?;
             while let Some(bytes) = iter.next() {
-                let rec = LogRecord::create_log_record(bytes);
-                if let Some(txnum) = rec.tx_number() {
-                    if txnum == self.txnum {
-                        match rec {
-                            LogRecordType::START(_) => break, // arrived at the start record of this transaction
-                            _ => {
-                                rec.undo(tx_for_undo);
-                            } // undo the log record
-                        }
-                    }
+                let rec = LogRecord::create_log_record(bytes?)?;
+                if rec.tx_number() != Some(self.txnum) {
+                    continue;
+                }
+                match rec {
+                    LogRecordType::START(_) => break, // arrived at the start record of this transaction
+                    LogRecordType::SAVEPOINT(_) if savepoint == Some(iter.lsn()) => break, // arrived at the savepoint
+                    _ => recs.push(rec),
                 }
             }
         }
+        for rec in recs.iter() {
+            if savepoint.is_some() {
+                // lock the log manager
+                let mut lm_ = self.lm.lock().unwrap();
+                rec.compensate(&mut lm_);
+            }
+            rec.undo(tx_for_undo)?; // undo the log record
+        }
+        Ok(())
     }
 
-    pub fn do_recover(&mut self, tx_for_undo: &mut TransactionForUndo) {
-        let mut finished_txs: HashSet<i32> = HashSet::new();
+    /// write a non-quiescent checkpoint while the transactions keep running
+    /// a change logged before the flush began is on disk, so recovery redoes nothing older than
+    /// the previous checkpoint, and undoes nothing older than the start of the listed transactions
+    /// @return: the lsn of the NQCKPT record
+    /// algorithm:
+    /// 1. flush the modified pages of every transaction
+    /// 2. write a NQCKPT record listing the running transactions and flush it to disk
+    /// 3. release the log segments recovery no longer needs
+    pub fn checkpoint(
+        lm: &Arc<Mutex<LogMgr>>,
+        bm: &Arc<Mutex<BufferMgr>>,
+        versions: &VersionMgr,
+    ) -> DbResult<i32> {
+        {
+            // lock the buffer manager
+            let bm_ = bm.lock().unwrap();
+            bm_.flush_all_txs();
+        }
+        // a transaction registers before it writes its START record,
+        // so a listed transaction may start after the checkpoint but never the other way round
         {
             // lock the log manager
-            let mut lm_ = self.lm.lock().unwrap();
-            let mut iter = lm_.iterator();
-            while let Some(bytes) = iter.next() {
-                let rec = LogRecord::create_log_record(bytes);
-                if let LogRecordType::CHECKPOINT(_) = rec {
-                    return;
+            let mut lm_ = lm.lock().unwrap();
+            // read under the lock, so a transaction numbered later writes its START record after
+            let last_txnum = Transaction::last_tx_number();
+            let lsn =
+                NqCheckPointRecord::write_to_log(&mut lm_, last_txnum, &versions.active_txs());
+            lm_.flush(lsn);
+            Self::release_log(&mut lm_)?;
+            return Ok(lsn);
+        }
+    }
+
+    /// a rolled back transaction needs neither pass, its undone pages were flushed before its ROLLBACK record
+    /// algorithm:
+    /// 1. redo pass: going forward, write the new values of the committed transactions
+    /// 2. undo pass: going backward, write the old values of the unfinished transactions
+    pub fn do_recover(&mut self, tx_for_undo: &mut TransactionForUndo) -> DbResult<()> {
+        // lock the log manager
+        let (recs, _) = Self::read_back(&mut self.lm.lock().unwrap())?;
+        let mut committed_txs: HashSet<i32> = HashSet::new();
+        let mut finished_txs: HashSet<i32> = HashSet::new();
+        for rec in recs.iter() {
+            match rec {
+                LogRecordType::COMMIT(_) => {
+                    committed_txs.insert(rec.tx_number().unwrap());
+                    finished_txs.insert(rec.tx_number().unwrap());
                 }
-                if let LogRecordType::COMMIT(_) | LogRecordType::ROLLBACK(_) = rec {
+                LogRecordType::ROLLBACK(_) => {
                     finished_txs.insert(rec.tx_number().unwrap());
-                } else if !finished_txs.contains(&rec.tx_number().unwrap()) {
-                    rec.undo(tx_for_undo);
                 }
+                _ => {}
+            }
+        }
+
+        for rec in recs.iter().rev() {
+            if rec.tx_number().is_some_and(|t| committed_txs.contains(&t)) {
+                rec.redo(tx_for_undo)?;
+            }
+        }
+        for rec in recs.iter() {
+            if rec.tx_number().is_some_and(|t| !finished_txs.contains(&t)) {
+                rec.undo(tx_for_undo)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// read the log back as far as recovery needs, that is until a quiescent CHECKPOINT,
+    /// before which nothing needs to be done, or until both the NQCKPT before the last one
+    /// and the START record of every transaction listed in the last one have been read
+    /// @return: the records read, newest first, and the lsn of the oldest record recovery needs
+    fn read_back(lm: &mut LogMgr) -> DbResult<(Vec<LogRecordType>, i32)> {
+        let mut started_txs: HashSet<i32> = HashSet::new();
+        // the transactions of the last NQCKPT whose START record is not read yet
+        let mut unstarted_txs: Option<HashSet<i32>> = None;
+        let mut passed_previous_checkpoint = false;
+        let mut recs: Vec<LogRecordType> = Vec::new();
+        let mut iter = lm.iterator()?;
+        let mut oldest_lsn = iter.lsn();
+        while let Some(bytes) = iter.next() {
+            oldest_lsn = iter.lsn();
+            let rec = LogRecord::create_log_record(bytes?)?;
+            match &rec {
+                LogRecordType::CHECKPOINT(_) => break,
+                LogRecordType::NQCKPT(c) => match unstarted_txs {
+                    None => {
+                        unstarted_txs = Some(
+                            c.tx_numbers()
+                                .iter()
+                                .filter(|t| !started_txs.contains(t))
+                                .copied()
+                                .collect(),
+                        )
+                    }
+                    Some(_) => passed_previous_checkpoint = true,
+                },
+                LogRecordType::START(_) => {
+                    let txnum = rec.tx_number().unwrap();
+                    started_txs.insert(txnum);
+                    if let Some(txs) = unstarted_txs.as_mut() {
+                        txs.remove(&txnum);
+                    }
+                }
+                _ => {}
+            }
+            if passed_previous_checkpoint && unstarted_txs.as_ref().is_some_and(|t| t.is_empty()) {
+                break;
             }
+            recs.push(rec);
+        }
+        return Ok((recs, oldest_lsn));
+    }
+
+    /// the highest transaction number in the log
+    /// a transaction whose records were removed from the log started before the last checkpoint,
+    /// so it is enough to read back to the last checkpoint, which records the highest number then
+    /// @return: 0 if the log is empty
+    pub fn last_tx_number(lm: &mut LogMgr) -> DbResult<i32> {
+        let mut last = 0;
+        let mut iter = lm.iterator()?;
+        while let Some(bytes) = iter.next() {
+            let rec = LogRecord::create_log_record(bytes?)?;
+            match &rec {
+                LogRecordType::CHECKPOINT(c) => return Ok(last.max(c.last_tx_number())),
+                LogRecordType::NQCKPT(c) => return Ok(last.max(c.last_tx_number())),
+                _ => last = last.max(rec.tx_number().unwrap_or(0)),
+            }
+        }
+        return Ok(last);
+    }
+
+    /// remove the log segments recovery no longer needs, as the retention of the log says
+    fn release_log(lm: &mut LogMgr) -> DbResult<()> {
+        if *lm.retention() != LogRetention::Keep {
+            let (_, oldest_lsn) = Self::read_back(lm)?;
+            lm.release(oldest_lsn)?;
         }
+        Ok(())
     }
 }
@@ -2,34 +2,48 @@ use std::fmt::Display;
 use std::vec;
 
 use crate::common::integer;
+use crate::error::DbResult;
 use crate::file_manager::page::Page;
 use crate::log_manager::log_mgr::LogMgr;
 use crate::transaction_manager::recovery_manager::log_record_item::{LogRecordItem, RecordType};
 use crate::transaction_manager::transaction::TransactionForUndo;
 
+/// the record keeps the highest transaction number handed out when it was written,
+/// since the records of the older transactions may be removed from the log
 /// data format:
-/// |     4      |
-/// | CHECKPOINT |
+/// |     4      |      4      |
+/// | CHECKPOINT | last txnum  |
 #[derive(Debug)]
-pub struct CheckPointRecord {}
+pub struct CheckPointRecord {
+    last_txnum: i32,
+}
 
 impl Display for CheckPointRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "<CHECKPOINT>")
+        write!(f, "<CHECKPOINT {}>", self.last_txnum)
     }
 }
 
 impl CheckPointRecord {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(mut p: Page) -> DbResult<Self> {
+        let tpos = integer::BYTES;
+        let last_txnum = p.get_int(tpos)?;
+        Ok(Self { last_txnum })
+    }
+
+    /// the highest transaction number handed out when the checkpoint was written
+    pub fn last_tx_number(&self) -> i32 {
+        self.last_txnum
     }
 
     /// write a checkpoint record to the log and return its LSN
-    pub fn write_to_log(lm: &mut LogMgr) -> i32 {
+    pub fn write_to_log(lm: &mut LogMgr, last_txnum: i32) -> i32 {
         let tpos = integer::BYTES;
-        let rec = vec![0; tpos as usize];
+        let reclen = tpos + integer::BYTES;
+        let rec = vec![0; reclen as usize];
         let mut p = Page::new_log(rec);
         p.set_int(0, RecordType::CHECKPOINT as i32);
+        p.set_int(tpos, last_txnum);
         return lm.append(p.contents().into_vec());
     }
 }
@@ -43,7 +57,8 @@ impl LogRecordItem for CheckPointRecord {
         None
     }
 
-    fn undo(&self, _tx: &mut TransactionForUndo) {
+    fn undo(&self, _tx: &mut TransactionForUndo) -> DbResult<()> {
         // do nothing
+        Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use crate::common::integer;
+use crate::error::DbResult;
 use crate::file_manager::page::Page;
 use crate::log_manager::log_mgr::LogMgr;
 use crate::transaction_manager::recovery_manager::log_record_item::{LogRecordItem, RecordType};
@@ -21,12 +22,10 @@ impl Display for CommitRecord {
 }
 
 impl CommitRecord {
-    pub fn new(mut p: Page) -> Self {
+    pub fn new(mut p: Page) -> DbResult<Self> {
         let tpos = integer::BYTES;
-        let txnum = p
-            .get_int(tpos)
-            .expect("CommitRecord::new: failed to get txnum");
-        Self { txnum }
+        let txnum = p.get_int(tpos)?;
+        Ok(Self { txnum })
     }
 
     /// write a commit record to the log and return its lsn
@@ -50,7 +49,8 @@ impl LogRecordItem for CommitRecord {
         Some(self.txnum)
     }
 
-    fn undo(&self, _tx: &mut TransactionForUndo) {
+    fn undo(&self, _tx: &mut TransactionForUndo) -> DbResult<()> {
         // do nothing
+        Ok(())
     }
 }
@@ -1,9 +1,12 @@
+use crate::error::DbResult;
 use crate::file_manager::page::Page;
+use crate::log_manager::log_mgr::LogMgr;
 use crate::transaction_manager::recovery_manager::log_record_item::LogRecordItem;
 use crate::transaction_manager::recovery_manager::log_record_item::RecordType;
 use crate::transaction_manager::recovery_manager::{
     check_point_record::CheckPointRecord, commit_record::CommitRecord,
-    roll_back_record::RollBackRecord, set_int_record::SetIntRecord,
+    nq_check_point_record::NqCheckPointRecord, roll_back_record::RollBackRecord,
+    savepoint_record::SavepointRecord, set_int_record::SetIntRecord,
     set_string_record::SetStringRecord, start_record::StartRecord,
 };
 use crate::transaction_manager::transaction::TransactionForUndo;
@@ -18,6 +21,8 @@ pub enum LogRecordType {
     ROLLBACK(RollBackRecord),
     SETINT(SetIntRecord),
     SETSTRING(SetStringRecord),
+    NQCKPT(NqCheckPointRecord),
+    SAVEPOINT(SavepointRecord),
 }
 
 impl LogRecordType {
@@ -29,10 +34,12 @@ impl LogRecordType {
             LogRecordType::ROLLBACK(r) => r.tx_number(),
             LogRecordType::SETINT(s) => s.tx_number(),
             LogRecordType::SETSTRING(s) => s.tx_number(),
+            LogRecordType::NQCKPT(n) => n.tx_number(),
+            LogRecordType::SAVEPOINT(s) => s.tx_number(),
         }
     }
 
-    pub fn undo(&self, tx: &mut TransactionForUndo) {
+    pub fn undo(&self, tx: &mut TransactionForUndo) -> DbResult<()> {
         match self {
             LogRecordType::CHECKPOINT(c) => c.undo(tx),
             LogRecordType::START(s) => s.undo(tx),
@@ -40,24 +47,53 @@ impl LogRecordType {
             LogRecordType::ROLLBACK(r) => r.undo(tx),
             LogRecordType::SETINT(s) => s.undo(tx),
             LogRecordType::SETSTRING(s) => s.undo(tx),
+            LogRecordType::NQCKPT(n) => n.undo(tx),
+            LogRecordType::SAVEPOINT(s) => s.undo(tx),
+        }
+    }
+
+    pub fn compensate(&self, lm: &mut LogMgr) -> Option<i32> {
+        match self {
+            LogRecordType::CHECKPOINT(c) => c.compensate(lm),
+            LogRecordType::START(s) => s.compensate(lm),
+            LogRecordType::COMMIT(c) => c.compensate(lm),
+            LogRecordType::ROLLBACK(r) => r.compensate(lm),
+            LogRecordType::SETINT(s) => s.compensate(lm),
+            LogRecordType::SETSTRING(s) => s.compensate(lm),
+            LogRecordType::NQCKPT(n) => n.compensate(lm),
+            LogRecordType::SAVEPOINT(s) => s.compensate(lm),
+        }
+    }
+
+    pub fn redo(&self, tx: &mut TransactionForUndo) -> DbResult<()> {
+        match self {
+            LogRecordType::CHECKPOINT(c) => c.redo(tx),
+            LogRecordType::START(s) => s.redo(tx),
+            LogRecordType::COMMIT(c) => c.redo(tx),
+            LogRecordType::ROLLBACK(r) => r.redo(tx),
+            LogRecordType::SETINT(s) => s.redo(tx),
+            LogRecordType::SETSTRING(s) => s.redo(tx),
+            LogRecordType::NQCKPT(n) => n.redo(tx),
+            LogRecordType::SAVEPOINT(s) => s.redo(tx),
         }
     }
 }
 
 impl LogRecord {
-    pub fn create_log_record(bytes: Vec<u8>) -> LogRecordType {
+    /// @return: a Corruption error if the bytes are not a log record
+    pub fn create_log_record(bytes: Vec<u8>) -> DbResult<LogRecordType> {
         let mut p = Page::new_log(bytes);
-        let log_record = RecordType::from(
-            p.get_int(0)
-                .expect("Logrecord::create_log_record: failed to get record type"),
-        );
-        match log_record {
-            RecordType::CHECKPOINT => LogRecordType::CHECKPOINT(CheckPointRecord::new()),
-            RecordType::START => LogRecordType::START(StartRecord::new(p)),
-            RecordType::COMMIT => LogRecordType::COMMIT(CommitRecord::new(p)),
-            RecordType::ROLLBACK => LogRecordType::ROLLBACK(RollBackRecord::new(p)),
-            RecordType::SETINT => LogRecordType::SETINT(SetIntRecord::new(p)),
-            RecordType::SETSTRING => LogRecordType::SETSTRING(SetStringRecord::new(p)),
-        }
+        let log_record = RecordType::try_from(p.get_int(0)?)?;
+        let rec = match log_record {
+            RecordType::CHECKPOINT => LogRecordType::CHECKPOINT(CheckPointRecord::new(p)?),
+            RecordType::START => LogRecordType::START(StartRecord::new(p)?),
+            RecordType::COMMIT => LogRecordType::COMMIT(CommitRecord::new(p)?),
+            RecordType::ROLLBACK => LogRecordType::ROLLBACK(RollBackRecord::new(p)?),
+            RecordType::SETINT => LogRecordType::SETINT(SetIntRecord::new(p)?),
+            RecordType::SETSTRING => LogRecordType::SETSTRING(SetStringRecord::new(p)?),
+            RecordType::NQCKPT => LogRecordType::NQCKPT(NqCheckPointRecord::new(p)?),
+            RecordType::SAVEPOINT => LogRecordType::SAVEPOINT(SavepointRecord::new(p)?),
+        };
+        Ok(rec)
     }
 }
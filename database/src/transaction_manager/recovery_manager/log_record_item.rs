@@ -1,3 +1,5 @@
+use crate::error::{DbError, DbResult};
+use crate::log_manager::log_mgr::LogMgr;
 use crate::transaction_manager::transaction::TransactionForUndo;
 
 pub enum RecordType {
@@ -7,18 +9,28 @@ pub enum RecordType {
     ROLLBACK,
     SETINT,
     SETSTRING,
+    NQCKPT,
+    SAVEPOINT,
 }
 
-impl From<i32> for RecordType {
-    fn from(i: i32) -> Self {
+/// a log record of another type cannot be read back
+impl TryFrom<i32> for RecordType {
+    type Error = DbError;
+
+    fn try_from(i: i32) -> DbResult<Self> {
         match i {
-            0 => RecordType::CHECKPOINT,
-            1 => RecordType::START,
-            2 => RecordType::COMMIT,
-            3 => RecordType::ROLLBACK,
-            4 => RecordType::SETINT,
-            5 => RecordType::SETSTRING,
-            _ => panic!("RecordType::from: invalid i32"),
+            0 => Ok(RecordType::CHECKPOINT),
+            1 => Ok(RecordType::START),
+            2 => Ok(RecordType::COMMIT),
+            3 => Ok(RecordType::ROLLBACK),
+            4 => Ok(RecordType::SETINT),
+            5 => Ok(RecordType::SETSTRING),
+            6 => Ok(RecordType::NQCKPT),
+            7 => Ok(RecordType::SAVEPOINT),
+            _ => Err(DbError::Corruption(format!(
+                "unknown log record type {}",
+                i
+            ))),
         }
     }
 }
@@ -26,5 +38,14 @@ impl From<i32> for RecordType {
 pub trait LogRecordItem {
     fn op() -> RecordType;
     fn tx_number(&self) -> Option<i32>;
-    fn undo(&self, tx: &mut TransactionForUndo) -> ();
+    fn undo(&self, tx: &mut TransactionForUndo) -> DbResult<()>;
+    /// write the change of the record again, only update records change anything
+    fn redo(&self, _tx: &mut TransactionForUndo) -> DbResult<()> {
+        Ok(())
+    }
+    /// log the undo of the change as a change of its own and return its lsn,
+    /// so that redoing a transaction rolled back to a savepoint does not bring the change back
+    fn compensate(&self, _lm: &mut LogMgr) -> Option<i32> {
+        None
+    }
 }
@@ -0,0 +1,82 @@
+use std::fmt::Display;
+
+use crate::common::integer;
+use crate::error::DbResult;
+use crate::file_manager::page::Page;
+use crate::log_manager::log_mgr::LogMgr;
+use crate::transaction_manager::recovery_manager::log_record_item::{LogRecordItem, RecordType};
+use crate::transaction_manager::transaction::TransactionForUndo;
+
+/// a non-quiescent checkpoint, written while transactions keep running
+/// like a CHECKPOINT, it keeps the highest transaction number handed out when it was written
+/// data format:
+/// |       4       |     4      |     4     |    4    | ... |    4    |
+/// | NQCKPT ( = 6) | last txnum | n (count) | txnum 1 | ... | txnum n |
+#[derive(Debug)]
+pub struct NqCheckPointRecord {
+    last_txnum: i32,
+    txnums: Vec<i32>,
+}
+
+impl Display for NqCheckPointRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let txnums: Vec<String> = self.txnums.iter().map(|t| t.to_string()).collect();
+        write!(f, "<NQCKPT {}>", txnums.join(","))
+    }
+}
+
+impl NqCheckPointRecord {
+    pub fn new(mut p: Page) -> DbResult<Self> {
+        let lpos = integer::BYTES;
+        let last_txnum = p.get_int(lpos)?;
+        let npos = lpos + integer::BYTES;
+        let n = p.get_int(npos)?;
+        let mut txnums = Vec::new();
+        for i in 0..n {
+            let tpos = npos + (i + 1) * integer::BYTES;
+            txnums.push(p.get_int(tpos)?);
+        }
+        Ok(Self { last_txnum, txnums })
+    }
+
+    /// the highest transaction number handed out when the checkpoint was written
+    pub fn last_tx_number(&self) -> i32 {
+        self.last_txnum
+    }
+
+    /// the transactions that were running when the checkpoint was written
+    pub fn tx_numbers(&self) -> &Vec<i32> {
+        &self.txnums
+    }
+
+    /// write a nqckpt record to the log and return its lsn
+    pub fn write_to_log(lm: &mut LogMgr, last_txnum: i32, txnums: &[i32]) -> i32 {
+        let lpos = integer::BYTES;
+        let npos = lpos + integer::BYTES;
+        let reclen = npos + (txnums.len() as i32 + 1) * integer::BYTES;
+        let rec = vec![0; reclen as usize];
+        let mut p = Page::new_log(rec);
+        p.set_int(0, RecordType::NQCKPT as i32);
+        p.set_int(lpos, last_txnum);
+        p.set_int(npos, txnums.len() as i32);
+        for (i, txnum) in txnums.iter().enumerate() {
+            p.set_int(npos + (i as i32 + 1) * integer::BYTES, *txnum);
+        }
+        return lm.append(p.contents().into_vec());
+    }
+}
+
+impl LogRecordItem for NqCheckPointRecord {
+    fn op() -> RecordType {
+        RecordType::NQCKPT
+    }
+
+    fn tx_number(&self) -> Option<i32> {
+        None
+    }
+
+    fn undo(&self, _tx: &mut TransactionForUndo) -> DbResult<()> {
+        // do nothing
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::Display;
+
+use crate::common::integer;
+use crate::error::DbResult;
+use crate::file_manager::page::Page;
+use crate::log_manager::log_mgr::LogMgr;
+use crate::transaction_manager::recovery_manager::log_record_item::{LogRecordItem, RecordType};
+use crate::transaction_manager::transaction::TransactionForUndo;
+
+/// marks the point a transaction can roll back to without ending
+/// data format:
+/// |        4         |   4   |       4     |   n  |
+/// | SAVEPOINT ( = 7) | txnum | name.length | name |
+#[derive(Debug)]
+pub struct SavepointRecord {
+    txnum: i32,
+    name: String,
+}
+
+impl Display for SavepointRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<SAVEPOINT {} {}>", self.txnum, self.name)
+    }
+}
+
+impl SavepointRecord {
+    pub fn new(mut p: Page) -> DbResult<Self> {
+        let tpos = integer::BYTES;
+        let txnum = p.get_int(tpos)?;
+        let npos = tpos + integer::BYTES;
+        let name = p.get_string(npos)?;
+        Ok(Self { txnum, name })
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    /// write a savepoint record to the log and return its lsn
+    pub fn write_to_log(lm: &mut LogMgr, txnum: i32, name: &str) -> i32 {
+        let tpos = integer::BYTES;
+        let npos = tpos + integer::BYTES;
+        let reclen = npos + Page::max_length(name.len() as i32);
+        let rec = vec![0; reclen as usize];
+        let mut p = Page::new_log(rec);
+        p.set_int(0, RecordType::SAVEPOINT as i32);
+        p.set_int(tpos, txnum);
+        p.set_string(npos, name.to_string());
+        return lm.append(p.contents().into_vec());
+    }
+}
+
+impl LogRecordItem for SavepointRecord {
+    fn op() -> RecordType {
+        RecordType::SAVEPOINT
+    }
+
+    fn tx_number(&self) -> Option<i32> {
+        Some(self.txnum)
+    }
+
+    fn undo(&self, _tx: &mut TransactionForUndo) -> DbResult<()> {
+        // do nothing
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use std::fmt::Display;
 use std::vec;
 
 use crate::common::integer;
+use crate::error::{DbError, DbResult};
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::page::Page;
 use crate::log_manager::log_mgr::LogMgr;
@@ -9,13 +10,15 @@ use crate::transaction_manager::recovery_manager::log_record_item::{LogRecordIte
 use crate::transaction_manager::transaction::TransactionForUndo;
 
 /// data format:
-/// |       4        |   4   |         4       |     n    |    4   |   4    |  4  |
-/// | SETINT ( = 4 ) | txnum | filename.length | filename | blknum | offset | val |
+/// |       4        |   4   |         4       |     n    |    4   |   4    |    4    |    4    |
+/// | SETINT ( = 4 ) | txnum | filename.length | filename | blknum | offset | old val | new val |
+/// the old value is written back by undo, the new value by redo
 #[derive(Debug)]
 pub struct SetIntRecord {
     txnum: i32,
     offset: i32,
-    val: i32,
+    old_val: i32,
+    new_val: i32,
     blk: BlockId,
 }
 
@@ -23,51 +26,52 @@ impl Display for SetIntRecord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "<SETINT {} {} {} {}>",
-            self.txnum, self.blk, self.offset, self.val
+            "<SETINT {} {} {} {} {}>",
+            self.txnum, self.blk, self.offset, self.old_val, self.new_val
         )
     }
 }
 
 impl SetIntRecord {
-    pub fn new(mut p: Page) -> Self {
+    pub fn new(mut p: Page) -> DbResult<Self> {
         let tpos = integer::BYTES;
-        let txnum = p
-            .get_int(tpos)
-            .expect("SetIntRecord::new: failed to get txnum");
+        let txnum = p.get_int(tpos)?;
         let fpos = tpos + integer::BYTES;
-        let filename = p
-            .get_string(fpos)
-            .expect("SetIntRecord::new: failed to get filename");
+        let filename = p.get_string(fpos)?;
         let bpos = fpos + Page::max_length(filename.len() as i32);
-        let blknum = p
-            .get_int(bpos)
-            .expect("SetIntRecord::new: failed to get blknum");
+        let blknum = p.get_int(bpos)?;
         let blk = BlockId::new(filename, blknum);
         let opos = bpos + integer::BYTES;
-        let offset = p
-            .get_int(opos)
-            .expect("SetIntRecord::new: failed to get offset");
+        let offset = p.get_int(opos)?;
         let vpos = opos + integer::BYTES;
-        let val = p
-            .get_int(vpos)
-            .expect("SetIntRecord::new: failed to get val");
-        Self {
+        let old_val = p.get_int(vpos)?;
+        let npos = vpos + integer::BYTES;
+        let new_val = p.get_int(npos)?;
+        Ok(Self {
             txnum,
             offset,
-            val,
+            old_val,
+            new_val,
             blk,
-        }
+        })
     }
 
     /// write a setint record to the log and return its lsn
-    pub fn write_to_log(lm: &mut LogMgr, txnum: i32, blk: BlockId, offset: i32, val: i32) -> i32 {
+    pub fn write_to_log(
+        lm: &mut LogMgr,
+        txnum: i32,
+        blk: BlockId,
+        offset: i32,
+        old_val: i32,
+        new_val: i32,
+    ) -> i32 {
         let tpos = integer::BYTES;
         let fpos = tpos + integer::BYTES;
         let bpos = fpos + Page::max_length(blk.filename().len() as i32);
         let opos = bpos + integer::BYTES;
         let vpos = opos + integer::BYTES;
-        let reclen = vpos + integer::BYTES;
+        let npos = vpos + integer::BYTES;
+        let reclen = npos + integer::BYTES;
         let rec = vec![0; reclen as usize];
         let mut p = Page::new_log(rec);
         p.set_int(0, RecordType::SETINT as i32);
@@ -75,7 +79,8 @@ impl SetIntRecord {
         p.set_string(fpos, blk.filename());
         p.set_int(bpos, blk.number());
         p.set_int(opos, offset);
-        p.set_int(vpos, val);
+        p.set_int(vpos, old_val);
+        p.set_int(npos, new_val);
         return lm.append(p.contents().into_vec());
     }
 }
@@ -89,28 +94,50 @@ impl LogRecordItem for SetIntRecord {
         Some(self.txnum)
     }
 
-    fn undo(&self, tx: &mut TransactionForUndo) {
+    fn undo(&self, tx: &mut TransactionForUndo) -> DbResult<()> {
+        self.write(tx, self.old_val)
+    }
+
+    fn redo(&self, tx: &mut TransactionForUndo) -> DbResult<()> {
+        self.write(tx, self.new_val)
+    }
+
+    /// the compensation swaps the old and the new value
+    fn compensate(&self, lm: &mut LogMgr) -> Option<i32> {
+        Some(Self::write_to_log(
+            lm,
+            self.txnum,
+            self.blk.clone(),
+            self.offset,
+            self.new_val,
+            self.old_val,
+        ))
+    }
+}
+
+impl SetIntRecord {
+    /// write the value to the block without logging it
+    /// nobody else can hold a lock on the block: the transaction being undone
+    /// already has its xlock, and recovery runs before any other transaction
+    fn write(&self, tx: &mut TransactionForUndo, val: i32) -> DbResult<()> {
         // pin
-        tx.my_buffers.pin(self.blk.clone());
+        tx.my_buffers.pin(self.blk.clone())?;
 
-        // set old value
-        tx.concur_mgr.xlock(self.blk.clone());
-        match tx.my_buffers.get_buffer(self.blk.clone()) {
-            Some(buff) => {
-                {
-                    // get lock on buffer
-                    let mut b = buff.lock().unwrap();
-                    let p = b.contents();
-                    p.set_int(self.offset, self.val);
-                    b.set_modified(self.txnum, -1);
-                }
-            }
-            None => {
-                panic!("SetIntRecord::undo: failed to get buffer")
-            }
+        tx.concur_mgr.xlock(self.blk.clone())?;
+        let buff = tx
+            .my_buffers
+            .get_buffer(self.blk.clone())
+            .ok_or_else(|| DbError::Transaction(format!("block {} is not pinned", self.blk)))?;
+        {
+            // get lock on buffer
+            let mut b = buff.lock().unwrap();
+            let p = b.contents();
+            p.set_int(self.offset, val);
+            b.set_modified(tx.txnum, -1);
         }
 
         // unpin
         tx.my_buffers.unpin(self.blk.clone());
+        Ok(())
     }
 }
@@ -5,11 +5,15 @@ use std::sync::{Arc, Mutex};
 
 use crate::buffer_manager::buffer::Buffer;
 use crate::buffer_manager::buffer_mgr::BufferMgr;
+use crate::error::{DbError, DbResult};
 use crate::file_manager::block_id::BlockId;
 use crate::file_manager::file_mgr::FileMgr;
 use crate::log_manager::log_mgr::LogMgr;
 use crate::transaction_manager::concurrency_manager::concurrency_mgr::ConcurrencyMgr;
+use crate::transaction_manager::concurrency_manager::lock_table::LockTable;
+use crate::transaction_manager::concurrency_manager::{IsolationLevel, LockAbortError};
 use crate::transaction_manager::recovery_manager::RecoveryMgr;
+use crate::transaction_manager::version_manager::{TxMode, VersionMgr};
 
 #[derive(Debug)]
 pub struct BufferList {
@@ -31,14 +35,15 @@ impl BufferList {
         self.buffers.get(&blk).map(|b| b.clone())
     }
 
-    pub fn pin(&mut self, blk: BlockId) {
+    pub fn pin(&mut self, blk: BlockId) -> DbResult<()> {
         {
             // lock the buffer manager
             let mut bm_ = self.bm.lock().unwrap();
-            let buff = bm_.pin(blk.clone());
+            let buff = bm_.pin(blk.clone())?;
             self.buffers.insert(blk.clone(), buff);
         }
         self.pins.push(blk.clone());
+        Ok(())
     }
 
     pub fn unpin(&mut self, blk: BlockId) {
@@ -93,46 +98,114 @@ pub struct Transaction {
     fm: FileMgr,
     txnum: i32,
     my_buffers: BufferList,
+    versions: Arc<VersionMgr>,
+    snapshot: Option<u64>,
+    /// false until the snapshot is known not to miss the changes of an unversioned writer
+    snapshot_ready: bool,
+    /// the names of the savepoints and the lsns of their SAVEPOINT records, oldest first
+    savepoints: Vec<(String, i32)>,
 }
 
 pub struct TransactionForUndo<'a> {
+    /// the transaction doing the undo or redo, which marks the pages it writes
+    pub(crate) txnum: i32,
     pub(crate) concur_mgr: &'a mut ConcurrencyMgr,
     pub(crate) my_buffers: &'a mut BufferList,
 }
 
 impl Transaction {
-    pub fn new(fm: FileMgr, lm: Arc<Mutex<LogMgr>>, bm: Arc<Mutex<BufferMgr>>) -> Self {
+    /// @param locktbl: the lock table shared by all transactions of the database
+    /// @param versions: the commit order shared by all transactions of the database
+    /// @param mode: whether the reads take slocks or see a snapshot
+    pub fn new(
+        fm: FileMgr,
+        lm: Arc<Mutex<LogMgr>>,
+        bm: Arc<Mutex<BufferMgr>>,
+        locktbl: Arc<LockTable>,
+        versions: Arc<VersionMgr>,
+        mode: TxMode,
+    ) -> Self {
         let txnum = Self::next_tx_number();
+        let snapshot = versions.begin(txnum, mode);
         Self {
             recovery_mgr: RecoveryMgr::new(txnum, lm.clone(), bm.clone()),
-            concur_mgr: ConcurrencyMgr::new(),
+            concur_mgr: ConcurrencyMgr::new(locktbl, txnum),
             bm: bm.clone(),
             fm: fm.clone(),
             txnum,
             my_buffers: BufferList::new(bm.clone()),
+            versions,
+            snapshot,
+            snapshot_ready: snapshot.is_none(),
+            savepoints: Vec::new(),
         }
     }
 
-    pub fn commit(&mut self) {
+    pub fn commit(&mut self) -> DbResult<()> {
         self.recovery_mgr.commit();
+        // the waiting writers must find the transaction committed once its locks are released
+        self.versions.commit(self.txnum);
         self.concur_mgr.release();
         self.my_buffers.unpin_all();
-        println!("transaction {} committed", self.txnum);
+        Ok(())
     }
 
-    pub fn roll_back(&mut self) {
+    /// the transaction ends even if its log cannot be read back,
+    /// the changes it could not undo are undone by the next recovery
+    pub fn roll_back(&mut self) -> DbResult<()> {
         let mut tx_for_undo = TransactionForUndo {
+            txnum: self.txnum,
             concur_mgr: &mut self.concur_mgr,
             my_buffers: &mut self.my_buffers,
         };
-        self.recovery_mgr.rollback(&mut tx_for_undo);
+        let result = self.recovery_mgr.rollback(&mut tx_for_undo);
+        self.versions.abort(self.txnum);
         self.concur_mgr.release();
         self.my_buffers.unpin_all();
-        println!("transaction {} rolled back", self.txnum);
+        result
+    }
+
+    /// mark the point the transaction can roll back to
+    /// a savepoint with the name of an older one hides it until it is released
+    pub fn savepoint(&mut self, name: String) {
+        let lsn = self.recovery_mgr.savepoint(&name);
+        self.savepoints.push((name, lsn));
     }
 
-    pub fn recovery(&mut self) {
+    /// undo the changes made since the savepoint, which is kept, and drop the later savepoints
+    /// the locks taken since the savepoint are kept as well
+    /// @return: false if the transaction has no savepoint of that name
+    pub fn rollback_to_savepoint(&mut self, name: &str) -> DbResult<bool> {
+        let index = match self.savepoints.iter().rposition(|(n, _)| n == name) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        self.savepoints.truncate(index + 1);
         let mut tx_for_undo = TransactionForUndo {
+            txnum: self.txnum,
+            concur_mgr: &mut self.concur_mgr,
+            my_buffers: &mut self.my_buffers,
+        };
+        self.recovery_mgr
+            .rollback_to_savepoint(&mut tx_for_undo, self.savepoints[index].1)?;
+        return Ok(true);
+    }
+
+    /// drop the savepoint and the later ones, keeping the changes made since
+    /// @return: false if the transaction has no savepoint of that name
+    pub fn release_savepoint(&mut self, name: &str) -> bool {
+        match self.savepoints.iter().rposition(|(n, _)| n == name) {
+            Some(index) => {
+                self.savepoints.truncate(index);
+                return true;
+            }
+            None => return false,
+        }
+    }
+
+    pub fn recovery(&mut self) -> DbResult<()> {
+        let mut tx_for_undo = TransactionForUndo {
+            txnum: self.txnum,
             concur_mgr: &mut self.concur_mgr,
             my_buffers: &mut self.my_buffers,
         };
@@ -143,102 +216,205 @@ impl Transaction {
             bm_.flush_all(self.txnum);
         }
 
-        self.recovery_mgr.recover(&mut tx_for_undo);
+        self.recovery_mgr.recover(&mut tx_for_undo)
+    }
+
+    /// on a buffer abort the transaction must be rolled back
+    /// a snapshot transaction begun while unversioned writers ran waits for them before its first read
+    /// on a lock abort the transaction must be rolled back
+    pub fn pin(&mut self, blk: BlockId) -> DbResult<()> {
+        if !self.snapshot_ready {
+            let max_time = self.concur_mgr.max_time();
+            match self.versions.wait_for_snapshot(self.txnum, max_time) {
+                Some(snapshot) => self.snapshot = Some(snapshot),
+                None => return Err(LockAbortError::Timeout(blk).into()),
+            }
+            self.snapshot_ready = true;
+        }
+        self.my_buffers.pin(blk)
+    }
+
+    /// get an slock on the block, waiting while another transaction writes it
+    /// on a lock abort the transaction must be rolled back
+    pub fn slock(&mut self, blk: BlockId) -> DbResult<()> {
+        Ok(self.concur_mgr.slock(blk)?)
     }
 
-    pub fn pin(&mut self, blk: BlockId) {
-        self.my_buffers.pin(blk);
+    /// get an xlock on the block, waiting while another transaction reads or writes it
+    /// on a lock abort the transaction must be rolled back
+    pub fn xlock(&mut self, blk: BlockId) -> DbResult<()> {
+        Ok(self.concur_mgr.xlock(blk)?)
     }
     pub fn unpin(&mut self, blk: BlockId) {
         self.my_buffers.unpin(blk);
     }
 
-    pub fn get_int(&mut self, blk: BlockId, offset: i32) -> i32 {
-        self.concur_mgr.slock(blk.clone());
-        let buff = self.my_buffers.get_buffer(blk.clone());
-        match buff {
-            Some(b) => {
-                let get_from_block: i32;
-                {
-                    // lock the buffer
-                    let mut b_ = b.lock().unwrap();
-                    get_from_block = b_.contents().get_int(offset).unwrap().clone();
-                }
-                return get_from_block;
-            }
-            None => panic!("Transaction::get_int: failed to get buffer"),
+    pub fn get_int(&mut self, blk: BlockId, offset: i32) -> DbResult<i32> {
+        self.concur_mgr.slock(blk.clone())?;
+        let val = self.read_int(blk.clone(), offset)?;
+        self.concur_mgr.end_read(blk);
+        return Ok(val);
+    }
+
+    fn read_int(&mut self, blk: BlockId, offset: i32) -> DbResult<i32> {
+        let b = self.buffer(blk)?;
+        let get_from_block: i32;
+        {
+            // lock the buffer
+            let mut b_ = b.lock().unwrap();
+            get_from_block = b_.contents().get_int(offset)?;
         }
+        return Ok(get_from_block);
     }
 
-    pub fn get_string(&mut self, blk: BlockId, offset: i32) -> String {
-        self.concur_mgr.slock(blk.clone());
-        let buff = self.my_buffers.get_buffer(blk.clone());
-        match buff {
-            Some(b) => {
-                let get_from_block: String;
-                {
-                    // lock the buffer
-                    let mut b_ = b.lock().unwrap();
-                    get_from_block = b_.contents().get_string(offset).unwrap().clone();
-                }
-                return get_from_block;
-            }
-            None => panic!("Transaction::get_string: failed to get buffer"),
+    pub fn get_string(&mut self, blk: BlockId, offset: i32) -> DbResult<String> {
+        self.concur_mgr.slock(blk.clone())?;
+        let val = self.read_string(blk.clone(), offset)?;
+        self.concur_mgr.end_read(blk);
+        return Ok(val);
+    }
+
+    /// read an int of a record slot
+    /// a snapshot transaction takes no slock, since its snapshot chooses the record versions it reads
+    pub fn get_record_int(&mut self, blk: BlockId, offset: i32) -> DbResult<i32> {
+        if self.snapshot.is_some() {
+            return self.read_int(blk, offset);
+        }
+        return self.get_int(blk, offset);
+    }
+
+    /// read a string of a record slot
+    /// a snapshot transaction takes no slock, since its snapshot chooses the record versions it reads
+    pub fn get_record_string(&mut self, blk: BlockId, offset: i32) -> DbResult<String> {
+        if self.snapshot.is_some() {
+            return self.read_string(blk, offset);
+        }
+        return self.get_string(blk, offset);
+    }
+
+    fn read_string(&mut self, blk: BlockId, offset: i32) -> DbResult<String> {
+        let b = self.buffer(blk)?;
+        let get_from_block: String;
+        {
+            // lock the buffer
+            let mut b_ = b.lock().unwrap();
+            get_from_block = b_.contents().get_string(offset)?;
         }
+        return Ok(get_from_block);
     }
 
-    pub fn set_int(&mut self, blk: BlockId, offset: i32, val: i32, ok_to_log: bool) {
-        self.concur_mgr.xlock(blk.clone());
-        let buff = self.my_buffers.get_buffer(blk.clone());
+    /// the buffer holding a block the transaction has pinned
+    /// an unpinned block has no buffer to read or write, which is reported as a buffer abort
+    fn buffer(&self, blk: BlockId) -> DbResult<Arc<Mutex<Buffer>>> {
+        match self.my_buffers.get_buffer(blk.clone()) {
+            Some(b) => Ok(b),
+            None => Err(DbError::BufferAbort(blk)),
+        }
+    }
+
+    pub fn set_int(
+        &mut self,
+        blk: BlockId,
+        offset: i32,
+        val: i32,
+        ok_to_log: bool,
+    ) -> DbResult<()> {
+        self.concur_mgr.xlock(blk.clone())?;
+        let b = self.buffer(blk)?;
         let mut lsn = -1;
-        match buff {
-            Some(b) => {
-                {
-                    // lock the buffer
-                    let mut b_ = b.lock().unwrap();
-                    if ok_to_log {
-                        lsn = self.recovery_mgr.set_int(&mut b_, offset, val);
-                    }
-                    let p = b_.contents();
-                    p.set_int(offset, val);
-                    b_.set_modified(self.txnum, lsn);
-                }
+        {
+            // lock the buffer
+            let mut b_ = b.lock().unwrap();
+            if ok_to_log {
+                lsn = self.recovery_mgr.set_int(&mut b_, offset, val)?;
             }
-            None => panic!("Transaction::set_int: failed to get buffer"),
+            let p = b_.contents();
+            p.set_int(offset, val);
+            b_.set_modified(self.txnum, lsn);
         }
+        Ok(())
     }
 
-    pub fn set_string(&mut self, blk: BlockId, offset: i32, val: String, ok_to_log: bool) {
-        self.concur_mgr.xlock(blk.clone());
-        let buff = self.my_buffers.get_buffer(blk.clone());
+    pub fn set_string(
+        &mut self,
+        blk: BlockId,
+        offset: i32,
+        val: String,
+        ok_to_log: bool,
+    ) -> DbResult<()> {
+        self.concur_mgr.xlock(blk.clone())?;
+        let b = self.buffer(blk)?;
         let mut lsn = -1;
-        match buff {
-            Some(b) => {
-                {
-                    // lock the buffer
-                    let mut b_ = b.lock().unwrap();
-                    if ok_to_log {
-                        lsn = self.recovery_mgr.set_string(&mut b_, offset, val.clone());
-                    }
-                    let p = b_.contents();
-                    p.set_string(offset, val);
-                    b_.set_modified(self.txnum, lsn);
-                }
+        {
+            // lock the buffer
+            let mut b_ = b.lock().unwrap();
+            if ok_to_log {
+                lsn = self.recovery_mgr.set_string(&mut b_, offset, val.clone())?;
             }
-            None => panic!("Transaction::set_string: failed to get buffer"),
+            let p = b_.contents();
+            p.set_string(offset, val);
+            b_.set_modified(self.txnum, lsn);
         }
+        Ok(())
     }
 
-    pub fn size(&mut self, filename: String) -> i32 {
-        let dummyblk = BlockId::new(filename.clone(), END_OF_FILE);
-        self.concur_mgr.slock(dummyblk.clone());
-        return self.fm.length(filename.clone()).unwrap();
+    /// only a serializable locking transaction slocks the end of file,
+    /// so that no record can be appended to a file it has scanned (a phantom)
+    pub fn size(&mut self, filename: String) -> DbResult<i32> {
+        if self.snapshot.is_none()
+            && self.concur_mgr.isolation_level() == IsolationLevel::Serializable
+        {
+            let dummyblk = BlockId::new(filename.clone(), END_OF_FILE);
+            self.concur_mgr.slock(dummyblk.clone())?;
+        }
+        return Ok(self.fm.length(filename.clone())?);
     }
 
-    pub fn append(&mut self, filename: String) -> BlockId {
+    pub fn append(&mut self, filename: String) -> DbResult<BlockId> {
         let dummyblk = BlockId::new(filename.clone(), END_OF_FILE);
-        self.concur_mgr.xlock(dummyblk.clone());
-        return self.fm.append(filename.clone()).unwrap();
+        self.concur_mgr.xlock(dummyblk.clone())?;
+        return Ok(self.fm.append(filename.clone())?);
+    }
+
+    pub fn tx_num(&self) -> i32 {
+        self.txnum
+    }
+
+    pub fn isolation_level(&self) -> IsolationLevel {
+        self.concur_mgr.isolation_level()
+    }
+
+    /// choose how long the slocks of a locking transaction are held
+    /// the level applies to the reads from now on, the locks already held are kept
+    pub fn set_isolation_level(&mut self, level: IsolationLevel) {
+        self.concur_mgr.set_isolation_level(level);
+    }
+
+    pub fn mode(&self) -> TxMode {
+        match self.snapshot {
+            Some(_) => TxMode::Snapshot,
+            None => TxMode::Locking,
+        }
+    }
+
+    /// whether this transaction sees the changes of the transaction
+    /// a locking transaction sees every committed change, since it locks out the running writers
+    pub fn sees(&self, txnum: i32) -> bool {
+        match self.snapshot {
+            Some(snapshot) => txnum == self.txnum || self.versions.is_visible(txnum, snapshot),
+            None => true,
+        }
+    }
+
+    /// whether the transaction may overwrite and free records in place, without keeping versions
+    /// a locking transaction may while no snapshot transaction runs
+    pub fn skips_versions(&self) -> bool {
+        self.versions.skips_versions(self.txnum)
+    }
+
+    /// whether every snapshot, present or future, sees the changes of the transaction
+    pub fn is_settled(&self, txnum: i32) -> bool {
+        self.versions.is_settled(txnum)
     }
 
     pub fn block_size(&self) -> i32 {
@@ -255,11 +431,19 @@ impl Transaction {
         num
     }
 
+    /// make the transactions started from now on be numbered above txnum,
+    /// e.g. above the numbers found in the log of a reopened database
+    pub fn start_tx_numbers_after(txnum: i32) {
+        NEXT_TX_NUM.fetch_max(txnum, Ordering::SeqCst);
+    }
+
+    /// the highest transaction number handed out so far
+    pub fn last_tx_number() -> i32 {
+        return NEXT_TX_NUM.load(Ordering::SeqCst);
+    }
+
     fn next_tx_number() -> i32 {
-        NEXT_TX_NUM.fetch_add(1, Ordering::SeqCst);
-        let tx_num: i32 = NEXT_TX_NUM.load(Ordering::SeqCst).clone();
-        println!("nwxt transaction: {}", tx_num);
-        tx_num
+        return NEXT_TX_NUM.fetch_add(1, Ordering::SeqCst) + 1;
     }
 }
 
@@ -271,10 +455,15 @@ mod test {
     use std::sync::{Arc, Mutex};
 
     use crate::common::integer;
+    use crate::error::DbError;
+    use crate::transaction_manager::concurrency_manager::lock_table::DeadlockPolicy;
+    use crate::transaction_manager::concurrency_manager::IsolationLevel;
+    use crate::transaction_manager::recovery_manager::check_point_record::CheckPointRecord;
+    use crate::transaction_manager::recovery_manager::start_record::StartRecord;
     use crate::{
         buffer_manager::buffer_mgr::BufferMgr,
         file_manager::{block_id::BlockId, file_mgr::FileMgr, page::Page},
-        log_manager::log_mgr::LogMgr,
+        log_manager::log_mgr::{LogMgr, LogRetention},
     };
 
     fn setup(db_directory: String) {
@@ -302,19 +491,40 @@ mod test {
             LogMgr::new(fm.clone(), log_file.clone()).unwrap(),
         ));
         let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        let locktbl = Arc::new(LockTable::new());
+        let versions = Arc::new(VersionMgr::new());
 
         // Test transaction on ok_to_log = false
         // when ok_to_log is false, the transaction does not write to log, only write value to disk
         {
             // create Transaction
-            let mut tx1 = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+            let mut tx1 = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
             // check transaction number
             // assert_eq!(tx1.txnum, 1);
             let blk = BlockId::new("testfile".to_string(), 1);
-            tx1.pin(blk.clone());
-            tx1.set_int(blk.clone(), 80, 123, false);
-            tx1.set_string(blk, 40, "one".to_string(), false);
-            tx1.commit();
+            // a block has to be pinned before it is read or written
+            assert!(matches!(
+                tx1.get_int(blk.clone(), 80),
+                Err(DbError::BufferAbort(_))
+            ));
+            assert!(matches!(
+                tx1.set_int(blk.clone(), 80, 123, true),
+                Err(DbError::BufferAbort(_))
+            ));
+            tx1.pin(blk.clone())?;
+            tx1.set_int(blk.clone(), 80, 123, false)?;
+            tx1.set_string(blk, 40, "one".to_string(), false)?;
+            tx1.commit()?;
+
+            // commit leaves the page in its buffer, it reaches the disk when the buffer is flushed
+            bm.lock().unwrap().flush_all(tx1.txnum);
 
             // check disk content
             let mut p = Page::new(fm.block_size());
@@ -327,159 +537,135 @@ mod test {
 
             // check log content
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new("testfile".to_string(), 0);
+            let blk = BlockId::new("testfile.1".to_string(), 0);
             fm.read(&blk, &mut p).unwrap();
             let commit_offset = p.get_int(0).unwrap();
             let commit_log = p.get_bytes(commit_offset).unwrap();
             // commit log record | COMMIT (= 2) | txnum (= 1) |
-            assert_eq!(commit_log, vec![0, 0, 0, 2, 0, 0, 0, tx1.txnum as u8]);
+            assert_eq!(
+                commit_log,
+                [vec![0, 0, 0, 2], tx1.txnum.to_be_bytes().to_vec()].concat()
+            );
             let start_offset = commit_offset + commit_log.len() as i32 + integer::BYTES;
             let start_log = p.get_bytes(start_offset).unwrap();
             // start log record | START (= 1) | txnum (= 1) |
-            assert_eq!(start_log, vec![0, 0, 0, 1, 0, 0, 0, tx1.txnum as u8]);
+            assert_eq!(
+                start_log,
+                [vec![0, 0, 0, 1], tx1.txnum.to_be_bytes().to_vec()].concat()
+            );
         }
 
         // Test transaction on ok_to_log = true
         // when ok_to_log is true, the transaction writes to log and disk
         {
-            let mut tx2 = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+            let mut tx2 = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
             // check transaction number
             // assert_eq!(tx2.txnum, 2);
             let blk = BlockId::new("testfile".to_string(), 1);
-            tx2.pin(blk.clone());
-            let ival = tx2.get_int(blk.clone(), 80);
-            let sval = tx2.get_string(blk.clone(), 40);
+            tx2.pin(blk.clone())?;
+            let ival = tx2.get_int(blk.clone(), 80)?;
+            let sval = tx2.get_string(blk.clone(), 40)?;
             assert_eq!(ival, 123);
             assert_eq!(sval, "one".to_string());
             let new_ival = ival + 1;
             let new_sval = sval + "!";
-            tx2.set_int(blk.clone(), 80, new_ival, true);
-            tx2.set_string(blk.clone(), 40, new_sval.clone(), true);
-            tx2.commit();
+            tx2.set_int(blk.clone(), 80, new_ival, true)?;
+            tx2.set_string(blk.clone(), 40, new_sval.clone(), true)?;
+            tx2.commit()?;
 
             // check disk content
+            // commit flushes only the log, recovery would redo the change after a crash
             let mut p = Page::new(fm.block_size());
             let blk = BlockId::new("testfile".to_string(), 1);
             fm.read(&blk, &mut p).unwrap();
             let n = p.get_int(80).unwrap();
+            assert_eq!(n, 123); // old value
+            bm.lock().unwrap().flush_all(tx2.txnum);
+            fm.read(&blk, &mut p).unwrap();
+            let n = p.get_int(80).unwrap();
             assert_eq!(n, 124); // new value
 
             // Test log content
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new("testfile".to_string(), 0);
+            let blk = BlockId::new("testfile.1".to_string(), 0);
             fm.read(&blk, &mut p).unwrap();
 
             // check commit log
             let commit_offset = p.get_int(0).unwrap();
             let commit_log = p.get_bytes(commit_offset).unwrap();
             // commit log record | COMMIT (= 2) | txnum (= 2) |
-            assert_eq!(commit_log, vec![0, 0, 0, 2, 0, 0, 0, tx2.txnum as u8]);
+            assert_eq!(
+                commit_log,
+                [vec![0, 0, 0, 2], tx2.txnum.to_be_bytes().to_vec()].concat()
+            );
 
             // check set_string log
             let set_string_offset = commit_offset + commit_log.len() as i32 + integer::BYTES;
             let set_string_log = p.get_bytes(set_string_offset).unwrap();
-            // set_string log record | SETSTRING (= 5) | txnum (= 2) | blk filename (= 8) | blk number (= 1) | offset (= 40) | val.length (= 3) | val (= "one") |
-            // value is old value, not new value
+            // set_string log record | SETSTRING (= 5) | txnum (= 2) | blk filename (= 8) | blk number (= 1) | offset (= 40) | old val.length (= 3) | old val (= "one") | new val.length (= 4) | new val (= "one!") |
             assert_eq!(
                 set_string_log,
-                vec![
-                    0,
-                    0,
-                    0,
-                    5,
-                    0,
-                    0,
-                    0,
-                    tx2.txnum as u8,
-                    0,
-                    0,
-                    0,
-                    8,
-                    116,
-                    101,
-                    115,
-                    116,
-                    102,
-                    105,
-                    108,
-                    101,
-                    0,
-                    0,
-                    0,
-                    1,
-                    0,
-                    0,
-                    0,
-                    40,
-                    0,
-                    0,
-                    0,
-                    3,
-                    111,
-                    110,
-                    101
+                [
+                    vec![0, 0, 0, 5],
+                    tx2.txnum.to_be_bytes().to_vec(),
+                    vec![
+                        0, 0, 0, 8, 116, 101, 115, 116, 102, 105, 108, 101, 0, 0, 0, 1, 0, 0, 0,
+                        40, 0, 0, 0, 3, 111, 110, 101, 0, 0, 0, 4, 111, 110, 101, 33
+                    ],
                 ]
+                .concat()
             );
 
             // check set_int log
             let set_int_offset = set_string_offset + set_string_log.len() as i32 + integer::BYTES;
             let set_int_log = p.get_bytes(set_int_offset).unwrap();
-            // set_int log record | SETINT (= 4) | txnum (= 2) | blk filename (= 8) | blk number (= 1) | offset (= 80) | val (= 123) |
-            // value is old value, not new value
+            // set_int log record | SETINT (= 4) | txnum (= 2) | blk filename (= 8) | blk number (= 1) | offset (= 80) | old val (= 123) | new val (= 124) |
             assert_eq!(
                 set_int_log,
-                vec![
-                    0,
-                    0,
-                    0,
-                    4,
-                    0,
-                    0,
-                    0,
-                    tx2.txnum as u8,
-                    0,
-                    0,
-                    0,
-                    8,
-                    116,
-                    101,
-                    115,
-                    116,
-                    102,
-                    105,
-                    108,
-                    101,
-                    0,
-                    0,
-                    0,
-                    1,
-                    0,
-                    0,
-                    0,
-                    80,
-                    0,
-                    0,
-                    0,
-                    123
+                [
+                    vec![0, 0, 0, 4],
+                    tx2.txnum.to_be_bytes().to_vec(),
+                    vec![
+                        0, 0, 0, 8, 116, 101, 115, 116, 102, 105, 108, 101, 0, 0, 0, 1, 0, 0, 0,
+                        80, 0, 0, 0, 123, 0, 0, 0, 124
+                    ],
                 ]
+                .concat()
             );
 
             // check start log
             let start_offset = set_int_offset + set_int_log.len() as i32 + integer::BYTES;
             let start_log = p.get_bytes(start_offset).unwrap();
             // start log record | START (= 1) | txnum (= 2) |
-            assert_eq!(start_log, vec![0, 0, 0, 1, 0, 0, 0, tx2.txnum as u8]);
+            assert_eq!(
+                start_log,
+                [vec![0, 0, 0, 1], tx2.txnum.to_be_bytes().to_vec()].concat()
+            );
         }
 
         // Test rollback
         {
-            let mut tx3 = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+            let mut tx3 = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
             // check transaction number
             let blk = BlockId::new("testfile".to_string(), 1);
-            tx3.pin(blk.clone());
+            tx3.pin(blk.clone())?;
 
-            tx3.set_int(blk, 80, 160, true);
-            tx3.roll_back();
+            tx3.set_int(blk, 80, 160, true)?;
+            tx3.roll_back()?;
 
             // check disk content
             let mut p = Page::new(fm.block_size());
@@ -490,76 +676,572 @@ mod test {
 
             // check log content
             let mut p = Page::new(fm.block_size());
-            let blk = BlockId::new("testfile".to_string(), 0);
+            let blk = BlockId::new("testfile.1".to_string(), 0);
             fm.read(&blk, &mut p).unwrap();
 
             // check rollback log
             let rollback_offset = p.get_int(0).unwrap();
             let rollback_log = p.get_bytes(rollback_offset).unwrap();
             // rollback log record | ROLLBACK (= 3) | txnum (= 3) |
-            assert_eq!(rollback_log, vec![0, 0, 0, 3, 0, 0, 0, tx3.txnum as u8]);
+            assert_eq!(
+                rollback_log,
+                [vec![0, 0, 0, 3], tx3.txnum.to_be_bytes().to_vec()].concat()
+            );
 
             // check set_int log
             let set_int_offset = rollback_offset + rollback_log.len() as i32 + integer::BYTES;
             let set_int_log = p.get_bytes(set_int_offset).unwrap();
-            // set_int log record | SETINT (= 4) | txnum (= 3) | blk filename (= 8) | blk number (= 1) | offset (= 80) | val (= 124) |
-            // value is new value, not old value
+            // set_int log record | SETINT (= 4) | txnum (= 3) | blk filename (= 8) | blk number (= 1) | offset (= 80) | old val (= 124) | new val (= 160) |
             assert_eq!(
                 set_int_log,
-                vec![
-                    0,
-                    0,
-                    0,
-                    4,
-                    0,
-                    0,
-                    0,
-                    tx3.txnum as u8,
-                    0,
-                    0,
-                    0,
-                    8,
-                    116,
-                    101,
-                    115,
-                    116,
-                    102,
-                    105,
-                    108,
-                    101,
-                    0,
-                    0,
-                    0,
-                    1,
-                    0,
-                    0,
-                    0,
-                    80,
-                    0,
-                    0,
-                    0,
-                    124
+                [
+                    vec![0, 0, 0, 4],
+                    tx3.txnum.to_be_bytes().to_vec(),
+                    vec![
+                        0, 0, 0, 8, 116, 101, 115, 116, 102, 105, 108, 101, 0, 0, 0, 1, 0, 0, 0,
+                        80, 0, 0, 0, 124, 0, 0, 0, 160
+                    ],
                 ]
+                .concat()
             );
 
             // check start log
             let start_offset = set_int_offset + set_int_log.len() as i32 + integer::BYTES;
             let start_log = p.get_bytes(start_offset).unwrap();
             // start log record | START (= 1) | txnum (= 3) |
-            assert_eq!(start_log, vec![0, 0, 0, 1, 0, 0, 0, tx3.txnum as u8]);
+            assert_eq!(
+                start_log,
+                [vec![0, 0, 0, 1], tx3.txnum.to_be_bytes().to_vec()].concat()
+            );
         }
 
         // Test get_int
         {
-            let mut tx4 = Transaction::new(fm.clone(), log_mgr.clone(), bm.clone());
+            let mut tx4 = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
             // check transaction number
             let blk = BlockId::new("testfile".to_string(), 1);
-            tx4.pin(blk.clone());
-            let ival = tx4.get_int(blk.clone(), 80);
+            tx4.pin(blk.clone())?;
+            let ival = tx4.get_int(blk.clone(), 80)?;
             assert_eq!(ival, 124);
         }
 
         teardown(db_directory.clone());
         Ok(())
     }
+
+    /// a crash loses the buffers but not the log
+    /// recovery redoes the committed change that stayed in a buffer and
+    /// undoes the unfinished change whose page was flushed
+    #[test]
+    fn test_recovery() -> Result<()> {
+        let db_directory = "./db/recoverytest".to_string();
+        setup(db_directory.clone());
+        let blk0 = BlockId::new("recoveryfile".to_string(), 0);
+        let blk1 = BlockId::new("recoveryfile".to_string(), 1);
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let versions = Arc::new(VersionMgr::new());
+        {
+            let (log_mgr, bm) = start_up(&fm);
+            let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            for blk in [&blk0, &blk1] {
+                tx.append("recoveryfile".to_string())?;
+                tx.pin(blk.clone())?;
+                tx.set_int(blk.clone(), 0, 1, true)?;
+                tx.set_string(blk.clone(), 40, "old".to_string(), true)?;
+            }
+            tx.commit()?;
+            // recovery checkpoints the committed changes
+            new_locking_tx(&fm, &log_mgr, &bm, &versions).recovery()?;
+            assert_eq!(read_block(&mut fm, &blk0), (1, "old".to_string()));
+
+            let mut tx1 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx1.pin(blk0.clone())?;
+            tx1.set_int(blk0.clone(), 0, 2, true)?;
+            tx1.set_string(blk0.clone(), 40, "new".to_string(), true)?;
+            tx1.commit()?;
+
+            let mut tx2 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx2.pin(blk1.clone())?;
+            tx2.set_int(blk1.clone(), 0, 3, true)?;
+            tx2.set_string(blk1.clone(), 40, "dirty".to_string(), true)?;
+            bm.lock().unwrap().flush_all(tx2.txnum);
+
+            // crash
+            assert_eq!(read_block(&mut fm, &blk0), (1, "old".to_string()));
+            assert_eq!(read_block(&mut fm, &blk1), (3, "dirty".to_string()));
+        }
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let (log_mgr, bm) = start_up(&fm);
+        let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+        tx.recovery()?;
+        assert_eq!(read_block(&mut fm, &blk0), (2, "new".to_string()));
+        assert_eq!(read_block(&mut fm, &blk1), (1, "old".to_string()));
+
+        // recovery ends with a checkpoint, so the next recovery has nothing to do
+        let mut p = Page::new(fm.block_size());
+        let last = fm.length("testlog.1".to_string()).unwrap() - 1;
+        fm.read(&BlockId::new("testlog.1".to_string(), last), &mut p)
+            .unwrap();
+        let offset = p.get_int(0).unwrap();
+        assert_eq!(p.get_bytes(offset).unwrap()[..4], [0, 0, 0, 0]);
+
+        teardown(db_directory);
+        Ok(())
+    }
+
+    /// the checkpoint lists a transaction that is still running,
+    /// so recovery reads back past it to undo the changes that transaction made before
+    #[test]
+    fn test_recovery_from_nq_checkpoint() -> Result<()> {
+        let db_directory = "./db/nqcheckpointtest".to_string();
+        setup(db_directory.clone());
+        let blk0 = BlockId::new("recoveryfile".to_string(), 0);
+        let blk1 = BlockId::new("recoveryfile".to_string(), 1);
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let versions = Arc::new(VersionMgr::new());
+        {
+            let (log_mgr, bm) = start_up(&fm);
+            let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            for blk in [&blk0, &blk1] {
+                tx.append("recoveryfile".to_string())?;
+                tx.pin(blk.clone())?;
+                tx.set_int(blk.clone(), 0, 1, true)?;
+                tx.set_string(blk.clone(), 40, "old".to_string(), true)?;
+            }
+            tx.commit()?;
+
+            let mut tx1 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx1.pin(blk0.clone())?;
+            tx1.set_int(blk0.clone(), 0, 10, true)?;
+            let mut tx2 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx2.pin(blk1.clone())?;
+            tx2.set_int(blk1.clone(), 0, 20, true)?;
+            tx2.commit()?;
+
+            RecoveryMgr::checkpoint(&log_mgr, &bm, &versions)?;
+            assert_eq!(read_block(&mut fm, &blk0), (10, "old".to_string()));
+            assert_eq!(read_block(&mut fm, &blk1), (20, "old".to_string()));
+
+            let mut tx3 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx3.pin(blk1.clone())?;
+            tx3.set_string(blk1.clone(), 40, "after".to_string(), true)?;
+            tx3.commit()?;
+            tx1.set_string(blk0.clone(), 40, "dirty".to_string(), true)?;
+            bm.lock().unwrap().flush_all(tx1.txnum);
+
+            // crash
+            assert_eq!(read_block(&mut fm, &blk0), (10, "dirty".to_string()));
+            assert_eq!(read_block(&mut fm, &blk1), (20, "old".to_string()));
+        }
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let (log_mgr, bm) = start_up(&fm);
+        new_locking_tx(&fm, &log_mgr, &bm, &Arc::new(VersionMgr::new())).recovery()?;
+        assert_eq!(read_block(&mut fm, &blk0), (1, "old".to_string()));
+        assert_eq!(read_block(&mut fm, &blk1), (20, "after".to_string()));
+
+        teardown(db_directory);
+        Ok(())
+    }
+
+    #[test]
+    fn test_savepoint() -> Result<()> {
+        let db_directory = "./db/savepointtest".to_string();
+        setup(db_directory.clone());
+        let blk = BlockId::new("savepointfile".to_string(), 0);
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let versions = Arc::new(VersionMgr::new());
+        {
+            let (log_mgr, bm) = start_up(&fm);
+            let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx.append("savepointfile".to_string())?;
+            tx.pin(blk.clone())?;
+            tx.set_int(blk.clone(), 0, 1, true)?;
+            tx.set_string(blk.clone(), 40, "one".to_string(), true)?;
+            tx.savepoint("a".to_string());
+            tx.set_int(blk.clone(), 0, 2, true)?;
+            tx.savepoint("b".to_string());
+            tx.set_string(blk.clone(), 40, "three".to_string(), true)?;
+
+            assert!(tx.rollback_to_savepoint("b")?);
+            assert_eq!(tx.get_int(blk.clone(), 0)?, 2);
+            assert_eq!(tx.get_string(blk.clone(), 40)?, "one".to_string());
+            assert!(tx.rollback_to_savepoint("a")?);
+            assert_eq!(tx.get_int(blk.clone(), 0)?, 1);
+            // rolling back to a drops the later savepoints, a itself is kept
+            assert!(!tx.rollback_to_savepoint("b")?);
+            tx.set_int(blk.clone(), 0, 4, true)?;
+            assert!(tx.rollback_to_savepoint("a")?);
+            assert_eq!(tx.get_int(blk.clone(), 0)?, 1);
+            assert!(tx.release_savepoint("a"));
+            assert!(!tx.release_savepoint("a"));
+            tx.commit()?;
+
+            // a full rollback undoes the compensations as well
+            let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx.pin(blk.clone())?;
+            tx.set_int(blk.clone(), 0, 5, true)?;
+            tx.savepoint("c".to_string());
+            tx.set_int(blk.clone(), 0, 6, true)?;
+            assert!(tx.rollback_to_savepoint("c")?);
+            tx.roll_back()?;
+            assert_eq!(read_block(&mut fm, &blk), (1, "one".to_string()));
+
+            // crash with the committed pages unflushed
+            let mut tx = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx.pin(blk.clone())?;
+            tx.set_int(blk.clone(), 0, 7, true)?;
+            tx.savepoint("d".to_string());
+            tx.set_string(blk.clone(), 40, "seven".to_string(), true)?;
+            assert!(tx.rollback_to_savepoint("d")?);
+            tx.commit()?;
+        }
+
+        // the redo pass replays the compensations after the changes they undo
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let (log_mgr, bm) = start_up(&fm);
+        new_locking_tx(&fm, &log_mgr, &bm, &Arc::new(VersionMgr::new())).recovery()?;
+        assert_eq!(read_block(&mut fm, &blk), (7, "one".to_string()));
+
+        teardown(db_directory);
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_log_after_checkpoint() -> Result<()> {
+        let db_directory = "./db/truncatetest".to_string();
+        setup(db_directory.clone());
+        let blk = BlockId::new("truncatefile".to_string(), 0);
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let versions = Arc::new(VersionMgr::new());
+        let tx2_num = {
+            let (log_mgr, bm) = start_up(&fm);
+            log_mgr.lock().unwrap().set_segment_blocks(1);
+            log_mgr
+                .lock()
+                .unwrap()
+                .set_retention(LogRetention::Truncate);
+
+            // a running transaction keeps the segment of its START record
+            let mut tx1 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx1.append("truncatefile".to_string())?;
+            tx1.pin(blk.clone())?;
+            for i in 1..=50 {
+                tx1.set_int(blk.clone(), 0, i, true)?;
+            }
+            RecoveryMgr::checkpoint(&log_mgr, &bm, &versions)?;
+            let segments = log_mgr.lock().unwrap().segment_names();
+            assert!(segments.len() > 1);
+            assert_eq!(segments[0], "testlog.1");
+            tx1.commit()?;
+
+            let mut tx2 = new_locking_tx(&fm, &log_mgr, &bm, &versions);
+            tx2.pin(blk.clone())?;
+            tx2.set_int(blk.clone(), 0, 100, true)?;
+            tx2.set_string(blk.clone(), 40, "dirty".to_string(), true)?;
+            RecoveryMgr::checkpoint(&log_mgr, &bm, &versions)?;
+            // only the segment of the START record of tx2 is kept
+            let segments = log_mgr.lock().unwrap().segment_names();
+            assert!(segments.len() < 3);
+            assert_ne!(segments[0], "testlog.1");
+            assert!(fs::metadata(format!("{}/testlog.1", db_directory)).is_err());
+
+            // crash
+            assert_eq!(read_block(&mut fm, &blk), (100, "dirty".to_string()));
+            tx2.txnum
+        };
+
+        let mut fm = FileMgr::new(db_directory.clone(), 400);
+        let (log_mgr, bm) = start_up(&fm);
+        assert!(RecoveryMgr::last_tx_number(&mut log_mgr.lock().unwrap())? >= tx2_num);
+        new_locking_tx(&fm, &log_mgr, &bm, &Arc::new(VersionMgr::new())).recovery()?;
+        assert_eq!(read_block(&mut fm, &blk), (50, "".to_string()));
+        // the checkpoint written by recovery keeps the number of tx2 once its records are released
+        assert!(RecoveryMgr::last_tx_number(&mut log_mgr.lock().unwrap())? >= tx2_num);
+
+        teardown(db_directory);
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_tx_number() -> Result<()> {
+        let db_directory = "./db/lasttxnumtest".to_string();
+        setup(db_directory.clone());
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let (log_mgr, _) = start_up(&fm);
+        let mut lm = log_mgr.lock().unwrap();
+        assert_eq!(RecoveryMgr::last_tx_number(&mut lm)?, 0);
+
+        // the checkpoint stands for the records before it
+        StartRecord::write_to_log(&mut lm, 5);
+        CheckPointRecord::write_to_log(&mut lm, 9);
+        StartRecord::write_to_log(&mut lm, 8);
+        assert_eq!(RecoveryMgr::last_tx_number(&mut lm)?, 9);
+        StartRecord::write_to_log(&mut lm, 12);
+        assert_eq!(RecoveryMgr::last_tx_number(&mut lm)?, 12);
+
+        // a record of no known type is reported rather than read as something else
+        let mut p = Page::new_log(vec![0; integer::BYTES as usize]);
+        p.set_int(0, 99);
+        lm.append(p.contents().into_vec());
+        assert!(matches!(
+            RecoveryMgr::last_tx_number(&mut lm),
+            Err(DbError::Corruption(_))
+        ));
+
+        drop(lm);
+        teardown(db_directory);
+        Ok(())
+    }
+
+    fn start_up(fm: &FileMgr) -> (Arc<Mutex<LogMgr>>, Arc<Mutex<BufferMgr>>) {
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testlog".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 8)));
+        (log_mgr, bm)
+    }
+
+    fn new_locking_tx(
+        fm: &FileMgr,
+        log_mgr: &Arc<Mutex<LogMgr>>,
+        bm: &Arc<Mutex<BufferMgr>>,
+        versions: &Arc<VersionMgr>,
+    ) -> Transaction {
+        Transaction::new(
+            fm.clone(),
+            log_mgr.clone(),
+            bm.clone(),
+            Arc::new(LockTable::new()),
+            versions.clone(),
+            TxMode::Locking,
+        )
+    }
+
+    fn read_block(fm: &mut FileMgr, blk: &BlockId) -> (i32, String) {
+        let mut p = Page::new(fm.block_size());
+        fm.read(blk, &mut p).unwrap();
+        (p.get_int(0).unwrap(), p.get_string(40).unwrap())
+    }
+
+    /// each thread repeatedly increments the same integer in its own transaction
+    /// it reads under an slock and upgrades to an xlock to write, so two increments
+    /// can wait for each other until one of them is aborted, rolled back and retried
+    #[test]
+    fn test_concurrent_increments() -> Result<()> {
+        let db_directory = "./db/concurrenttest".to_string();
+        setup(db_directory.clone());
+
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testlog".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        for policy in [
+            DeadlockPolicy::Timeout,
+            DeadlockPolicy::Detect,
+            DeadlockPolicy::WaitDie,
+            DeadlockPolicy::WoundWait,
+        ] {
+            let locktbl = Arc::new(LockTable::with_policy(
+                policy,
+                std::time::Duration::from_millis(20),
+            ));
+            run_increments(&fm, &log_mgr, &bm, &locktbl);
+        }
+
+        teardown(db_directory);
+        Ok(())
+    }
+
+    fn run_increments(
+        fm: &FileMgr,
+        log_mgr: &Arc<Mutex<LogMgr>>,
+        bm: &Arc<Mutex<BufferMgr>>,
+        locktbl: &Arc<LockTable>,
+    ) {
+        let blk = BlockId::new("counter".to_string(), 0);
+        let versions = Arc::new(VersionMgr::new());
+        {
+            let mut tx = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
+            if tx.size("counter".to_string()).unwrap() == 0 {
+                tx.append("counter".to_string()).unwrap();
+            }
+            tx.pin(blk.clone()).unwrap();
+            tx.set_int(blk.clone(), 0, 0, false).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let num_threads = 4;
+        let increments = 10;
+        let mut handles = Vec::new();
+        for i in 0..num_threads {
+            let (fm, log_mgr, bm, locktbl, versions) = (
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+            );
+            let blk = blk.clone();
+            handles.push(std::thread::spawn(move || {
+                let mut seen = Vec::new();
+                // back off for a different time in each thread so that retries do not collide again
+                let backoff = std::time::Duration::from_millis(5 * i as u64);
+                while seen.len() < increments {
+                    let mut tx = Transaction::new(
+                        fm.clone(),
+                        log_mgr.clone(),
+                        bm.clone(),
+                        locktbl.clone(),
+                        versions.clone(),
+                        TxMode::Locking,
+                    );
+                    tx.pin(blk.clone()).unwrap();
+                    if tx.slock(blk.clone()).is_err() {
+                        tx.roll_back().unwrap();
+                        std::thread::sleep(backoff);
+                        continue;
+                    }
+                    let val = tx.get_int(blk.clone(), 0).unwrap();
+                    // give the other threads time to read the same value
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                    if tx.xlock(blk.clone()).is_err() {
+                        tx.roll_back().unwrap();
+                        std::thread::sleep(backoff);
+                        continue;
+                    }
+                    tx.set_int(blk.clone(), 0, val + 1, true).unwrap();
+                    tx.commit().unwrap();
+                    seen.push(val);
+                }
+                seen
+            }));
+        }
+        let mut seen = Vec::new();
+        for handle in handles {
+            seen.extend(handle.join().unwrap());
+        }
+
+        // the committed increments behave as if they had run one after another
+        seen.sort();
+        let total = (num_threads * increments) as i32;
+        assert_eq!(seen, (0..total).collect::<Vec<i32>>());
+        let mut tx = Transaction::new(
+            fm.clone(),
+            log_mgr.clone(),
+            bm.clone(),
+            locktbl.clone(),
+            versions.clone(),
+            TxMode::Locking,
+        );
+        tx.pin(blk.clone()).unwrap();
+        assert_eq!(tx.get_int(blk.clone(), 0).unwrap(), total);
+        tx.commit().unwrap();
+    }
+
+    /// which reads of one transaction wait for, or are waited for by, the writes of another
+    #[test]
+    fn test_isolation_levels() -> Result<()> {
+        let db_directory = "./db/isolationtest".to_string();
+        setup(db_directory.clone());
+
+        let fm = FileMgr::new(db_directory.clone(), 400);
+        let log_mgr = Arc::new(Mutex::new(
+            LogMgr::new(fm.clone(), "testlog".to_string()).unwrap(),
+        ));
+        let bm = Arc::new(Mutex::new(BufferMgr::new(fm.clone(), log_mgr.clone(), 10)));
+        let locktbl = Arc::new(LockTable::with_max_time(std::time::Duration::from_millis(
+            20,
+        )));
+        let versions = Arc::new(VersionMgr::new());
+        let new_tx = |level: IsolationLevel| {
+            let mut tx = Transaction::new(
+                fm.clone(),
+                log_mgr.clone(),
+                bm.clone(),
+                locktbl.clone(),
+                versions.clone(),
+                TxMode::Locking,
+            );
+            tx.set_isolation_level(level);
+            tx.pin(BlockId::new("isofile".to_string(), 0)).unwrap();
+            tx
+        };
+        let filename = "isofile".to_string();
+        let blk = BlockId::new(filename.clone(), 0);
+        let eof = BlockId::new(filename.clone(), END_OF_FILE);
+
+        let mut tx = new_tx(IsolationLevel::Serializable);
+        tx.append(filename.clone())?;
+        tx.set_int(blk.clone(), 0, 1, false)?;
+        tx.commit()?;
+
+        // read uncommitted sees a change that is not committed yet
+        let mut writer = new_tx(IsolationLevel::Serializable);
+        writer.set_int(blk.clone(), 0, 2, true)?;
+        let mut reader = new_tx(IsolationLevel::ReadUncommitted);
+        assert_eq!(reader.isolation_level(), IsolationLevel::ReadUncommitted);
+        assert_eq!(reader.get_int(blk.clone(), 0)?, 2);
+        reader.commit()?;
+
+        // read committed waits for the writer
+        let mut reader = new_tx(IsolationLevel::ReadCommitted);
+        assert!(reader.slock(blk.clone()).is_err());
+        reader.roll_back()?;
+        writer.commit()?;
+
+        // but does not make the next writer wait, so reading again may see another value
+        let mut reader = new_tx(IsolationLevel::ReadCommitted);
+        assert_eq!(reader.get_int(blk.clone(), 0)?, 2);
+        let mut writer = new_tx(IsolationLevel::Serializable);
+        assert!(writer.xlock(blk.clone()).is_ok());
+        writer.set_int(blk.clone(), 0, 3, true)?;
+        writer.commit()?;
+        assert_eq!(reader.get_int(blk.clone(), 0)?, 3);
+        reader.commit()?;
+
+        // repeatable read holds its slocks, but not the end of file
+        let mut reader = new_tx(IsolationLevel::RepeatableRead);
+        assert_eq!(reader.get_int(blk.clone(), 0)?, 3);
+        assert_eq!(reader.size(filename.clone())?, 1);
+        let mut writer = new_tx(IsolationLevel::Serializable);
+        assert!(writer.xlock(blk.clone()).is_err());
+        writer.roll_back()?;
+        let mut writer = new_tx(IsolationLevel::Serializable);
+        writer.append(filename.clone())?;
+        writer.commit()?;
+        assert_eq!(reader.size(filename.clone())?, 2);
+        reader.commit()?;
+
+        // serializable locks the end of file too, so no phantom block can be appended
+        let mut reader = new_tx(IsolationLevel::Serializable);
+        assert_eq!(reader.size(filename.clone())?, 2);
+        let mut writer = new_tx(IsolationLevel::Serializable);
+        assert!(writer.xlock(eof.clone()).is_err());
+        writer.roll_back()?;
+        reader.commit()?;
+
+        teardown(db_directory);
+        Ok(())
+    }
 }
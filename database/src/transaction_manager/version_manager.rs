@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// how a transaction isolates its reads from the other transactions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxMode {
+    /// reads take slocks and see the latest committed version of each record
+    Locking,
+    /// reads take no locks and see the versions committed before the transaction started
+    /// a write fails if a transaction committed a newer version of the record first
+    Snapshot,
+}
+
+struct VersionState {
+    /// the running transactions, with the snapshot of the snapshot transactions
+    active: HashMap<i32, Option<u64>>,
+    /// the commit sequence number of the transactions committed after the oldest snapshot
+    /// a committed transaction missing here is seen by every snapshot
+    committed: HashMap<i32, u64>,
+    last_commit: u64,
+    /// the running locking transactions that write records in place, without keeping versions
+    unversioned: HashSet<i32>,
+    /// the snapshot transactions begun while unversioned writers were running,
+    /// whose snapshot is taken again once the writers are done
+    waiting: HashSet<i32>,
+}
+
+impl VersionState {
+    /// every snapshot, present or future, sees the transactions committed up to the horizon
+    fn horizon(&self) -> u64 {
+        self.active
+            .values()
+            .flatten()
+            .copied()
+            .min()
+            .unwrap_or(self.last_commit)
+    }
+
+    /// forget the commit order of the transactions every snapshot sees
+    fn prune(&mut self) {
+        let horizon = self.horizon();
+        self.committed.retain(|_, seq| *seq > horizon);
+    }
+
+    /// the transaction is done, so the snapshots waiting for it may be taken
+    fn end(&mut self, txnum: i32) {
+        self.active.remove(&txnum);
+        self.unversioned.remove(&txnum);
+        self.waiting.remove(&txnum);
+    }
+}
+
+/// the commit order of the transactions, shared by all transactions of the database
+/// a snapshot is the commit sequence number of the last transaction committed when it was taken,
+/// and sees exactly the transactions committed up to that number
+/// while no snapshot transaction runs, the locking transactions keep no versions;
+/// a snapshot begun meanwhile waits for them before it reads anything
+pub struct VersionMgr {
+    state: Mutex<VersionState>,
+    finished: Condvar,
+}
+
+impl VersionMgr {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(VersionState {
+                active: HashMap::new(),
+                committed: HashMap::new(),
+                last_commit: 0,
+                unversioned: HashSet::new(),
+                waiting: HashSet::new(),
+            }),
+            finished: Condvar::new(),
+        }
+    }
+
+    /// register a new transaction
+    /// @return: the snapshot of a snapshot transaction, None for a locking one
+    pub fn begin(&self, txnum: i32, mode: TxMode) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        let snapshot = match mode {
+            TxMode::Locking => None,
+            TxMode::Snapshot => Some(state.last_commit),
+        };
+        state.active.insert(txnum, snapshot);
+        if snapshot.is_some() && !state.unversioned.is_empty() {
+            state.waiting.insert(txnum);
+        }
+        return snapshot;
+    }
+
+    /// whether the transaction may overwrite and free records in place, without keeping versions
+    /// a locking transaction may while no snapshot transaction runs,
+    /// and then keeps doing so until it ends, since the snapshots begun later wait for it
+    pub fn skips_versions(&self, txnum: i32) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.unversioned.contains(&txnum) {
+            return true;
+        }
+        if state.active.get(&txnum) != Some(&None) || state.active.values().any(|s| s.is_some()) {
+            return false;
+        }
+        state.unversioned.insert(txnum);
+        return true;
+    }
+
+    /// wait until the unversioned writers running when the snapshot transaction began are done,
+    /// then take its snapshot again, which is fine since it has read nothing yet
+    /// @return: the snapshot of the transaction, or None if the writers are not done within max_time
+    pub fn wait_for_snapshot(&self, txnum: i32, max_time: Duration) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        let deadline = Instant::now() + max_time;
+        while state.waiting.contains(&txnum) {
+            if state.unversioned.is_empty() {
+                let snapshot = state.last_commit;
+                state.active.insert(txnum, Some(snapshot));
+                state.waiting.remove(&txnum);
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            state = self.finished.wait_timeout(state, deadline - now).unwrap().0;
+        }
+        return state.active.get(&txnum).copied().flatten();
+    }
+
+    /// the changes of the transaction become visible to the snapshots taken from now on
+    pub fn commit(&self, txnum: i32) {
+        let mut state = self.state.lock().unwrap();
+        state.end(txnum);
+        state.last_commit += 1;
+        let seq = state.last_commit;
+        state.committed.insert(txnum, seq);
+        state.prune();
+        self.finished.notify_all();
+    }
+
+    /// the changes of a rolled back transaction have been undone, so there is nothing to see
+    pub fn abort(&self, txnum: i32) {
+        let mut state = self.state.lock().unwrap();
+        state.end(txnum);
+        state.prune();
+        self.finished.notify_all();
+    }
+
+    /// the running transactions, in the order they started
+    pub fn active_txs(&self) -> Vec<i32> {
+        let state = self.state.lock().unwrap();
+        let mut txnums: Vec<i32> = state.active.keys().copied().collect();
+        txnums.sort();
+        return txnums;
+    }
+
+    /// whether the snapshot sees the changes of the transaction
+    pub fn is_visible(&self, txnum: i32, snapshot: u64) -> bool {
+        let state = self.state.lock().unwrap();
+        if state.active.contains_key(&txnum) {
+            return false;
+        }
+        return state
+            .committed
+            .get(&txnum)
+            .is_none_or(|seq| *seq <= snapshot);
+    }
+
+    /// whether every snapshot, present or future, sees the changes of the transaction
+    /// the versions these changes replaced can then be reclaimed
+    pub fn is_settled(&self, txnum: i32) -> bool {
+        let state = self.state.lock().unwrap();
+        if state.active.contains_key(&txnum) {
+            return false;
+        }
+        let horizon = state.horizon();
+        return state
+            .committed
+            .get(&txnum)
+            .is_none_or(|seq| *seq <= horizon);
+    }
+}
+
+impl Default for VersionMgr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_version_mgr() -> Result<()> {
+        let vm = VersionMgr::new();
+        assert_eq!(vm.begin(1, TxMode::Locking), None);
+        let s2 = vm.begin(2, TxMode::Snapshot).unwrap();
+
+        // running transactions are seen by nobody
+        assert!(!vm.is_visible(1, s2));
+        assert!(!vm.is_settled(1));
+        assert_eq!(vm.active_txs(), vec![1, 2]);
+
+        // a transaction committed after the snapshot was taken is not seen by it
+        vm.commit(1);
+        assert!(!vm.is_visible(1, s2));
+        assert!(!vm.is_settled(1));
+        let s3 = vm.begin(3, TxMode::Snapshot).unwrap();
+        assert!(vm.is_visible(1, s3));
+
+        // the changes are settled once the older snapshot goes away
+        vm.commit(2);
+        assert!(vm.is_settled(1));
+        assert!(!vm.is_settled(2));
+
+        // a rolled back transaction does not hold the horizon back
+        vm.begin(4, TxMode::Snapshot);
+        vm.commit(3);
+        vm.abort(4);
+        assert!(vm.is_settled(2));
+
+        // transactions unknown to the manager committed long ago
+        assert!(vm.is_visible(100, s2));
+        assert!(vm.state.lock().unwrap().committed.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unversioned_writers() -> Result<()> {
+        let vm = VersionMgr::new();
+        let max_time = Duration::from_millis(50);
+
+        // without snapshots a locking writer keeps no versions, until it ends
+        vm.begin(1, TxMode::Locking);
+        assert!(vm.skips_versions(1));
+        vm.begin(2, TxMode::Snapshot);
+        assert!(vm.skips_versions(1));
+        // the other writers keep versions for the new snapshot
+        vm.begin(3, TxMode::Locking);
+        assert!(!vm.skips_versions(3));
+        // and so do the snapshot transactions
+        assert!(!vm.skips_versions(2));
+
+        // the snapshot waits for the unversioned writer, then sees its changes
+        assert_eq!(vm.wait_for_snapshot(2, max_time), None);
+        vm.commit(1);
+        let s2 = vm.wait_for_snapshot(2, max_time).unwrap();
+        assert!(vm.is_visible(1, s2));
+        assert_eq!(vm.wait_for_snapshot(2, max_time), Some(s2));
+
+        // a snapshot begun without unversioned writers does not wait
+        vm.commit(2);
+        vm.commit(3);
+        let s4 = vm.begin(4, TxMode::Snapshot).unwrap();
+        assert_eq!(vm.wait_for_snapshot(4, Duration::ZERO), Some(s4));
+        vm.abort(4);
+
+        // a writer waking up the waiting snapshot from another thread
+        vm.begin(5, TxMode::Locking);
+        assert!(vm.skips_versions(5));
+        vm.begin(6, TxMode::Snapshot);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                vm.abort(5);
+            });
+            assert!(vm.wait_for_snapshot(6, Duration::from_secs(10)).is_some());
+        });
+        Ok(())
+    }
+}